@@ -23,6 +23,7 @@ use serde::{Deserialize, Deserializer};
 
 use alacritty_terminal::config::LOG_TARGET_CONFIG;
 use alacritty_terminal::term::TermMode;
+use alacritty_terminal::vi_mode::ViMotion;
 
 /// Describes a state and action to take in that state
 ///
@@ -157,6 +158,15 @@ pub enum Action {
     /// Scroll all the way to the bottom.
     ScrollToBottom,
 
+    /// Scroll the previous shell prompt to the top of the screen.
+    ScrollToPreviousPrompt,
+
+    /// Scroll the next shell prompt to the top of the screen.
+    ScrollToNextPrompt,
+
+    /// Select the output of the last finished shell command.
+    SelectLastCommandOutput,
+
     /// Clear the display buffer(s) to remove history.
     ClearHistory,
 
@@ -186,10 +196,49 @@ pub enum Action {
     /// Allow receiving char input.
     ReceiveChar,
 
+    /// Toggle vi mode.
+    ToggleViMode,
+
+    /// Clear active selection.
+    ClearSelection,
+
+    /// Start a regex search towards the bottom of the scrollback history.
+    SearchForward,
+
+    /// Start a regex search towards the top of the scrollback history.
+    SearchBackward,
+
+    /// Jump to the next regex search match.
+    SearchNext,
+
+    /// Jump to the previous regex search match.
+    SearchPrevious,
+
+    /// Move the vi mode cursor.
+    #[serde(skip)]
+    ViMotion(ViMotion),
+
+    /// Perform vi mode action.
+    #[serde(skip)]
+    ViAction(ViAction),
+
     /// No action.
     None,
 }
 
+/// Vi mode specific actions.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ViAction {
+    /// Toggle normal vi selection.
+    ToggleNormalSelection,
+
+    /// Toggle line vi selection.
+    ToggleLineSelection,
+
+    /// Toggle block vi selection.
+    ToggleBlockSelection,
+}
+
 impl Default for Action {
     fn default() -> Action {
         Action::None
@@ -202,6 +251,18 @@ impl From<&'static str> for Action {
     }
 }
 
+impl From<ViMotion> for Action {
+    fn from(motion: ViMotion) -> Action {
+        Action::ViMotion(motion)
+    }
+}
+
+impl From<ViAction> for Action {
+    fn from(action: ViAction) -> Action {
+        Action::ViAction(action)
+    }
+}
+
 pub trait RelaxedEq<T: ?Sized = Self> {
     fn relaxed_eq(&self, other: T) -> bool;
 }
@@ -316,6 +377,9 @@ pub fn default_key_bindings() -> Vec<KeyBinding> {
         Key::F19; Action::Esc("\x1b[33~".into());
         Key::F20; Action::Esc("\x1b[34~".into());
         Key::NumpadEnter; Action::Esc("\n".into());
+        Key::Space, [shift: true, ctrl: true]; Action::ToggleViMode;
+        Key::F, [shift: true, ctrl: true]; Action::SearchForward;
+        Key::B, [shift: true, ctrl: true]; Action::SearchBackward;
     );
 
     //   Code     Modifiers
@@ -416,6 +480,45 @@ pub fn default_key_bindings() -> Vec<KeyBinding> {
     bindings
 }
 
+pub fn default_vi_key_bindings() -> Vec<KeyBinding> {
+    bindings!(
+        KeyBinding;
+        Key::I; Action::ToggleViMode;
+        Key::Space, [shift: true, ctrl: true]; Action::ToggleViMode;
+        Key::Escape; Action::ClearSelection;
+        Key::Y; Action::Copy;
+        Key::Y; Action::ClearSelection;
+        Key::V; ViAction::ToggleNormalSelection.into();
+        Key::V, [shift: true]; ViAction::ToggleLineSelection.into();
+        Key::V, [ctrl: true]; ViAction::ToggleBlockSelection.into();
+        Key::B, [ctrl: true]; Action::ScrollPageUp;
+        Key::F, [ctrl: true]; Action::ScrollPageDown;
+        Key::K; ViMotion::Up.into();
+        Key::J; ViMotion::Down.into();
+        Key::H; ViMotion::Left.into();
+        Key::L; ViMotion::Right.into();
+        Key::Up; ViMotion::Up.into();
+        Key::Down; ViMotion::Down.into();
+        Key::Left; ViMotion::Left.into();
+        Key::Right; ViMotion::Right.into();
+        Key::Key0; ViMotion::First.into();
+        Key::Key4, [shift: true]; ViMotion::Last.into();
+        Key::G; ViMotion::Top.into();
+        Key::G, [shift: true]; ViMotion::Bottom.into();
+        Key::H, [shift: true]; ViMotion::High.into();
+        Key::M, [shift: true]; ViMotion::Middle.into();
+        Key::L, [shift: true]; ViMotion::Low.into();
+        Key::B; ViMotion::SemanticLeft.into();
+        Key::W; ViMotion::SemanticRight.into();
+        Key::E; ViMotion::SemanticRightEnd.into();
+        Key::Key5, [shift: true]; ViMotion::Bracket.into();
+        Key::Slash; Action::SearchForward;
+        Key::Slash, [shift: true]; Action::SearchBackward;
+        Key::N; Action::SearchNext;
+        Key::N, [shift: true]; Action::SearchPrevious;
+    )
+}
+
 #[cfg(not(any(target_os = "macos", test)))]
 fn common_keybindings() -> Vec<KeyBinding> {
     bindings!(
@@ -1057,7 +1160,16 @@ impl<'a> Deserialize<'a> for RawBinding {
                                 return Err(<V::Error as Error>::duplicate_field("action"));
                             }
 
-                            action = Some(map.next_value::<Action>()?);
+                            let val = map.next_value::<serde_yaml::Value>()?;
+                            action = Some(match Action::deserialize(val.clone()) {
+                                Ok(action) => action,
+                                Err(err) => match ViMotion::deserialize(val.clone()) {
+                                    Ok(motion) => motion.into(),
+                                    Err(_) => ViAction::deserialize(val)
+                                        .map(Action::from)
+                                        .map_err(|_| V::Error::custom(err))?,
+                                },
+                            });
                         },
                         Field::Chars => {
                             if chars.is_some() {
@@ -1221,7 +1333,9 @@ mod test {
     use glutin::event::ModifiersState;
 
     use alacritty_terminal::term::TermMode;
+    use alacritty_terminal::vi_mode::ViMotion;
 
+    use crate::config::bindings::{KeyBinding, ViAction};
     use crate::config::{Action, Binding};
 
     type MockBinding = Binding<usize>;
@@ -1406,4 +1520,23 @@ mod test {
         assert!(!binding.is_triggered_by(TermMode::ALT_SCREEN, mods, &t, true));
         assert!(!binding.is_triggered_by(TermMode::ALT_SCREEN | TermMode::INSERT, mods, &t, true));
     }
+
+    #[test]
+    fn deserialize_vi_actions() {
+        let bindings: Vec<KeyBinding> = serde_yaml::from_str(
+            "
+            - { key: K, action: Up }
+            - { key: V, mods: Shift, action: ToggleLineSelection }
+            - { key: I, action: ToggleViMode }
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(bindings[0].action, Action::ViMotion(ViMotion::Up));
+        assert_eq!(bindings[1].action, Action::ViAction(ViAction::ToggleLineSelection));
+        assert_eq!(bindings[2].action, Action::ToggleViMode);
+
+        let invalid = serde_yaml::from_str::<Vec<KeyBinding>>("- { key: K, action: Invalid }");
+        assert!(invalid.is_err());
+    }
 }
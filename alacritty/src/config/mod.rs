@@ -14,9 +14,10 @@ use alacritty_terminal::config::{Config as TermConfig, LOG_TARGET_CONFIG};
 mod bindings;
 pub mod monitor;
 mod mouse;
+mod notifications;
 mod ui_config;
 
-pub use crate::config::bindings::{Action, Binding, Key, RelaxedEq};
+pub use crate::config::bindings::{Action, Binding, Key, RelaxedEq, ViAction};
 #[cfg(test)]
 pub use crate::config::mouse::{ClickHandler, Mouse};
 use crate::config::ui_config::UIConfig;
@@ -0,0 +1,60 @@
+use log::error;
+use serde::{Deserialize, Deserializer};
+
+use alacritty_terminal::config::{failure_default, LOG_TARGET_CONFIG};
+
+use crate::config::bindings::CommandWrapper;
+
+#[serde(default)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Notifications {
+    // Program for displaying desktop notifications
+    #[serde(deserialize_with = "deserialize_command")]
+    pub command: Option<CommandWrapper>,
+
+    // Only display notifications while the window is unfocused
+    #[serde(deserialize_with = "failure_default")]
+    pub unfocused_only: bool,
+}
+
+impl Default for Notifications {
+    fn default() -> Notifications {
+        Notifications {
+            #[cfg(not(any(target_os = "macos", windows)))]
+            command: Some(CommandWrapper::Just(String::from("notify-send"))),
+            #[cfg(any(target_os = "macos", windows))]
+            command: None,
+            unfocused_only: true,
+        }
+    }
+}
+
+fn deserialize_command<'a, D>(
+    deserializer: D,
+) -> ::std::result::Result<Option<CommandWrapper>, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    let default = Notifications::default().command;
+
+    // Deserialize to generic value
+    let val = serde_yaml::Value::deserialize(deserializer)?;
+
+    // Accept `None` to disable notifications
+    if val.as_str().filter(|v| v.to_lowercase() == "none").is_some() {
+        return Ok(None);
+    }
+
+    match <Option<CommandWrapper>>::deserialize(val) {
+        Ok(command) => Ok(command),
+        Err(err) => {
+            error!(
+                target: LOG_TARGET_CONFIG,
+                "Problem with config: {}; using {}",
+                err,
+                default.as_ref().map(CommandWrapper::program).unwrap_or("None")
+            );
+            Ok(default)
+        },
+    }
+}
@@ -4,16 +4,24 @@ use alacritty_terminal::config::failure_default;
 
 use crate::config::bindings::{self, Binding, KeyBinding, MouseBinding};
 use crate::config::mouse::Mouse;
+use crate::config::notifications::Notifications;
 
 #[derive(Debug, PartialEq, Deserialize)]
 pub struct UIConfig {
     #[serde(default, deserialize_with = "failure_default")]
     pub mouse: Mouse,
 
+    #[serde(default, deserialize_with = "failure_default")]
+    pub notifications: Notifications,
+
     /// Keybindings
     #[serde(default = "default_key_bindings", deserialize_with = "deserialize_key_bindings")]
     pub key_bindings: Vec<KeyBinding>,
 
+    /// Keybindings active in vi mode
+    #[serde(default = "default_vi_key_bindings", deserialize_with = "deserialize_vi_key_bindings")]
+    pub vi_key_bindings: Vec<KeyBinding>,
+
     /// Bindings for the mouse
     #[serde(default = "default_mouse_bindings", deserialize_with = "deserialize_mouse_bindings")]
     pub mouse_bindings: Vec<MouseBinding>,
@@ -23,7 +31,9 @@ impl Default for UIConfig {
     fn default() -> Self {
         UIConfig {
             mouse: Mouse::default(),
+            notifications: Notifications::default(),
             key_bindings: default_key_bindings(),
+            vi_key_bindings: default_vi_key_bindings(),
             mouse_bindings: default_mouse_bindings(),
         }
     }
@@ -33,6 +43,10 @@ fn default_key_bindings() -> Vec<KeyBinding> {
     bindings::default_key_bindings()
 }
 
+fn default_vi_key_bindings() -> Vec<KeyBinding> {
+    bindings::default_vi_key_bindings()
+}
+
 fn default_mouse_bindings() -> Vec<MouseBinding> {
     bindings::default_mouse_bindings()
 }
@@ -44,6 +58,13 @@ where
     deserialize_bindings(deserializer, bindings::default_key_bindings())
 }
 
+fn deserialize_vi_key_bindings<'a, D>(deserializer: D) -> Result<Vec<KeyBinding>, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    deserialize_bindings(deserializer, bindings::default_vi_key_bindings())
+}
+
 fn deserialize_mouse_bindings<'a, D>(deserializer: D) -> Result<Vec<MouseBinding>, D::Error>
 where
     D: Deserializer<'a>,
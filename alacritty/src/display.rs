@@ -35,11 +35,13 @@ use alacritty_terminal::meter::Meter;
 use alacritty_terminal::renderer::rects::{RenderLines, RenderRect};
 use alacritty_terminal::renderer::{self, GlyphCache, QuadRenderer};
 use alacritty_terminal::selection::Selection;
+use alacritty_terminal::term::cell::Flags;
 use alacritty_terminal::term::color::Rgb;
+use alacritty_terminal::term::search::Direction;
 use alacritty_terminal::term::{RenderableCell, SizeInfo, Term, TermMode};
 
 use crate::config::Config;
-use crate::event::{DisplayUpdate, Mouse};
+use crate::event::{DisplayUpdate, Mouse, SearchState};
 use crate::url::{Url, Urls};
 use crate::window::{self, Window};
 
@@ -122,6 +124,9 @@ pub struct Display {
     /// Currently highlighted URL.
     pub highlighted_url: Option<Url>,
 
+    /// Blinking text was visible in the last frame.
+    pub blinking_text: bool,
+
     renderer: QuadRenderer,
     glyph_cache: GlyphCache,
     meter: Meter,
@@ -240,6 +245,7 @@ impl Display {
             size_info,
             urls: Urls::new(),
             highlighted_url: None,
+            blinking_text: false,
         })
     }
 
@@ -295,6 +301,7 @@ impl Display {
         terminal: &mut Term<T>,
         pty_resize_handle: &mut dyn OnResize,
         message_buffer: &MessageBuffer,
+        search_active: bool,
         config: &Config,
         update_pending: DisplayUpdate,
     ) {
@@ -334,6 +341,11 @@ impl Display {
             pty_size.height -= pty_size.cell_height * lines as f32;
         }
 
+        // Subtract the search bar line from pty size
+        if search_active {
+            pty_size.height -= pty_size.cell_height;
+        }
+
         // Resize PTY
         pty_resize_handle.on_resize(&pty_size);
 
@@ -354,12 +366,14 @@ impl Display {
     /// This call may block if vsync is enabled
     pub fn draw<T>(
         &mut self,
-        terminal: MutexGuard<'_, Term<T>>,
+        mut terminal: MutexGuard<'_, Term<T>>,
         message_buffer: &MessageBuffer,
         config: &Config,
         mouse: &Mouse,
         mods: ModifiersState,
+        search_state: &SearchState,
     ) {
+        terminal.update_search_matches();
         let grid_cells: Vec<RenderableCell> = terminal.renderable_cells(config).collect();
         let visual_bell_intensity = terminal.visual_bell.intensity();
         let background_color = terminal.background_color();
@@ -369,6 +383,7 @@ impl Display {
 
         let selection = !terminal.selection().as_ref().map(Selection::is_empty).unwrap_or(true);
         let mouse_mode = terminal.mode().intersects(TermMode::MOUSE_MODE);
+        let graphic_updates = terminal.graphics_mut().take_updates();
 
         // Update IME position
         #[cfg(not(windows))]
@@ -381,8 +396,12 @@ impl Display {
             api.clear(background_color);
         });
 
+        self.renderer.update_graphics(graphic_updates);
+
         let mut lines = RenderLines::new();
         let mut urls = Urls::new();
+        let mut graphic_cells = Vec::new();
+        let mut blinking_text = false;
 
         // Draw grid
         {
@@ -390,19 +409,31 @@ impl Display {
 
             self.renderer.with_api(&config, &size_info, |mut api| {
                 // Iterate over all non-empty cells in the grid
-                for cell in grid_cells {
+                for cell in &grid_cells {
                     // Update URL underlines
                     urls.update(size_info.cols().0, cell);
 
                     // Update underline/strikeout
                     lines.update(cell);
 
-                    // Draw the cell
-                    api.render_cell(cell, glyph_cache);
+                    // Collect the cells covered by graphics
+                    if cell.graphic.is_some() {
+                        graphic_cells.push(cell.clone());
+                    }
+
+                    blinking_text |= cell.flags.contains(Flags::BLINK);
                 }
+
+                // Draw the cells, combining runs of them into ligatures
+                api.render_cells(grid_cells, glyph_cache);
             });
         }
 
+        self.blinking_text = blinking_text;
+
+        // Draw graphics on top of the cell backgrounds
+        self.renderer.draw_graphics(&size_info, &graphic_cells);
+
         let mut rects = lines.rects(&metrics, &size_info);
 
         // Update visible URLs
@@ -436,6 +467,29 @@ impl Display {
             rects.push(visual_bell_rect);
         }
 
+        // Push search bar right above the message bar
+        let message_lines = message_buffer.message().map(|m| m.text(&size_info).len());
+        let search_line = Line(size_info.lines().saturating_sub(message_lines.unwrap_or(0) + 1));
+        let search_text = search_state.input.as_ref().map(|input| {
+            let prefix = match search_state.direction {
+                Direction::Forward => '/',
+                Direction::Backward => '?',
+            };
+            search_bar_text(prefix, input, size_info.cols().0)
+        });
+        if search_text.is_some() {
+            let y = size_info.cell_height.mul_add(search_line.0 as f32, size_info.padding_y);
+            let search_bar_rect = RenderRect::new(
+                0.,
+                y,
+                size_info.width,
+                size_info.cell_height,
+                config.colors.search.bar,
+                1.,
+            );
+            rects.push(search_bar_rect);
+        }
+
         if let Some(message) = message_buffer.message() {
             let text = message.text(&size_info);
 
@@ -469,6 +523,13 @@ impl Display {
             self.renderer.draw_rects(&size_info, rects);
         }
 
+        // Draw search input
+        if let Some(text) = search_text {
+            self.renderer.with_api(&config, &size_info, |mut api| {
+                api.render_string(&text, search_line, glyph_cache, None);
+            });
+        }
+
         // Draw render timer
         if config.render_timer() {
             let timing = format!("{:.3} usec", self.meter.average());
@@ -482,6 +543,13 @@ impl Display {
     }
 }
 
+/// Text of the search bar, truncated at the start to fit into the available columns
+fn search_bar_text(prefix: char, input: &str, cols: usize) -> String {
+    let text = format!("{}{}", prefix, input);
+    let len = text.chars().count();
+    text.chars().skip(len.saturating_sub(cols)).collect()
+}
+
 /// Calculate padding to spread it evenly around the terminal content
 #[inline]
 fn dynamic_padding(padding: f32, dimension: f32, cell_dimension: f32) -> f32 {
@@ -1,13 +1,14 @@
 //! Process window events
 use std::borrow::Cow;
-use std::cmp::max;
+use std::cmp::{max, min};
 use std::env;
 #[cfg(unix)]
 use std::fs;
 use std::fs::File;
 use std::io::Write;
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use glutin::dpi::PhysicalSize;
 use glutin::event::{ElementState, Event as GlutinEvent, ModifiersState, MouseButton};
@@ -18,9 +19,10 @@ use serde_json as json;
 
 use font::Size;
 
+use alacritty_terminal::ansi::{Notification, WindowOperation, WorkingDirectory};
 use alacritty_terminal::clipboard::ClipboardType;
 use alacritty_terminal::config::Font;
-use alacritty_terminal::config::LOG_TARGET_CONFIG;
+use alacritty_terminal::config::{DEFAULT_NAME, LOG_TARGET_CONFIG};
 use alacritty_terminal::event::OnResize;
 use alacritty_terminal::event::{Event, EventListener, Notify};
 use alacritty_terminal::grid::Scroll;
@@ -29,8 +31,8 @@ use alacritty_terminal::message_bar::{Message, MessageBuffer};
 use alacritty_terminal::selection::Selection;
 use alacritty_terminal::sync::FairMutex;
 use alacritty_terminal::term::cell::Cell;
-use alacritty_terminal::term::{SizeInfo, Term};
-#[cfg(not(windows))]
+use alacritty_terminal::term::search::{Direction, Match};
+use alacritty_terminal::term::{SizeInfo, Term, TermMode};
 use alacritty_terminal::tty;
 use alacritty_terminal::util::{limit, start_daemon};
 
@@ -38,18 +40,29 @@ use crate::config;
 use crate::config::Config;
 use crate::display::Display;
 use crate::input::{self, ActionContext as _, FONT_SIZE_STEP};
+use crate::keyboard::TextKeys;
 use crate::window::Window;
 
+/// Minimum time between two desktop notifications
+const NOTIFICATION_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Minimum time between two updates of a title which shows the state of the child process
+const CHILD_TITLE_INTERVAL: Duration = Duration::from_millis(500);
+
 #[derive(Default, Clone, Debug, PartialEq)]
 pub struct DisplayUpdate {
     pub dimensions: Option<PhysicalSize>,
     pub message_buffer: Option<()>,
+    pub search_bar: Option<()>,
     pub font: Option<Font>,
 }
 
 impl DisplayUpdate {
     fn is_empty(&self) -> bool {
-        self.dimensions.is_none() && self.font.is_none() && self.message_buffer.is_none()
+        self.dimensions.is_none()
+            && self.font.is_none()
+            && self.message_buffer.is_none()
+            && self.search_bar.is_none()
     }
 }
 
@@ -60,11 +73,16 @@ pub struct ActionContext<'a, N, T> {
     pub mouse: &'a mut Mouse,
     pub received_count: &'a mut usize,
     pub suppress_chars: &'a mut bool,
+    pub text_keys: &'a mut TextKeys,
     pub modifiers: &'a mut ModifiersState,
     pub window: &'a mut Window,
     pub message_buffer: &'a mut MessageBuffer,
     pub display_update_pending: &'a mut DisplayUpdate,
     pub config: &'a mut Config,
+    pub search_state: &'a mut SearchState,
+    pub last_notification: &'a mut Option<Instant>,
+    pub blink_deadline: &'a mut Option<Instant>,
+    pub title_deadline: &'a mut Option<Instant>,
     font_size: &'a mut Size,
 }
 
@@ -83,7 +101,7 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         if let ElementState::Pressed = self.mouse().left_button_state {
             let (x, y) = (self.mouse().x, self.mouse().y);
             let size_info = self.size_info();
-            let point = size_info.pixels_to_coords(x, y);
+            let point = self.terminal.screen_to_cell(size_info.pixels_to_coords(x, y));
             let cell_side = self.mouse().cell_side;
             self.update_selection(Point { line: point.line, col: point.col }, cell_side);
         }
@@ -146,7 +164,7 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         let y = self.mouse.y as usize;
 
         if self.size_info.contains_point(x, y) {
-            Some(self.size_info.pixels_to_coords(x, y))
+            Some(self.terminal.screen_to_cell(self.size_info.pixels_to_coords(x, y)))
         } else {
             None
         }
@@ -172,6 +190,11 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         &mut self.suppress_chars
     }
 
+    #[inline]
+    fn text_keys(&mut self) -> &mut TextKeys {
+        &mut self.text_keys
+    }
+
     #[inline]
     fn modifiers(&mut self) -> &mut ModifiersState {
         &mut self.modifiers
@@ -200,21 +223,19 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
     fn spawn_new_instance(&mut self) {
         let alacritty = env::args().next().unwrap();
 
-        #[cfg(unix)]
-        let args = {
-            #[cfg(not(target_os = "freebsd"))]
-            let proc_prefix = "";
-            #[cfg(target_os = "freebsd")]
-            let proc_prefix = "/compat/linux";
-            let link_path = format!("{}/proc/{}/cwd", proc_prefix, tty::child_pid());
-            if let Ok(path) = fs::read_link(link_path) {
-                vec!["--working-directory".into(), path]
-            } else {
-                Vec::new()
-            }
+        // Prefer the directory reported by the shell, since the child process is not necessarily
+        // the one running in the foreground
+        let working_directory = self
+            .terminal
+            .working_directory()
+            .filter(|directory| directory.host.is_none())
+            .map(|directory| directory.path.clone())
+            .or_else(child_working_directory);
+
+        let args: Vec<PathBuf> = match working_directory {
+            Some(path) => vec!["--working-directory".into(), path],
+            None => Vec::new(),
         };
-        #[cfg(not(unix))]
-        let args: Vec<String> = Vec::new();
 
         match start_daemon(&alacritty, &args) {
             Ok(_) => debug!("Started new Alacritty process: {} {:?}", alacritty, args),
@@ -247,6 +268,183 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
     fn config(&self) -> &Config {
         self.config
     }
+
+    #[inline]
+    fn search_state(&mut self) -> &mut SearchState {
+        self.search_state
+    }
+
+    fn toggle_search_bar(&mut self) {
+        self.display_update_pending.search_bar = Some(());
+    }
+}
+
+impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
+    /// Show blinking content and restart the blink interval
+    fn reset_blink(&mut self) {
+        if self.terminal.blink_hidden {
+            self.terminal.blink_hidden = false;
+            self.terminal.dirty = true;
+        }
+        *self.blink_deadline = None;
+    }
+
+    /// Pass a desktop notification to the notification command
+    fn show_notification(&mut self, notification: Notification) {
+        let config = &self.config.ui_config.notifications;
+        let command = match config.command {
+            Some(ref command) => command,
+            None => return,
+        };
+
+        if config.unfocused_only && self.terminal.is_focused {
+            return;
+        }
+
+        // Drop notifications which arrive too quickly after each other
+        let now = Instant::now();
+        if self.last_notification.map_or(false, |last| now - last < NOTIFICATION_INTERVAL) {
+            debug!("Dropping notification {:?}", notification);
+            return;
+        }
+        *self.last_notification = Some(now);
+
+        let mut args = command.args().to_vec();
+        args.push(notification.title.unwrap_or_else(|| String::from(DEFAULT_NAME)));
+        args.push(notification.body);
+
+        match start_daemon(command.program(), &args) {
+            Ok(_) => debug!("Launched {} with args {:?}", command.program(), args),
+            Err(_) => warn!("Unable to launch {} with args {:?}", command.program(), args),
+        }
+    }
+
+    /// Update the window title after one of its components has changed
+    fn update_title(&mut self) {
+        let title = window_title(self.config, self.terminal);
+        self.window.set_title(&title);
+    }
+
+    /// Update a title showing the child's foreground process or directory after it wrote output
+    ///
+    /// These can change without the application setting a title, so the title is updated at
+    /// most once per interval while the child is running.
+    fn schedule_title_update(&mut self) {
+        let template = match self.config.window.title_template {
+            Some(ref template) => template,
+            None => return,
+        };
+
+        let polled = template.contains("{process}")
+            || (template.contains("{cwd}") && self.terminal.working_directory().is_none());
+        if polled && self.title_deadline.is_none() {
+            *self.title_deadline = Some(Instant::now() + CHILD_TITLE_INTERVAL);
+        }
+    }
+
+    /// Apply a change to the window requested by the application
+    fn window_operation(&mut self, operation: WindowOperation) {
+        if !self.config.window.allow_window_ops {
+            debug!("Ignoring window operation {:?}", operation);
+            return;
+        }
+
+        let size_info = *self.size_info;
+        let (width, height) = match operation {
+            WindowOperation::ResizePixels { width, height } => (
+                width.map(|width| width as f32 + 2. * size_info.padding_x),
+                height.map(|height| height as f32 + 2. * size_info.padding_y),
+            ),
+            WindowOperation::ResizeChars { lines, cols } => (
+                cols.map(|cols| cols as f32 * size_info.cell_width + 2. * size_info.padding_x),
+                lines.map(|lines| lines as f32 * size_info.cell_height + 2. * size_info.padding_y),
+            ),
+            // Minimizing and raising windows is not supported by the windowing library
+            WindowOperation::Deiconify | WindowOperation::Iconify | WindowOperation::Raise => {
+                debug!("Unsupported window operation {:?}", operation);
+                return;
+            },
+        };
+
+        let width = width.unwrap_or(size_info.width);
+        let height = height.unwrap_or(size_info.height);
+        let size = PhysicalSize::new(f64::from(width), f64::from(height));
+        self.window.set_inner_size(size.to_logical(size_info.dpr));
+    }
+}
+
+/// Window title built from `window.title_template`
+///
+/// Without a template, the title set by the application is used directly.
+fn window_title<T>(config: &Config, terminal: &Term<T>) -> String {
+    let template = match config.window.title_template {
+        Some(ref template) => template,
+        None => return terminal.title().to_owned(),
+    };
+
+    expand_title_template(template, |placeholder| match placeholder {
+        "title" => Some(terminal.title().to_owned()),
+        "icon_name" => Some(terminal.icon_name().to_owned()),
+        "cwd" => Some(match terminal.working_directory() {
+            Some(WorkingDirectory { host: Some(host), path }) => {
+                format!("{}:{}", host, path.display())
+            },
+            Some(directory) => directory.path.display().to_string(),
+            None => {
+                child_working_directory().map(|path| path.display().to_string()).unwrap_or_default()
+            },
+        }),
+        "process" => Some(tty::foreground_process_name().unwrap_or_default()),
+        _ => None,
+    })
+}
+
+/// Replace the `{placeholder}`s of a title template
+///
+/// Unknown placeholders are kept as they are.
+fn expand_title_template<F>(template: &str, value: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut title = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        title.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let replacement =
+            rest.find('}').and_then(|end| value(&rest[1..end]).map(|value| (end, value)));
+        match replacement {
+            Some((end, value)) => {
+                title.push_str(&value);
+                rest = &rest[end + 1..];
+            },
+            None => {
+                title.push('{');
+                rest = &rest[1..];
+            },
+        }
+    }
+
+    title.push_str(rest);
+    title
+}
+
+/// Current working directory of the shell process
+#[cfg(unix)]
+fn child_working_directory() -> Option<PathBuf> {
+    #[cfg(not(target_os = "freebsd"))]
+    let proc_prefix = "";
+    #[cfg(target_os = "freebsd")]
+    let proc_prefix = "/compat/linux";
+    let link_path = format!("{}/proc/{}/cwd", proc_prefix, tty::child_pid());
+    fs::read_link(link_path).ok()
+}
+
+#[cfg(not(unix))]
+fn child_working_directory() -> Option<PathBuf> {
+    None
 }
 
 pub enum ClickState {
@@ -297,6 +495,17 @@ impl Default for Mouse {
     }
 }
 
+/// State of the regex search
+#[derive(Default)]
+pub struct SearchState {
+    /// Text of the search bar, `None` while the search bar is closed
+    pub input: Option<String>,
+    pub direction: Direction,
+    /// Buffer position the search was started from
+    pub origin: Point<usize>,
+    pub focused_match: Option<Match>,
+}
+
 /// The event processor
 ///
 /// Stores some state from received events and dispatches actions when they are
@@ -306,12 +515,23 @@ pub struct Processor<N> {
     mouse: Mouse,
     received_count: usize,
     suppress_chars: bool,
+    text_keys: TextKeys,
     modifiers: ModifiersState,
     config: Config,
     pty_resize_handle: Box<dyn OnResize>,
     message_buffer: MessageBuffer,
     display: Display,
     font_size: Size,
+    search_state: SearchState,
+
+    /// Time at which blinking content is toggled next, `None` while nothing is blinking
+    blink_deadline: Option<Instant>,
+
+    /// Time at which the window title is updated for changes in the child process
+    title_deadline: Option<Instant>,
+
+    /// Time the last desktop notification was shown
+    last_notification: Option<Instant>,
 }
 
 impl<N: Notify> Processor<N> {
@@ -331,12 +551,17 @@ impl<N: Notify> Processor<N> {
             mouse: Default::default(),
             received_count: 0,
             suppress_chars: false,
+            text_keys: Default::default(),
             modifiers: Default::default(),
             font_size: config.font.size,
             config,
             pty_resize_handle,
             message_buffer,
             display,
+            search_state: Default::default(),
+            blink_deadline: None,
+            title_deadline: None,
+            last_notification: None,
         }
     }
 
@@ -347,6 +572,9 @@ impl<N: Notify> Processor<N> {
     {
         let mut event_queue = Vec::new();
 
+        let title = window_title(&self.config, &terminal.lock());
+        self.display.window.set_title(&title);
+
         event_loop.run_return(|event, _event_loop, control_flow| {
             if self.config.debug.print_events {
                 info!("glutin event: {:?}", event);
@@ -362,7 +590,12 @@ impl<N: Notify> Processor<N> {
                 GlutinEvent::EventsCleared => {
                     *control_flow = ControlFlow::Wait;
 
-                    if event_queue.is_empty() {
+                    let deadline = self.next_deadline();
+                    let due = deadline.map_or(false, |deadline| deadline <= Instant::now());
+                    if event_queue.is_empty() && !due {
+                        if let Some(deadline) = deadline {
+                            *control_flow = ControlFlow::WaitUntil(deadline);
+                        }
                         return;
                     }
                 },
@@ -387,12 +620,17 @@ impl<N: Notify> Processor<N> {
                 size_info: &mut self.display.size_info,
                 received_count: &mut self.received_count,
                 suppress_chars: &mut self.suppress_chars,
+                text_keys: &mut self.text_keys,
                 modifiers: &mut self.modifiers,
                 message_buffer: &mut self.message_buffer,
                 display_update_pending: &mut display_update_pending,
                 window: &mut self.display.window,
                 font_size: &mut self.font_size,
                 config: &mut self.config,
+                search_state: &mut self.search_state,
+                last_notification: &mut self.last_notification,
+                blink_deadline: &mut self.blink_deadline,
+                title_deadline: &mut self.title_deadline,
             };
             let mut processor =
                 input::Processor::new(context, &self.display.urls, &self.display.highlighted_url);
@@ -407,12 +645,22 @@ impl<N: Notify> Processor<N> {
                     &mut terminal,
                     self.pty_resize_handle.as_mut(),
                     &self.message_buffer,
+                    self.search_state.input.is_some(),
                     &self.config,
                     display_update_pending,
                 );
             }
 
-            if terminal.dirty {
+            self.update_blink(&mut terminal);
+
+            if self.title_deadline.map_or(false, |deadline| deadline <= Instant::now()) {
+                self.title_deadline = None;
+                let title = window_title(&self.config, &terminal);
+                self.display.window.set_title(&title);
+            }
+
+            // Keep the previous frame while a synchronized update is in progress
+            if terminal.dirty && !terminal.mode().contains(TermMode::SYNC_UPDATE) {
                 terminal.dirty = false;
 
                 // Request immediate re-draw if visual bell animation is not finished yet
@@ -420,6 +668,8 @@ impl<N: Notify> Processor<N> {
                     event_queue.push(GlutinEvent::UserEvent(Event::Wakeup));
                 }
 
+                let is_focused = terminal.is_focused;
+
                 // Redraw screen
                 self.display.draw(
                     terminal,
@@ -427,7 +677,20 @@ impl<N: Notify> Processor<N> {
                     &self.config,
                     &self.mouse,
                     self.modifiers,
+                    &self.search_state,
                 );
+
+                // Blinking text is only known once it has been drawn
+                let interval = self.config.cursor.blink_interval().filter(|_| is_focused);
+                if let (None, Some(interval)) = (self.blink_deadline, interval) {
+                    if self.display.blinking_text {
+                        self.blink_deadline = Some(Instant::now() + interval);
+                    }
+                }
+            }
+
+            if let Some(deadline) = self.next_deadline() {
+                *control_flow = ControlFlow::WaitUntil(deadline);
             }
         });
 
@@ -435,6 +698,47 @@ impl<N: Notify> Processor<N> {
         self.write_ref_test_results(&terminal.lock());
     }
 
+    /// Earliest time at which the event loop has to wake up without receiving any events
+    fn next_deadline(&self) -> Option<Instant> {
+        match (self.blink_deadline, self.title_deadline) {
+            (Some(blink), Some(title)) => Some(min(blink, title)),
+            (blink, title) => blink.or(title),
+        }
+    }
+
+    /// Toggle the visibility of blinking content once the blink interval has passed
+    ///
+    /// Blinking is paused with all content visible while the window is unfocused.
+    fn update_blink<T>(&mut self, terminal: &mut Term<T>) {
+        let interval = match self.config.cursor.blink_interval() {
+            Some(interval)
+                if terminal.is_focused
+                    && (self.display.blinking_text || terminal.has_blinking_cursor()) =>
+            {
+                interval
+            },
+            _ => {
+                if terminal.blink_hidden {
+                    terminal.blink_hidden = false;
+                    terminal.dirty = true;
+                }
+                self.blink_deadline = None;
+                return;
+            },
+        };
+
+        let now = Instant::now();
+        match self.blink_deadline {
+            Some(deadline) if deadline > now => (),
+            Some(_) => {
+                terminal.blink_hidden = !terminal.blink_hidden;
+                terminal.dirty = true;
+                self.blink_deadline = Some(now + interval);
+            },
+            None => self.blink_deadline = Some(now + interval),
+        }
+    }
+
     /// Handle events from glutin
     ///
     /// Doesn't take self mutably due to borrow checking.
@@ -446,8 +750,15 @@ impl<N: Notify> Processor<N> {
     {
         match event {
             GlutinEvent::UserEvent(event) => match event {
-                Event::Title(title) => processor.ctx.window.set_title(&title),
-                Event::Wakeup => processor.ctx.terminal.dirty = true,
+                Event::Title(_) | Event::IconName(_) | Event::WorkingDirectory(_) => {
+                    processor.ctx.update_title()
+                },
+                Event::Notification(notification) => processor.ctx.show_notification(notification),
+                Event::WindowOperation(operation) => processor.ctx.window_operation(operation),
+                Event::Wakeup => {
+                    processor.ctx.terminal.dirty = true;
+                    processor.ctx.schedule_title_update();
+                },
                 Event::Urgent => {
                     processor.ctx.window.set_urgent(!processor.ctx.terminal.is_focused)
                 },
@@ -469,6 +780,7 @@ impl<N: Notify> Processor<N> {
                         }
 
                         *processor.ctx.config = config;
+                        processor.ctx.update_title();
 
                         processor.ctx.terminal.dirty = true;
                     }
@@ -493,6 +805,9 @@ impl<N: Notify> Processor<N> {
                     KeyboardInput { input, .. } => {
                         processor.key_input(input);
                         if input.state == ElementState::Pressed {
+                            // Keep the cursor visible while typing
+                            processor.ctx.reset_blink();
+
                             // Hide cursor while typing
                             if processor.ctx.config.ui_config.mouse.hide_when_typing {
                                 processor.ctx.window.set_mouse_visible(false);
@@ -667,3 +982,21 @@ impl EventListener for EventProxy {
         let _ = self.0.send_event(event);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::expand_title_template;
+
+    #[test]
+    fn expand_title_placeholders() {
+        let value = |placeholder: &str| match placeholder {
+            "title" => Some(String::from("vim {cwd}")),
+            "cwd" => Some(String::from("/tmp")),
+            _ => None,
+        };
+
+        assert_eq!(expand_title_template("{title} - {cwd}", value), "vim {cwd} - /tmp");
+        assert_eq!(expand_title_template("{unknown} {{title}}", value), "{unknown} {vim {cwd}}");
+        assert_eq!(expand_title_template("{title", value), "{title");
+    }
+}
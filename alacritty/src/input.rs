@@ -26,6 +26,7 @@ use std::time::Instant;
 
 use glutin::event::{
     ElementState, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta, TouchPhase,
+    VirtualKeyCode,
 };
 use glutin::window::CursorIcon;
 use log::{debug, trace, warn};
@@ -38,11 +39,13 @@ use alacritty_terminal::index::{Column, Line, Point, Side};
 use alacritty_terminal::message_bar::{self, Message};
 use alacritty_terminal::selection::Selection;
 use alacritty_terminal::term::mode::TermMode;
+use alacritty_terminal::term::search::{Direction, RegexSearch};
 use alacritty_terminal::term::{SizeInfo, Term};
 use alacritty_terminal::util::start_daemon;
 
-use crate::config::{Action, Binding, Config, Key};
-use crate::event::{ClickState, Mouse};
+use crate::config::{Action, Binding, Config, Key, ViAction};
+use crate::event::{ClickState, Mouse, SearchState};
+use crate::keyboard::{self, TextKeys};
 use crate::url::{Url, Urls};
 use crate::window::Window;
 
@@ -76,6 +79,7 @@ pub trait ActionContext<T: EventListener> {
     fn mouse_coords(&self) -> Option<Point>;
     fn received_count(&mut self) -> &mut usize;
     fn suppress_chars(&mut self) -> &mut bool;
+    fn text_keys(&mut self) -> &mut TextKeys;
     fn modifiers(&mut self) -> &mut ModifiersState;
     fn scroll(&mut self, scroll: Scroll);
     fn window(&self) -> &Window;
@@ -88,6 +92,8 @@ pub trait ActionContext<T: EventListener> {
     fn pop_message(&mut self);
     fn message(&self) -> Option<&Message>;
     fn config(&self) -> &Config;
+    fn search_state(&mut self) -> &mut SearchState;
+    fn toggle_search_bar(&mut self);
 }
 
 trait Execute<T: EventListener> {
@@ -147,14 +153,107 @@ impl<T: EventListener> Execute<T> for Action {
             Action::ScrollLineDown => ctx.scroll(Scroll::Lines(-1)),
             Action::ScrollToTop => ctx.scroll(Scroll::Top),
             Action::ScrollToBottom => ctx.scroll(Scroll::Bottom),
+            Action::ScrollToPreviousPrompt => scroll_to_prompt(ctx, Direction::Backward),
+            Action::ScrollToNextPrompt => scroll_to_prompt(ctx, Direction::Forward),
+            Action::SelectLastCommandOutput => select_last_command_output(ctx),
             Action::ClearHistory => ctx.terminal_mut().clear_screen(ClearMode::Saved),
             Action::ClearLogNotice => ctx.pop_message(),
             Action::SpawnNewInstance => ctx.spawn_new_instance(),
+            Action::ToggleViMode => ctx.terminal_mut().toggle_vi_mode(),
+            Action::ClearSelection => ctx.clear_selection(),
+            Action::ViMotion(motion) => ctx.terminal_mut().vi_motion(motion),
+            Action::ViAction(action) => toggle_vi_selection(ctx, action),
+            Action::SearchForward => start_search(ctx, Direction::Forward),
+            Action::SearchBackward => start_search(ctx, Direction::Backward),
+            Action::SearchNext => advance_search(ctx, false),
+            Action::SearchPrevious => advance_search(ctx, true),
             Action::ReceiveChar | Action::None => (),
         }
     }
 }
 
+/// Scroll the closest shell prompt in the specified direction to the top of the screen.
+fn scroll_to_prompt<T: EventListener, A: ActionContext<T>>(ctx: &mut A, direction: Direction) {
+    let term = ctx.terminal();
+    if let Some(line) = term.prompt_line(direction) {
+        let top = term.grid().display_offset() + term.grid().num_lines().0 - 1;
+        ctx.scroll(Scroll::Lines(line as isize - top as isize));
+    }
+}
+
+/// Select all lines of the last shell command's output.
+fn select_last_command_output<T: EventListener, A: ActionContext<T>>(ctx: &mut A) {
+    let term = ctx.terminal_mut();
+    if let Some(lines) = term.last_command_output() {
+        let end = Point::new(*lines.start(), term.grid().num_cols() - 1);
+        let mut selection = Selection::lines(Point::new(*lines.end(), Column(0)));
+        selection.update(end, Side::Right);
+
+        *term.selection_mut() = Some(selection);
+        term.dirty = true;
+    }
+}
+
+/// Start a new vi mode selection at the vi cursor, or clear it if one of the same kind is active.
+fn toggle_vi_selection<T: EventListener, A: ActionContext<T>>(ctx: &mut A, action: ViAction) {
+    let term = ctx.terminal_mut();
+    let point = term.vi_mode_cursor.point;
+
+    let selection = match (action, term.selection()) {
+        (ViAction::ToggleNormalSelection, Some(Selection::Simple { .. }))
+        | (ViAction::ToggleLineSelection, Some(Selection::Lines { .. }))
+        | (ViAction::ToggleBlockSelection, Some(Selection::Block { .. })) => None,
+        (ViAction::ToggleNormalSelection, _) => Some(Selection::simple(point, Side::Left)),
+        (ViAction::ToggleLineSelection, _) => Some(Selection::lines(point)),
+        (ViAction::ToggleBlockSelection, _) => Some(Selection::block(point, Side::Left)),
+    };
+
+    *term.selection_mut() = selection.map(|mut selection| {
+        selection.include_all();
+        selection
+    });
+    term.dirty = true;
+}
+
+/// Open the search bar for a new regex search.
+fn start_search<T: EventListener, A: ActionContext<T>>(ctx: &mut A, direction: Direction) {
+    let term = ctx.terminal();
+    let origin = if term.mode().contains(TermMode::VI) {
+        term.vi_mode_cursor.point
+    } else {
+        let cursor = term.cursor().point;
+        Point::new(term.grid().line_to_offset(cursor.line), cursor.col)
+    };
+
+    // Reserve a line for the search bar, unless it's already open
+    if ctx.search_state().input.is_none() {
+        ctx.toggle_search_bar();
+    }
+
+    *ctx.search_state() =
+        SearchState { input: Some(String::new()), direction, origin, focused_match: None };
+    ctx.terminal_mut().set_regex_search(None);
+}
+
+/// Jump to the next match of the active regex search.
+///
+/// With `reverse` the search is continued opposite to the direction it was started in.
+fn advance_search<T: EventListener, A: ActionContext<T>>(ctx: &mut A, reverse: bool) {
+    let state = ctx.search_state();
+    let direction = if reverse { state.direction.opposite() } else { state.direction };
+    let mut origin = state.focused_match.as_ref().map(|m| *m.start()).unwrap_or(state.origin);
+
+    // Continue searching from the vi cursor, since it might have been moved
+    let term = ctx.terminal_mut();
+    if term.mode().contains(TermMode::VI) {
+        origin = term.vi_mode_cursor.point;
+    }
+
+    if let Some(regex_match) = term.search_next(origin, direction) {
+        ctx.search_state().focused_match = Some(regex_match);
+    }
+}
+
 fn paste<T: EventListener, A: ActionContext<T>>(ctx: &mut A, contents: &str) {
     if ctx.terminal().mode().contains(TermMode::BRACKETED_PASTE) {
         ctx.write_to_pty(&b"\x1b[200~"[..]);
@@ -209,8 +308,12 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
         let cell_changed =
             point.line != self.ctx.mouse().line || point.col != self.ctx.mouse().column;
 
+        // Motion is reported for every pixel when pixel coordinates are requested
+        let report_pixels = self.ctx.terminal().mode().contains(TermMode::SGR_PIXEL_MOUSE);
+
         // If the mouse hasn't changed cells, do nothing
         if !cell_changed
+            && !report_pixels
             && self.ctx.mouse().cell_side == cell_side
             && self.ctx.mouse().inside_grid == inside_grid
         {
@@ -237,9 +340,10 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
             // Treat motion over message bar like motion over the last line
             let line = min(point.line, last_term_line);
 
-            self.ctx.update_selection(Point { line, col: point.col }, cell_side);
+            let point = self.ctx.terminal().screen_to_cell(Point { line, col: point.col });
+            self.ctx.update_selection(point, cell_side);
         } else if inside_grid
-            && cell_changed
+            && (cell_changed || report_pixels)
             && point.line <= last_term_line
             && self.ctx.terminal().mode().intersects(TermMode::MOUSE_MOTION | TermMode::MOUSE_DRAG)
         {
@@ -259,8 +363,16 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
         let size_info = self.ctx.size_info();
         let x = self.ctx.mouse().x;
 
-        let cell_x = x.saturating_sub(size_info.padding_x as usize) % size_info.cell_width as usize;
-        let half_cell_width = (size_info.cell_width / 2.0) as usize;
+        // Cells of double-width lines are twice as wide
+        let line = size_info.pixels_to_coords(x, self.ctx.mouse().y).line;
+        let cell_width = if self.ctx.terminal().line_size(line).is_double_width() {
+            size_info.cell_width * 2.
+        } else {
+            size_info.cell_width
+        };
+
+        let cell_x = x.saturating_sub(size_info.padding_x as usize) % cell_width as usize;
+        let half_cell_width = (cell_width / 2.0) as usize;
 
         let additional_padding =
             (size_info.width - size_info.padding_x * 2.) % size_info.cell_width;
@@ -310,17 +422,45 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
         self.ctx.write_to_pty(msg);
     }
 
-    fn sgr_mouse_report(&mut self, button: u8, state: ElementState) {
+    fn urxvt_mouse_report(&mut self, button: u8) {
         let (line, column) = (self.ctx.mouse().line, self.ctx.mouse().column);
+
+        let msg = format!("\x1b[{};{};{}M", 32 + u32::from(button), column + 1, line + 1);
+        self.ctx.write_to_pty(msg.into_bytes());
+    }
+
+    fn sgr_mouse_report(&mut self, button: u8, state: ElementState) {
+        let (x, y) = if self.ctx.terminal().mode().contains(TermMode::SGR_PIXEL_MOUSE) {
+            self.mouse_pixels()
+        } else {
+            (self.ctx.mouse().column.0, self.ctx.mouse().line.0)
+        };
         let c = match state {
             ElementState::Pressed => 'M',
             ElementState::Released => 'm',
         };
 
-        let msg = format!("\x1b[<{};{};{}{}", button, column + 1, line + 1, c);
+        let msg = format!("\x1b[<{};{};{}{}", button, x + 1, y + 1, c);
         self.ctx.write_to_pty(msg.into_bytes());
     }
 
+    /// Mouse position in pixels relative to the top left corner of the grid
+    ///
+    /// Positions in the padding or the message bar are clamped to the closest pixel of the grid.
+    fn mouse_pixels(&self) -> (usize, usize) {
+        let size_info = self.ctx.size_info();
+        let mouse = self.ctx.mouse();
+
+        let num_lines = self.ctx.terminal().grid().num_lines();
+        let max_x = size_info.cols().0 * size_info.cell_width as usize;
+        let max_y = num_lines.0 * size_info.cell_height as usize;
+
+        let x = mouse.x.saturating_sub(size_info.padding_x as usize);
+        let y = mouse.y.saturating_sub(size_info.padding_y as usize);
+
+        (min(x, max_x.saturating_sub(1)), min(y, max_y.saturating_sub(1)))
+    }
+
     fn mouse_report(&mut self, button: u8, state: ElementState, modifiers: ModifiersState) {
         // Calculate modifiers value
         let mut mods = 0;
@@ -335,12 +475,22 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
         }
 
         // Report mouse events
-        if self.ctx.terminal().mode().contains(TermMode::SGR_MOUSE) {
+        let mode = *self.ctx.terminal().mode();
+        if mode.intersects(TermMode::SGR_MOUSE | TermMode::SGR_PIXEL_MOUSE) {
             self.sgr_mouse_report(button + mods, state);
-        } else if let ElementState::Released = state {
-            self.normal_mouse_report(3 + mods);
+            return;
+        }
+
+        // Encodings other than SGR do not report which button has been released
+        let button = match state {
+            ElementState::Pressed => button + mods,
+            ElementState::Released => 3 + mods,
+        };
+
+        if mode.contains(TermMode::URXVT_MOUSE) {
+            self.urxvt_mouse_report(button);
         } else {
-            self.normal_mouse_report(button + mods);
+            self.normal_mouse_report(button);
         }
     }
 
@@ -367,6 +517,7 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
         let mouse = self.ctx.mouse();
         let mut point = self.ctx.size_info().pixels_to_coords(mouse.x, mouse.y);
         point.line = min(point.line, self.ctx.terminal().grid().num_lines() - 1);
+        let point = self.ctx.terminal().screen_to_cell(point);
 
         self.ctx.mouse_mut().click_state = match self.ctx.mouse().click_state {
             ClickState::Click
@@ -444,9 +595,15 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
 
         if let Some(ref launcher) = self.ctx.config().ui_config.mouse.url.launcher {
             let mut args = launcher.args().to_vec();
-            let start = self.ctx.terminal().visible_to_buffer(url.start());
-            let end = self.ctx.terminal().visible_to_buffer(url.end());
-            args.push(self.ctx.terminal().bounds_to_string(start, end));
+            let terminal = self.ctx.terminal();
+            match url.hyperlink().and_then(|hyperlink| terminal.hyperlink(hyperlink)) {
+                Some(hyperlink) => args.push(hyperlink.uri.clone()),
+                None => {
+                    let start = terminal.visible_to_buffer(url.start());
+                    let end = terminal.visible_to_buffer(url.end());
+                    args.push(terminal.bounds_to_string(start, end));
+                },
+            }
 
             match start_daemon(launcher.program(), &args) {
                 Ok(_) => debug!("Launched {} with args {:?}", launcher.program(), args),
@@ -595,12 +752,34 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
         // TODO: Implement `ModifiersChanged` event on all platforms: rust-windowing/winit#1151
         self.modifiers_input(input.modifiers);
 
+        // Text keys which did not produce any character are encoded by their position instead
+        let mode = *self.ctx.terminal().mode();
+        if let Some(bytes) = self.ctx.text_keys().flush(mode) {
+            self.ctx.write_to_pty(bytes);
+        }
+
         match input.state {
             ElementState::Pressed => {
                 *self.ctx.received_count() = 0;
-                self.process_key_bindings(input);
+
+                if self.ctx.search_state().input.is_some() {
+                    self.process_search_key(input);
+                } else {
+                    self.process_key_bindings(input);
+                }
+            },
+            ElementState::Released => {
+                *self.ctx.suppress_chars() = false;
+
+                if mode.contains(TermMode::REPORT_EVENT_TYPES)
+                    && !mode.contains(TermMode::VI)
+                    && self.ctx.search_state().input.is_none()
+                {
+                    if let Some(bytes) = self.ctx.text_keys().release(&input, mode) {
+                        self.ctx.write_to_pty(bytes);
+                    }
+                }
             },
-            ElementState::Released => *self.ctx.suppress_chars() = false,
         }
     }
 
@@ -616,13 +795,31 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
 
     /// Process a received character.
     pub fn received_char(&mut self, c: char) {
-        if *self.ctx.suppress_chars() {
+        // Add text to the search bar instead of the PTY while it is open
+        if self.ctx.search_state().input.is_some() {
+            if !*self.ctx.suppress_chars() && !c.is_control() {
+                if let Some(input) = &mut self.ctx.search_state().input {
+                    input.push(c);
+                }
+                self.update_search();
+            }
+            return;
+        }
+
+        let mode = *self.ctx.terminal().mode();
+        if *self.ctx.suppress_chars() || mode.contains(TermMode::VI) {
             return;
         }
 
         self.ctx.scroll(Scroll::Bottom);
         self.ctx.clear_selection();
 
+        // Text keys are identified by their character, since it depends on the keyboard layout
+        if let Some(bytes) = self.ctx.text_keys().receive(c, mode) {
+            self.ctx.write_to_pty(bytes);
+            return;
+        }
+
         let utf8_len = c.len_utf8();
         let mut bytes = Vec::with_capacity(utf8_len);
         unsafe {
@@ -652,15 +849,126 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
         self.ctx.window_mut().set_mouse_cursor(mouse_state.into());
     }
 
+    /// Process key input while the search bar is open.
+    fn process_search_key(&mut self, input: KeyboardInput) {
+        match input.virtual_keycode {
+            Some(VirtualKeyCode::Return) | Some(VirtualKeyCode::NumpadEnter) => {
+                advance_search(&mut self.ctx, input.modifiers.shift);
+            },
+            Some(VirtualKeyCode::Escape) => self.close_search(),
+            Some(VirtualKeyCode::Back) => {
+                if let Some(input) = &mut self.ctx.search_state().input {
+                    input.pop();
+                }
+                self.update_search();
+            },
+            _ => {
+                // Allow the character to be added to the search input
+                *self.ctx.suppress_chars() = false;
+                return;
+            },
+        }
+
+        *self.ctx.suppress_chars() = true;
+    }
+
+    /// Search for the first match of the search bar input.
+    fn update_search(&mut self) {
+        let state = self.ctx.search_state();
+        let search = match &state.input {
+            Some(input) if !input.is_empty() => RegexSearch::new(input).ok(),
+            _ => None,
+        };
+        let (origin, direction) = (state.origin, state.direction);
+
+        let term = self.ctx.terminal_mut();
+        let active = search.is_some();
+        term.set_regex_search(search);
+
+        let focused_match = if active { term.search_next(origin, direction) } else { None };
+        if focused_match.is_none() {
+            *term.selection_mut() = None;
+        }
+
+        self.ctx.search_state().focused_match = focused_match;
+    }
+
+    /// Close the search bar.
+    fn close_search(&mut self) {
+        self.ctx.search_state().input = None;
+        self.ctx.toggle_search_bar();
+
+        // Keep the matches around for `SearchNext` and `SearchPrevious` in vi mode
+        let term = self.ctx.terminal_mut();
+        if !term.mode().contains(TermMode::VI) {
+            term.set_regex_search(None);
+        }
+        term.dirty = true;
+    }
+
     /// Attempt to find a binding and execute its action.
     ///
     /// The provided mode, mods, and key must match what is allowed by a binding
     /// for its action to be executed.
     fn process_key_bindings(&mut self, input: KeyboardInput) {
+        let mode = *self.ctx.terminal().mode();
+        let vi_mode = mode.contains(TermMode::VI);
+
+        // Keys encoded for the active keyboard modes replace the escape bindings
+        //
+        // Text keys are only identified by their position here, the final encoding is chosen once
+        // their character has been received.
+        let encoded = if vi_mode { None } else { keyboard::encode(&input, mode) };
+        let skip_esc = vi_mode || encoded.is_some();
+
+        // Vi mode bindings take precedence over the regular bindings
+        let mut suppress_chars = None;
+        if vi_mode {
+            suppress_chars = self.process_key_binding_table(input, true, skip_esc);
+        }
+        if suppress_chars.is_none() {
+            suppress_chars = self.process_key_binding_table(input, false, skip_esc);
+        }
+
+        let pending =
+            !vi_mode && suppress_chars.is_none() && self.ctx.text_keys().press(input, mode);
+        if let (None, Some(bytes), false) = (suppress_chars, encoded, pending) {
+            self.ctx.scroll(Scroll::Bottom);
+            self.ctx.clear_selection();
+            self.ctx.write_to_pty(bytes);
+            suppress_chars = Some(true);
+        }
+
+        // Don't suppress char if no bindings were triggered, unless in vi mode
+        *self.ctx.suppress_chars() = vi_mode || suppress_chars.unwrap_or(false);
+    }
+
+    /// Execute all bindings of either the regular or the vi mode table triggered by the input.
+    ///
+    /// Returns `None` if no binding was triggered, otherwise whether chars should be suppressed.
+    fn process_key_binding_table(
+        &mut self,
+        input: KeyboardInput,
+        vi_table: bool,
+        skip_esc: bool,
+    ) -> Option<bool> {
         let mut suppress_chars = None;
 
-        for i in 0..self.ctx.config().ui_config.key_bindings.len() {
-            let binding = &self.ctx.config().ui_config.key_bindings[i];
+        let ui_config = &self.ctx.config().ui_config;
+        let len =
+            if vi_table { ui_config.vi_key_bindings.len() } else { ui_config.key_bindings.len() };
+
+        for i in 0..len {
+            let ui_config = &self.ctx.config().ui_config;
+            let binding =
+                if vi_table { &ui_config.vi_key_bindings[i] } else { &ui_config.key_bindings[i] };
+
+            // Nothing is ever written to the PTY while in vi mode, encoded keys replace escapes
+            if skip_esc {
+                if let Action::Esc(_) = binding.action {
+                    continue;
+                }
+            }
 
             let key = match (binding.trigger, input.virtual_keycode) {
                 (Key::Scancode(_), _) => Key::Scancode(input.scancode),
@@ -678,8 +986,7 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
             }
         }
 
-        // Don't suppress char if no bindings were triggered
-        *self.ctx.suppress_chars() = suppress_chars.unwrap_or(false);
+        suppress_chars
     }
 
     /// Attempt to find a binding and execute its action.
@@ -774,6 +1081,7 @@ mod tests {
         ElementState, Event, ModifiersState, MouseButton, VirtualKeyCode, WindowEvent,
     };
 
+    use alacritty_terminal::ansi::{self, Handler};
     use alacritty_terminal::clipboard::{Clipboard, ClipboardType};
     use alacritty_terminal::event::{Event as TerminalEvent, EventListener};
     use alacritty_terminal::grid::Scroll;
@@ -783,7 +1091,8 @@ mod tests {
     use alacritty_terminal::term::{SizeInfo, Term, TermMode};
 
     use crate::config::{ClickHandler, Config};
-    use crate::event::{ClickState, Mouse};
+    use crate::event::{ClickState, Mouse, SearchState};
+    use crate::keyboard::TextKeys;
     use crate::url::Urls;
     use crate::window::Window;
 
@@ -813,12 +1122,17 @@ mod tests {
         pub last_action: MultiClick,
         pub received_count: usize,
         pub suppress_chars: bool,
+        pub text_keys: TextKeys,
         pub modifiers: ModifiersState,
+        pub search_state: SearchState,
+        pub written: Vec<u8>,
         config: &'a Config,
     }
 
     impl<'a, T: EventListener> super::ActionContext<T> for ActionContext<'a, T> {
-        fn write_to_pty<B: Into<Cow<'static, [u8]>>>(&mut self, _val: B) {}
+        fn write_to_pty<B: Into<Cow<'static, [u8]>>>(&mut self, val: B) {
+            self.written.extend_from_slice(&val.into());
+        }
 
         fn update_selection(&mut self, _point: Point, _side: Side) {}
 
@@ -894,6 +1208,10 @@ mod tests {
             &mut self.suppress_chars
         }
 
+        fn text_keys(&mut self) -> &mut TextKeys {
+            &mut self.text_keys
+        }
+
         fn modifiers(&mut self) -> &mut ModifiersState {
             &mut self.modifiers
         }
@@ -917,6 +1235,12 @@ mod tests {
         fn config(&self) -> &Config {
             self.config
         }
+
+        fn search_state(&mut self) -> &mut SearchState {
+            &mut self.search_state
+        }
+
+        fn toggle_search_bar(&mut self) {}
     }
 
     macro_rules! test_clickstate {
@@ -970,8 +1294,11 @@ mod tests {
                     last_action: MultiClick::None,
                     received_count: 0,
                     suppress_chars: false,
+                    text_keys: Default::default(),
                     modifiers: Default::default(),
                     message_buffer: &mut message_buffer,
+                    search_state: Default::default(),
+                    written: Vec::new(),
                     config: &cfg,
                 };
 
@@ -1018,6 +1345,73 @@ mod tests {
         }
     }
 
+    macro_rules! test_mouse_report {
+        {
+            name: $name:ident,
+            mode: $mode:expr,
+            button: $button:expr,
+            state: $state:expr,
+            position: ($x:expr, $y:expr),
+            expected: $expected:expr
+        } => {
+            #[test]
+            fn $name() {
+                let cfg = Config::default();
+
+                let size = SizeInfo {
+                    width: 1250.0,
+                    height: 1250.0,
+                    cell_width: 5.0,
+                    cell_height: 10.0,
+                    padding_x: 10.0,
+                    padding_y: 20.0,
+                    dpr: 1.0,
+                };
+
+                let mut terminal = Term::new(&cfg, &size, Clipboard::new_nop(), MockEventProxy);
+                terminal.set_mode(ansi::Mode::ReportMouseClicks);
+                if let Some(mode) = $mode {
+                    terminal.set_mode(mode);
+                }
+
+                let point = size.pixels_to_coords($x, $y);
+                let mut mouse = Mouse::default();
+                mouse.x = $x;
+                mouse.y = $y;
+                mouse.line = point.line;
+                mouse.column = point.col;
+
+                let mut selection = None;
+
+                let mut message_buffer = MessageBuffer::new();
+
+                let context = ActionContext {
+                    terminal: &mut terminal,
+                    selection: &mut selection,
+                    mouse: &mut mouse,
+                    size_info: &size,
+                    last_action: MultiClick::None,
+                    received_count: 0,
+                    suppress_chars: false,
+                    text_keys: Default::default(),
+                    modifiers: Default::default(),
+                    message_buffer: &mut message_buffer,
+                    search_state: Default::default(),
+                    written: Vec::new(),
+                    config: &cfg,
+                };
+
+                let urls = Urls::new();
+                let mut processor = Processor::new(context, &urls, &None);
+
+                let modifiers = ModifiersState { shift: false, ctrl: true, alt: false, logo: false };
+                processor.mouse_report($button, $state, modifiers);
+
+                assert_eq!(processor.ctx.written, $expected.to_vec());
+            }
+        }
+    }
+
     test_clickstate! {
         name: single_click,
         initial_state: ClickState::None,
@@ -1086,6 +1480,87 @@ mod tests {
         last_action: MultiClick::None
     }
 
+    test_mouse_report! {
+        name: mouse_report_normal,
+        mode: None,
+        button: 0,
+        state: ElementState::Pressed,
+        position: (27, 52),
+        expected: b"\x1b[M\x30\x24\x24"
+    }
+
+    test_mouse_report! {
+        name: mouse_report_normal_release,
+        mode: None,
+        button: 2,
+        state: ElementState::Released,
+        position: (27, 52),
+        expected: b"\x1b[M\x33\x24\x24"
+    }
+
+    test_mouse_report! {
+        name: mouse_report_normal_out_of_range,
+        mode: None,
+        button: 0,
+        state: ElementState::Pressed,
+        position: (1200, 52),
+        expected: b""
+    }
+
+    test_mouse_report! {
+        name: mouse_report_utf8,
+        mode: Some(ansi::Mode::Utf8Mouse),
+        button: 0,
+        state: ElementState::Pressed,
+        position: (1200, 52),
+        expected: b"\x1b[M\x30\xc4\x8f\x24"
+    }
+
+    test_mouse_report! {
+        name: mouse_report_sgr,
+        mode: Some(ansi::Mode::SgrMouse),
+        button: 1,
+        state: ElementState::Released,
+        position: (27, 52),
+        expected: b"\x1b[<17;4;4m"
+    }
+
+    test_mouse_report! {
+        name: mouse_report_sgr_pixels,
+        mode: Some(ansi::Mode::SgrPixelMouse),
+        button: 0,
+        state: ElementState::Pressed,
+        position: (27, 52),
+        expected: b"\x1b[<16;18;33M"
+    }
+
+    test_mouse_report! {
+        name: mouse_report_sgr_pixels_clamped_to_grid,
+        mode: Some(ansi::Mode::SgrPixelMouse),
+        button: 64,
+        state: ElementState::Pressed,
+        position: (5, 1249),
+        expected: b"\x1b[<80;1;1210M"
+    }
+
+    test_mouse_report! {
+        name: mouse_report_urxvt,
+        mode: Some(ansi::Mode::UrxvtMouse),
+        button: 0,
+        state: ElementState::Pressed,
+        position: (1200, 52),
+        expected: b"\x1b[48;239;4M"
+    }
+
+    test_mouse_report! {
+        name: mouse_report_urxvt_release,
+        mode: Some(ansi::Mode::UrxvtMouse),
+        button: 0,
+        state: ElementState::Released,
+        position: (27, 52),
+        expected: b"\x1b[51;4;4M"
+    }
+
     test_process_binding! {
         name: process_binding_nomode_shiftmod_require_shift,
         binding: Binding { trigger: KEY, mods: ModifiersState { shift: true, ctrl: false, alt: false, logo: false }, action: Action::from("\x1b[1;2D"), mode: TermMode::NONE, notmode: TermMode::NONE },
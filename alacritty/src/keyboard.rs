@@ -0,0 +1,539 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Key encoding for the kitty keyboard protocol and xterm's modifyOtherKeys
+//!
+//! Keys which are not affected by the active keyboard modes are left to the key bindings and
+//! received characters, just like without any keyboard modes.
+use glutin::event::{ElementState, KeyboardInput, ModifiersState, VirtualKeyCode};
+
+use alacritty_terminal::term::mode::TermMode;
+
+/// Key codes of the legacy control keys
+const ENTER: u32 = 13;
+const TAB: u32 = 9;
+const BACKSPACE: u32 = 127;
+const ESCAPE: u32 = 27;
+
+/// Key as understood by the terminal
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Key {
+    /// Key producing text, identified by its unshifted character and the shifted one, if known
+    Text(char, Option<char>),
+
+    /// Enter, Tab, Backspace and Escape
+    Control(u32),
+
+    /// Functional key encoded as `CSI 1 ; modifiers <final>`
+    Cursor(char),
+
+    /// Functional key encoded as `CSI number ; modifiers ~`
+    Tilde(u32),
+
+    /// Functional key without legacy encoding, using kitty's private use codepoints
+    Private(u32),
+
+    /// Modifier key, only reported when all keys are reported as escape codes
+    Modifier(u32),
+}
+
+/// Text keys which are encoded once their character has been received
+///
+/// The character of a key depends on the keyboard layout and is only known after the key press,
+/// so text keys are encoded when it is received instead.
+#[derive(Debug, Default)]
+pub struct TextKeys {
+    /// Key press waiting for its character
+    pending: Option<KeyboardInput>,
+
+    /// Characters of the text keys which are held down, by scancode
+    pressed: Vec<(u32, char)>,
+}
+
+impl TextKeys {
+    /// Hold back a key press until its character has been received
+    ///
+    /// Returns `false` if the key is not a text key or no keyboard mode is active.
+    pub fn press(&mut self, input: KeyboardInput, mode: TermMode) -> bool {
+        let is_text = match input.virtual_keycode.and_then(key) {
+            Some(Key::Text(..)) | None => true,
+            Some(_) => false,
+        };
+
+        let keyboard_modes = TermMode::KITTY_KEYBOARD_PROTOCOL
+            | TermMode::MODIFY_OTHER_KEYS
+            | TermMode::MODIFY_ALL_KEYS;
+        if !is_text || !mode.intersects(keyboard_modes) {
+            return false;
+        }
+
+        self.pending = Some(input);
+        true
+    }
+
+    /// Encode the pending key press using its received character
+    ///
+    /// Returns `None` if the character should be written as it is instead.
+    pub fn receive(&mut self, c: char, mode: TermMode) -> Option<Vec<u8>> {
+        let input = self.pending.take()?;
+        self.pressed.retain(|(scancode, _)| *scancode != input.scancode);
+        self.pressed.push((input.scancode, c));
+
+        encode_key(text_key(c, &input)?, &input, mode)
+    }
+
+    /// Encode a pending key press which did not produce any character
+    pub fn flush(&mut self, mode: TermMode) -> Option<Vec<u8>> {
+        encode(&self.pending.take()?, mode)
+    }
+
+    /// Encode a key release, using the character of text keys which was received on press
+    pub fn release(&mut self, input: &KeyboardInput, mode: TermMode) -> Option<Vec<u8>> {
+        let index = self.pressed.iter().position(|(scancode, _)| *scancode == input.scancode);
+        match index {
+            Some(index) => encode_key(text_key(self.pressed.remove(index).1, input)?, input, mode),
+            None => encode(input, mode),
+        }
+    }
+}
+
+/// Encode a key event according to the active keyboard modes
+///
+/// Text keys are identified by their position on a US layout, use `TextKeys` to identify them by
+/// their character instead.
+///
+/// Returns `None` if the event should be handled by the key bindings and received characters
+/// instead.
+pub fn encode(input: &KeyboardInput, mode: TermMode) -> Option<Vec<u8>> {
+    encode_key(key(input.virtual_keycode?)?, input, mode)
+}
+
+fn encode_key(key: Key, input: &KeyboardInput, mode: TermMode) -> Option<Vec<u8>> {
+    if mode.intersects(TermMode::KITTY_KEYBOARD_PROTOCOL) {
+        encode_kitty(key, input, mode)
+    } else if mode.intersects(TermMode::MODIFY_OTHER_KEYS | TermMode::MODIFY_ALL_KEYS) {
+        encode_modify_other_keys(key, input, mode)
+    } else {
+        None
+    }
+}
+
+/// Identify a text key by the character it produced
+fn text_key(c: char, input: &KeyboardInput) -> Option<Key> {
+    let mods = input.modifiers;
+
+    // Option composes characters on macOS, so they do not identify the key
+    if cfg!(target_os = "macos") && mods.alt {
+        return key(input.virtual_keycode?);
+    }
+
+    let c = match c as u32 {
+        // Ctrl turns letters into C0 control characters
+        1..=26 => (b'a' + c as u8 - 1) as char,
+        _ if c.is_control() => return key(input.virtual_keycode?),
+        _ => c,
+    };
+
+    if c.is_alphabetic() {
+        let base = single(c.to_lowercase()).unwrap_or(c);
+        let shifted = if mods.shift { single(base.to_uppercase()) } else { None };
+        return Some(Key::Text(base, shifted.filter(|&shifted| shifted != base)));
+    }
+
+    if !mods.shift {
+        return Some(Key::Text(c, None));
+    }
+
+    // The unshifted character of other keys is only known for a US layout
+    match input.virtual_keycode.and_then(key) {
+        Some(Key::Text(base, _)) if base != c => Some(Key::Text(base, Some(c))),
+        _ => Some(Key::Text(c, None)),
+    }
+}
+
+/// Character of a case conversion which results in a single character
+fn single(mut chars: impl Iterator<Item = char>) -> Option<char> {
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+/// Encode a key using the kitty keyboard protocol
+fn encode_kitty(key: Key, input: &KeyboardInput, mode: TermMode) -> Option<Vec<u8>> {
+    let mods = input.modifiers;
+    let released = input.state == ElementState::Released;
+    if released && !mode.contains(TermMode::REPORT_EVENT_TYPES) {
+        return None;
+    }
+
+    let all_keys = mode.contains(TermMode::REPORT_ALL_KEYS_AS_ESC);
+    let disambiguate = all_keys || mode.contains(TermMode::DISAMBIGUATE_ESC_CODES);
+    let text_mods = mods.ctrl || mods.alt || mods.logo;
+
+    let encode = match key {
+        _ if all_keys => true,
+        Key::Modifier(_) => false,
+        Key::Text(..) => disambiguate && text_mods,
+        Key::Control(ESCAPE) | Key::Private(_) => disambiguate,
+        // Plain Enter, Tab and Backspace keep working in case an application did not restore the
+        // keyboard modes before exiting
+        Key::Control(_) => disambiguate && (text_mods || mods.shift) && !released,
+        Key::Cursor(_) | Key::Tilde(_) => mode.contains(TermMode::REPORT_EVENT_TYPES),
+    };
+    if !encode {
+        return None;
+    }
+
+    let mut params = String::new();
+    let modifiers = modifiers(mods);
+    if modifiers != 1 || released {
+        params.push_str(&format!(";{}", modifiers));
+    }
+    if released {
+        params.push_str(":3");
+    }
+
+    let sequence = match key {
+        Key::Cursor(c) if params.is_empty() => format!("\x1b[{}", c),
+        Key::Cursor(c) => format!("\x1b[1{}{}", params, c),
+        Key::Tilde(number) => format!("\x1b[{}{}~", number, params),
+        Key::Text(c, shifted) if mods.shift && mode.contains(TermMode::REPORT_ALTERNATE_KEYS) => {
+            match shifted.or_else(|| Some(c.to_ascii_uppercase()).filter(|&shifted| shifted != c)) {
+                Some(shifted) => format!("\x1b[{}:{}{}u", c as u32, shifted as u32, params),
+                None => format!("\x1b[{}{}u", c as u32, params),
+            }
+        },
+        Key::Text(c, _) => format!("\x1b[{}{}u", c as u32, params),
+        Key::Control(code) | Key::Private(code) | Key::Modifier(code) => {
+            format!("\x1b[{}{}u", code, params)
+        },
+    };
+
+    Some(sequence.into_bytes())
+}
+
+/// Encode a key using xterm's `CSI 27 ; modifiers ; code ~` format
+fn encode_modify_other_keys(key: Key, input: &KeyboardInput, mode: TermMode) -> Option<Vec<u8>> {
+    if input.state == ElementState::Released {
+        return None;
+    }
+
+    let mods = input.modifiers;
+    let code = match key {
+        Key::Text(c, shifted) if mods.shift => shifted.unwrap_or(c.to_ascii_uppercase()) as u32,
+        Key::Text(c, _) => c as u32,
+        Key::Control(code) => code,
+        _ => return None,
+    };
+
+    let encode = if mode.contains(TermMode::MODIFY_ALL_KEYS) {
+        mods.ctrl || mods.alt || mods.logo
+    } else {
+        mods.ctrl && !is_control_character(key, mods.shift)
+    };
+
+    if encode {
+        Some(format!("\x1b[27;{};{}~", modifiers(mods), code).into_bytes())
+    } else {
+        None
+    }
+}
+
+/// Check if a key pressed with Ctrl has a well-known control character
+fn is_control_character(key: Key, shift: bool) -> bool {
+    match key {
+        Key::Text(c, _) => !shift && (c.is_ascii_lowercase() || "[\\]/ ".contains(c)),
+        Key::Control(code) => code == ESCAPE,
+        _ => false,
+    }
+}
+
+/// Encoded modifier parameter
+fn modifiers(mods: ModifiersState) -> u8 {
+    1 + (mods.shift as u8 | (mods.alt as u8) << 1 | (mods.ctrl as u8) << 2 | (mods.logo as u8) << 3)
+}
+
+fn key(keycode: VirtualKeyCode) -> Option<Key> {
+    use glutin::event::VirtualKeyCode::*;
+
+    let key = match keycode {
+        Key1 => Key::Text('1', None),
+        Key2 => Key::Text('2', None),
+        Key3 => Key::Text('3', None),
+        Key4 => Key::Text('4', None),
+        Key5 => Key::Text('5', None),
+        Key6 => Key::Text('6', None),
+        Key7 => Key::Text('7', None),
+        Key8 => Key::Text('8', None),
+        Key9 => Key::Text('9', None),
+        Key0 => Key::Text('0', None),
+        A => Key::Text('a', None),
+        B => Key::Text('b', None),
+        C => Key::Text('c', None),
+        D => Key::Text('d', None),
+        E => Key::Text('e', None),
+        F => Key::Text('f', None),
+        G => Key::Text('g', None),
+        H => Key::Text('h', None),
+        I => Key::Text('i', None),
+        J => Key::Text('j', None),
+        K => Key::Text('k', None),
+        L => Key::Text('l', None),
+        M => Key::Text('m', None),
+        N => Key::Text('n', None),
+        O => Key::Text('o', None),
+        P => Key::Text('p', None),
+        Q => Key::Text('q', None),
+        R => Key::Text('r', None),
+        S => Key::Text('s', None),
+        T => Key::Text('t', None),
+        U => Key::Text('u', None),
+        V => Key::Text('v', None),
+        W => Key::Text('w', None),
+        X => Key::Text('x', None),
+        Y => Key::Text('y', None),
+        Z => Key::Text('z', None),
+        Space => Key::Text(' ', None),
+        Apostrophe => Key::Text('\'', None),
+        Backslash => Key::Text('\\', None),
+        Comma => Key::Text(',', None),
+        Equals => Key::Text('=', None),
+        Grave => Key::Text('`', None),
+        LBracket => Key::Text('[', None),
+        Minus => Key::Text('-', None),
+        Period => Key::Text('.', None),
+        RBracket => Key::Text(']', None),
+        Semicolon => Key::Text(';', None),
+        Slash => Key::Text('/', None),
+        Return => Key::Control(ENTER),
+        Tab => Key::Control(TAB),
+        Back => Key::Control(BACKSPACE),
+        Escape => Key::Control(ESCAPE),
+        Up => Key::Cursor('A'),
+        Down => Key::Cursor('B'),
+        Right => Key::Cursor('C'),
+        Left => Key::Cursor('D'),
+        End => Key::Cursor('F'),
+        Home => Key::Cursor('H'),
+        F1 => Key::Cursor('P'),
+        F2 => Key::Cursor('Q'),
+        F4 => Key::Cursor('S'),
+        Insert => Key::Tilde(2),
+        Delete => Key::Tilde(3),
+        PageUp => Key::Tilde(5),
+        PageDown => Key::Tilde(6),
+        F3 => Key::Tilde(13),
+        F5 => Key::Tilde(15),
+        F6 => Key::Tilde(17),
+        F7 => Key::Tilde(18),
+        F8 => Key::Tilde(19),
+        F9 => Key::Tilde(20),
+        F10 => Key::Tilde(21),
+        F11 => Key::Tilde(23),
+        F12 => Key::Tilde(24),
+        Capital => Key::Private(57358),
+        Scroll => Key::Private(57359),
+        Numlock => Key::Private(57360),
+        Snapshot => Key::Private(57361),
+        Pause => Key::Private(57362),
+        Apps => Key::Private(57363),
+        F13 => Key::Private(57376),
+        F14 => Key::Private(57377),
+        F15 => Key::Private(57378),
+        F16 => Key::Private(57379),
+        F17 => Key::Private(57380),
+        F18 => Key::Private(57381),
+        F19 => Key::Private(57382),
+        F20 => Key::Private(57383),
+        F21 => Key::Private(57384),
+        F22 => Key::Private(57385),
+        F23 => Key::Private(57386),
+        F24 => Key::Private(57387),
+        Numpad0 => Key::Private(57399),
+        Numpad1 => Key::Private(57400),
+        Numpad2 => Key::Private(57401),
+        Numpad3 => Key::Private(57402),
+        Numpad4 => Key::Private(57403),
+        Numpad5 => Key::Private(57404),
+        Numpad6 => Key::Private(57405),
+        Numpad7 => Key::Private(57406),
+        Numpad8 => Key::Private(57407),
+        Numpad9 => Key::Private(57408),
+        Decimal => Key::Private(57409),
+        Divide => Key::Private(57410),
+        Multiply => Key::Private(57411),
+        Subtract => Key::Private(57412),
+        Add => Key::Private(57413),
+        NumpadEnter => Key::Private(57414),
+        NumpadEquals => Key::Private(57415),
+        LShift => Key::Modifier(57441),
+        LControl => Key::Modifier(57442),
+        LAlt => Key::Modifier(57443),
+        LWin => Key::Modifier(57444),
+        RShift => Key::Modifier(57447),
+        RControl => Key::Modifier(57448),
+        RAlt => Key::Modifier(57449),
+        RWin => Key::Modifier(57450),
+        _ => return None,
+    };
+
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use glutin::event::{ElementState, KeyboardInput, ModifiersState, VirtualKeyCode};
+
+    use alacritty_terminal::term::mode::TermMode;
+
+    use super::{encode, TextKeys};
+
+    fn input(key: VirtualKeyCode, state: ElementState, mods: ModifiersState) -> KeyboardInput {
+        KeyboardInput { scancode: 0, state, virtual_keycode: Some(key), modifiers: mods }
+    }
+
+    fn press(key: VirtualKeyCode, mods: ModifiersState, mode: TermMode) -> Option<String> {
+        let bytes = encode(&input(key, ElementState::Pressed, mods), mode)?;
+        Some(String::from_utf8(bytes).unwrap())
+    }
+
+    fn release(key: VirtualKeyCode, mods: ModifiersState, mode: TermMode) -> Option<String> {
+        let bytes = encode(&input(key, ElementState::Released, mods), mode)?;
+        Some(String::from_utf8(bytes).unwrap())
+    }
+
+    fn typed(key: VirtualKeyCode, c: char, mods: ModifiersState, mode: TermMode) -> Option<String> {
+        let mut text_keys = TextKeys::default();
+        assert!(text_keys.press(input(key, ElementState::Pressed, mods), mode));
+        let bytes = text_keys.receive(c, mode)?;
+        Some(String::from_utf8(bytes).unwrap())
+    }
+
+    const NONE: ModifiersState =
+        ModifiersState { shift: false, ctrl: false, alt: false, logo: false };
+    const CTRL: ModifiersState =
+        ModifiersState { shift: false, ctrl: true, alt: false, logo: false };
+    const CTRL_SHIFT: ModifiersState =
+        ModifiersState { shift: true, ctrl: true, alt: false, logo: false };
+    const SHIFT: ModifiersState =
+        ModifiersState { shift: true, ctrl: false, alt: false, logo: false };
+
+    #[test]
+    fn legacy_without_modes() {
+        assert_eq!(press(VirtualKeyCode::I, CTRL, TermMode::NONE), None);
+        assert_eq!(press(VirtualKeyCode::Escape, NONE, TermMode::NONE), None);
+        assert_eq!(release(VirtualKeyCode::A, NONE, TermMode::NONE), None);
+    }
+
+    #[test]
+    fn disambiguate_escape_codes() {
+        let mode = TermMode::DISAMBIGUATE_ESC_CODES;
+
+        assert_eq!(press(VirtualKeyCode::I, CTRL, mode), Some("\x1b[105;5u".into()));
+        assert_eq!(press(VirtualKeyCode::Tab, NONE, mode), None);
+        assert_eq!(press(VirtualKeyCode::Tab, CTRL, mode), Some("\x1b[9;5u".into()));
+        assert_eq!(press(VirtualKeyCode::Escape, NONE, mode), Some("\x1b[27u".into()));
+        assert_eq!(press(VirtualKeyCode::A, NONE, mode), None);
+        assert_eq!(press(VirtualKeyCode::Up, CTRL, mode), None);
+        assert_eq!(press(VirtualKeyCode::Numpad1, NONE, mode), Some("\x1b[57400u".into()));
+        assert_eq!(release(VirtualKeyCode::I, CTRL, mode), None);
+    }
+
+    #[test]
+    fn report_event_types() {
+        let mode = TermMode::DISAMBIGUATE_ESC_CODES | TermMode::REPORT_EVENT_TYPES;
+
+        assert_eq!(release(VirtualKeyCode::I, CTRL, mode), Some("\x1b[105;5:3u".into()));
+        assert_eq!(press(VirtualKeyCode::Up, NONE, mode), Some("\x1b[A".into()));
+        assert_eq!(release(VirtualKeyCode::Up, NONE, mode), Some("\x1b[1;1:3A".into()));
+        assert_eq!(release(VirtualKeyCode::F5, CTRL, mode), Some("\x1b[15;5:3~".into()));
+        assert_eq!(release(VirtualKeyCode::Return, NONE, mode), None);
+    }
+
+    #[test]
+    fn report_all_keys() {
+        let mode = TermMode::REPORT_ALL_KEYS_AS_ESC | TermMode::REPORT_ALTERNATE_KEYS;
+
+        assert_eq!(press(VirtualKeyCode::A, NONE, mode), Some("\x1b[97u".into()));
+        assert_eq!(press(VirtualKeyCode::A, CTRL_SHIFT, mode), Some("\x1b[97:65;6u".into()));
+        assert_eq!(press(VirtualKeyCode::Return, NONE, mode), Some("\x1b[13u".into()));
+        assert_eq!(press(VirtualKeyCode::LShift, NONE, mode), Some("\x1b[57441u".into()));
+    }
+
+    #[test]
+    fn modify_other_keys() {
+        let mode = TermMode::MODIFY_OTHER_KEYS;
+        assert_eq!(press(VirtualKeyCode::A, CTRL, mode), None);
+        assert_eq!(press(VirtualKeyCode::A, CTRL_SHIFT, mode), Some("\x1b[27;6;65~".into()));
+        assert_eq!(press(VirtualKeyCode::Tab, CTRL, mode), Some("\x1b[27;5;9~".into()));
+
+        let mode = TermMode::MODIFY_ALL_KEYS;
+        assert_eq!(press(VirtualKeyCode::A, CTRL, mode), Some("\x1b[27;5;97~".into()));
+        assert_eq!(press(VirtualKeyCode::A, NONE, mode), None);
+    }
+
+    #[test]
+    fn text_keys_use_received_character() {
+        let mode = TermMode::REPORT_ALL_KEYS_AS_ESC | TermMode::REPORT_ALTERNATE_KEYS;
+
+        // The key labeled A on an AZERTY layout is at the position of Q
+        assert_eq!(typed(VirtualKeyCode::Q, 'a', NONE, mode), Some("\x1b[97u".into()));
+        assert_eq!(
+            typed(VirtualKeyCode::Q, '\x01', CTRL_SHIFT, mode),
+            Some("\x1b[97:65;6u".into())
+        );
+        assert_eq!(typed(VirtualKeyCode::Key2, 'é', NONE, mode), Some("\x1b[233u".into()));
+        assert_eq!(typed(VirtualKeyCode::Key1, '!', SHIFT, mode), Some("\x1b[49:33;2u".into()));
+
+        let mode = TermMode::DISAMBIGUATE_ESC_CODES;
+        assert_eq!(typed(VirtualKeyCode::Comma, '\x17', CTRL, mode), Some("\x1b[119;5u".into()));
+        assert_eq!(typed(VirtualKeyCode::Comma, 'w', NONE, mode), None);
+
+        let mode = TermMode::MODIFY_ALL_KEYS;
+        assert_eq!(
+            typed(VirtualKeyCode::Semicolon, '\x1a', CTRL, mode),
+            Some("\x1b[27;5;122~".into())
+        );
+    }
+
+    #[test]
+    fn text_key_release_uses_pressed_character() {
+        let mode = TermMode::REPORT_ALL_KEYS_AS_ESC | TermMode::REPORT_EVENT_TYPES;
+
+        let mut text_keys = TextKeys::default();
+        let mut key = input(VirtualKeyCode::Q, ElementState::Pressed, NONE);
+        key.scancode = 30;
+        assert!(text_keys.press(key, mode));
+        assert_eq!(text_keys.receive('a', mode), Some(b"\x1b[97u".to_vec()));
+
+        key.state = ElementState::Released;
+        assert_eq!(text_keys.release(&key, mode), Some(b"\x1b[97;1:3u".to_vec()));
+    }
+
+    #[test]
+    fn flush_text_key_without_character() {
+        let mode = TermMode::REPORT_ALL_KEYS_AS_ESC;
+
+        let mut text_keys = TextKeys::default();
+        assert!(text_keys.press(input(VirtualKeyCode::A, ElementState::Pressed, NONE), mode));
+        assert_eq!(text_keys.flush(mode), Some(b"\x1b[97u".to_vec()));
+        assert_eq!(text_keys.flush(mode), None);
+
+        assert!(!text_keys.press(input(VirtualKeyCode::Up, ElementState::Pressed, NONE), mode));
+        assert!(
+            !text_keys.press(input(VirtualKeyCode::A, ElementState::Pressed, NONE), TermMode::NONE)
+        );
+    }
+}
@@ -54,6 +54,7 @@ mod config;
 mod display;
 mod event;
 mod input;
+mod keyboard;
 mod logging;
 mod url;
 mod window;
@@ -8,7 +8,7 @@ use font::Metrics;
 
 use alacritty_terminal::index::Point;
 use alacritty_terminal::renderer::rects::{RenderLine, RenderRect};
-use alacritty_terminal::term::cell::Flags;
+use alacritty_terminal::term::cell::{Flags, HyperlinkId};
 use alacritty_terminal::term::{RenderableCell, RenderableCellContent, SizeInfo};
 
 use crate::config::{Config, RelaxedEq};
@@ -19,6 +19,7 @@ pub struct Url {
     lines: Vec<RenderLine>,
     end_offset: u16,
     num_cols: usize,
+    hyperlink: Option<HyperlinkId>,
 }
 
 impl Url {
@@ -43,11 +44,22 @@ impl Url {
     pub fn end(&self) -> Point {
         self.lines[self.lines.len() - 1].end.sub(self.num_cols, self.end_offset as usize)
     }
+
+    /// Explicit hyperlink set by the application with OSC 8
+    pub fn hyperlink(&self) -> Option<HyperlinkId> {
+        self.hyperlink
+    }
+
+    fn contains(&self, point: Point) -> bool {
+        let end = self.end();
+        self.lines.iter().any(|line| line.start <= point && point <= min(line.end, end))
+    }
 }
 
 pub struct Urls {
     locator: UrlLocator,
     urls: Vec<Url>,
+    hyperlinks: Vec<Url>,
     last_point: Option<Point>,
     state: UrlLocation,
 }
@@ -57,6 +69,7 @@ impl Default for Urls {
         Self {
             locator: UrlLocator::new(),
             urls: Vec::new(),
+            hyperlinks: Vec::new(),
             state: UrlLocation::Reset,
             last_point: None,
         }
@@ -69,7 +82,7 @@ impl Urls {
     }
 
     // Update tracked URLs
-    pub fn update(&mut self, num_cols: usize, cell: RenderableCell) {
+    pub fn update(&mut self, num_cols: usize, cell: &RenderableCell) {
         // Ignore double-width spacers to prevent reset
         if cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
             return;
@@ -77,12 +90,20 @@ impl Urls {
 
         // Convert cell to character
         let c = match cell.inner {
-            RenderableCellContent::Chars(chars) => chars[0],
+            RenderableCellContent::Chars(c, _) => c,
             RenderableCellContent::Cursor(_) => return,
         };
 
         let point: Point = cell.into();
 
+        // Explicit hyperlinks take precedence over URLs in the text
+        if let Some(hyperlink) = cell.hyperlink {
+            self.reset();
+            self.last_point = None;
+            self.update_hyperlink(num_cols, cell, hyperlink);
+            return;
+        }
+
         // Reset URL when empty cells have been skipped
         if point != Point::default() && Some(point.sub(num_cols, 1)) != self.last_point {
             self.reset();
@@ -126,6 +147,7 @@ impl Urls {
                     lines: vec![RenderLine { start: point, end: point, color: cell.fg }],
                     end_offset: 0,
                     num_cols,
+                    hyperlink: None,
                 });
             },
             (UrlLocation::Scheme, _) => {
@@ -167,13 +189,49 @@ impl Urls {
             return None;
         }
 
-        for url in &self.urls {
-            if (url.start()..=url.end()).contains(&Point::new(mouse.line, mouse.column)) {
-                return Some(url.clone());
-            }
+        let point = Point::new(mouse.line, mouse.column);
+        self.urls.iter().chain(&self.hyperlinks).find(|url| url.contains(point)).cloned()
+    }
+
+    /// Add a cell to the hyperlink it is part of
+    ///
+    /// All visible cells referencing the same hyperlink form a single URL, so hovering any part
+    /// of a hyperlink highlights all of it.
+    fn update_hyperlink(&mut self, num_cols: usize, cell: &RenderableCell, hyperlink: HyperlinkId) {
+        let start: Point = cell.into();
+        let mut end = start;
+
+        // Extend by one cell for double-width characters
+        if cell.flags.contains(Flags::WIDE_CHAR) {
+            end.col += 1;
         }
 
-        None
+        let url = match self.hyperlinks.iter_mut().find(|url| url.hyperlink == Some(hyperlink)) {
+            Some(url) => url,
+            None => {
+                self.hyperlinks.push(Url {
+                    lines: vec![RenderLine { start, end, color: cell.fg }],
+                    end_offset: 0,
+                    num_cols,
+                    hyperlink: Some(hyperlink),
+                });
+                return;
+            },
+        };
+
+        let last_index = url.lines.len() - 1;
+        let last_line = &mut url.lines[last_index];
+
+        if last_line.color == cell.fg
+            && last_line.end.line == start.line
+            && last_line.end.col + 1 == start.col
+        {
+            // Update existing line
+            last_line.end = end;
+        } else {
+            // Create new line for every new row, color or part of the hyperlink
+            url.lines.push(RenderLine { start, end, color: cell.fg });
+        }
     }
 
     fn reset(&mut self) {
@@ -13,16 +13,21 @@
 // limitations under the License.
 //
 //! ANSI Terminal Stream Parsing
+use std::cmp::max;
 use std::io;
+use std::path::PathBuf;
 use std::str;
 
+use bitflags::bitflags;
 use log::{debug, trace};
 use serde::{Deserialize, Serialize};
 
 use vte;
 
+use crate::graphics::{sixel, Image};
 use crate::index::{Column, Line};
 use crate::term::color::Rgb;
+use crate::termcap::{self, Capability};
 
 // Parse colors in XParseColor format
 fn xparse_color(color: &[u8]) -> Option<Rgb> {
@@ -89,15 +94,237 @@ fn parse_number(input: &[u8]) -> Option<u8> {
     Some(num)
 }
 
+/// Parse the `file://host/path` URI reported with OSC 7
+fn parse_working_directory(uri: &str) -> Option<WorkingDirectory> {
+    let prefix = "file://";
+    if uri.len() < prefix.len() || !uri[..prefix.len()].eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+
+    let uri = &uri[prefix.len()..];
+    let path_start = uri.find('/')?;
+
+    let host = &uri[..path_start];
+    let host = if host.is_empty() || host.eq_ignore_ascii_case("localhost") {
+        None
+    } else {
+        Some(String::from(host))
+    };
+
+    let path = String::from_utf8(percent_decode(&uri.as_bytes()[path_start..])?).ok()?;
+
+    // Drive letters are reported as `/C:/path`
+    #[cfg(windows)]
+    let path = path.trim_start_matches('/');
+
+    Some(WorkingDirectory { host, path: PathBuf::from(path) })
+}
+
+/// Decode `%XX` escapes
+fn percent_decode(input: &[u8]) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut bytes = input.iter();
+    while let Some(&byte) = bytes.next() {
+        if byte == b'%' {
+            let high = char::from(*bytes.next()?).to_digit(16)?;
+            let low = char::from(*bytes.next()?).to_digit(16)?;
+            output.push((high << 4 | low) as u8);
+        } else {
+            output.push(byte);
+        }
+    }
+    Some(output)
+}
+
+/// Build the XTGETTCAP response for a single hex encoded capability name
+fn termcap_response(hex_name: &[u8]) -> String {
+    let name = match decode_hex(hex_name) {
+        Some(name) => name,
+        None => return String::from("\x1bP0+r\x1b\\"),
+    };
+
+    let capability = str::from_utf8(&name).ok().and_then(termcap::lookup);
+    let hex_name = encode_hex(&name);
+    match capability {
+        Some(Capability::Boolean) => format!("\x1bP1+r{}\x1b\\", hex_name),
+        Some(Capability::Numeric(value)) => {
+            format!("\x1bP1+r{}={}\x1b\\", hex_name, encode_hex(value.to_string().as_bytes()))
+        },
+        Some(Capability::String(value)) => {
+            format!("\x1bP1+r{}={}\x1b\\", hex_name, encode_hex(value.as_bytes()))
+        },
+        None => format!("\x1bP0+r{}\x1b\\", hex_name),
+    }
+}
+
+fn decode_hex(input: &[u8]) -> Option<Vec<u8>> {
+    if input.is_empty() || input.len() % 2 != 0 {
+        return None;
+    }
+
+    input
+        .chunks(2)
+        .map(|pair| {
+            let high = (pair[0] as char).to_digit(16)?;
+            let low = (pair[1] as char).to_digit(16)?;
+            Some((high << 4 | low) as u8)
+        })
+        .collect()
+}
+
+fn encode_hex(input: &[u8]) -> String {
+    input.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
 /// The processor wraps a `vte::Parser` to ultimately call methods on a Handler
 pub struct Processor {
     state: ProcessorState,
     parser: vte::Parser,
+
+    /// Bytes received during the synchronized update in progress
+    sync_bytes: Option<Vec<u8>>,
 }
 
 /// Internal state for VTE processor
 struct ProcessorState {
     preceding_char: Option<char>,
+
+    /// DCS sequence which is currently received
+    dcs: Option<Dcs>,
+
+    /// Progress of the header of the DCS sequence which is currently received
+    dcs_header: DcsHeader,
+
+    /// Progress of the CSI sequence which is currently received
+    csi: CsiState,
+
+    /// Bitmask of the CSI parameters which are separated from the previous one by a colon
+    subparameters: u32,
+
+    /// Whether the last sequence began a synchronized update
+    sync_started: bool,
+}
+
+/// Supported DCS sequences and the state of their payload
+#[derive(Debug)]
+enum Dcs {
+    /// Sixel graphics
+    Sixel(sixel::Parser),
+
+    /// DECRQSS - Request Status String
+    RequestStatus(Vec<u8>),
+
+    /// XTGETTCAP - Request Termcap/Terminfo String
+    RequestTermcap(Vec<u8>),
+}
+
+/// Maximum length of the payload of a DCS request
+const MAX_DCS_REQUEST_LEN: usize = 1024;
+
+/// Maximum number of bytes held back by a synchronized update
+const MAX_SYNC_BYTES: usize = 0x20_0000;
+
+/// Sequences ending a synchronized update
+const SYNC_END_SEQUENCES: [&[u8]; 2] = [b"\x1b[?2026l", b"\x1bP=2s"];
+
+/// Position inside a CSI sequence, used to track colon separated subparameters
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum CsiState {
+    Ground,
+    Escape,
+    Params(usize),
+}
+
+/// Position inside the header of a DCS sequence
+///
+/// The vte parser passes neither the final character nor the last parameter of a DCS sequence to
+/// `hook`, so they are recovered from the raw bytes instead.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum DcsHeader {
+    Ground,
+    Escape,
+
+    /// Receiving the parameters, with the value of the current one
+    Params(Option<i64>),
+
+    /// Receiving the payload
+    Complete {
+        last_param: Option<i64>,
+        action: u8,
+    },
+}
+
+impl ProcessorState {
+    /// Replace colons inside CSI parameters with semicolons, recording their position
+    ///
+    /// The vte parser ignores all CSI sequences containing colons, so the subparameters are
+    /// passed as regular parameters instead.
+    #[inline]
+    fn track_subparameters(&mut self, byte: u8) -> u8 {
+        match (self.csi, byte) {
+            (_, 0x1b) => self.csi = CsiState::Escape,
+            (CsiState::Escape, b'[') => {
+                self.csi = CsiState::Params(0);
+                self.subparameters = 0;
+            },
+            (CsiState::Params(index), b';') => self.csi = CsiState::Params(index + 1),
+            (CsiState::Params(index), b':') => {
+                self.csi = CsiState::Params(index + 1);
+                if index + 1 < 32 {
+                    self.subparameters |= 1 << (index + 1);
+                }
+                return b';';
+            },
+            // Final byte or cancellation of the sequence
+            (CsiState::Params(_), 0x18)
+            | (CsiState::Params(_), 0x1a)
+            | (CsiState::Params(_), 0x40..=0x7e)
+            | (CsiState::Escape, _) => self.csi = CsiState::Ground,
+            _ => (),
+        }
+
+        byte
+    }
+
+    /// Record the final character and last parameter of DCS sequences
+    #[inline]
+    fn track_dcs_header(&mut self, byte: u8) {
+        self.dcs_header = match (self.dcs_header, byte) {
+            (_, 0x1b) => DcsHeader::Escape,
+            (DcsHeader::Escape, b'P') => DcsHeader::Params(None),
+            (DcsHeader::Params(param), b'0'..=b'9') => {
+                let digit = i64::from(byte - b'0');
+                DcsHeader::Params(Some(param.unwrap_or(0).saturating_mul(10).saturating_add(digit)))
+            },
+            (DcsHeader::Params(_), b';') => DcsHeader::Params(None),
+            (DcsHeader::Params(last_param), 0x40..=0x7e) => {
+                DcsHeader::Complete { last_param, action: byte }
+            },
+            // Cancellation of the sequence
+            (DcsHeader::Params(_), 0x18)
+            | (DcsHeader::Params(_), 0x1a)
+            | (DcsHeader::Escape, _) => DcsHeader::Ground,
+            (header, _) => header,
+        };
+    }
+
+    /// Final character of the DCS sequence which is currently received
+    #[inline]
+    fn dcs_action(&self) -> Option<u8> {
+        match self.dcs_header {
+            DcsHeader::Complete { action, .. } => Some(action),
+            _ => None,
+        }
+    }
+
+    /// Last parameter of the DCS sequence which is currently received
+    #[inline]
+    fn dcs_last_param(&self) -> Option<i64> {
+        match self.dcs_header {
+            DcsHeader::Complete { last_param, .. } => last_param,
+            _ => None,
+        }
+    }
 }
 
 /// Helper type that implements `vte::Perform`.
@@ -124,7 +351,18 @@ impl<'a, H: Handler + TermInfo + 'a, W: io::Write> Performer<'a, H, W> {
 
 impl Default for Processor {
     fn default() -> Processor {
-        Processor { state: ProcessorState { preceding_char: None }, parser: vte::Parser::new() }
+        Processor {
+            state: ProcessorState {
+                preceding_char: None,
+                dcs: None,
+                dcs_header: DcsHeader::Ground,
+                csi: CsiState::Ground,
+                subparameters: 0,
+                sync_started: false,
+            },
+            parser: vte::Parser::new(),
+            sync_bytes: None,
+        }
     }
 }
 
@@ -139,6 +377,47 @@ impl Processor {
         H: Handler + TermInfo,
         W: io::Write,
     {
+        // Hold back all bytes of a synchronized update, until it has been finished
+        if let Some(bytes) = self.sync_bytes.as_mut() {
+            bytes.push(byte);
+
+            let finished = SYNC_END_SEQUENCES.iter().any(|end| bytes.ends_with(end));
+            if finished || bytes.len() >= MAX_SYNC_BYTES {
+                self.stop_sync(handler, writer);
+            }
+            return;
+        }
+
+        self.parse(handler, byte, writer);
+
+        if self.state.sync_started {
+            self.state.sync_started = false;
+            self.sync_bytes = Some(Vec::new());
+        }
+    }
+
+    /// Process all bytes of the synchronized update in progress and end it
+    pub fn stop_sync<H, W>(&mut self, handler: &mut H, writer: &mut W)
+    where
+        H: Handler + TermInfo,
+        W: io::Write,
+    {
+        for byte in self.sync_bytes.take().unwrap_or_default() {
+            self.parse(handler, byte, writer);
+        }
+
+        self.state.sync_started = false;
+        handler.unset_mode(Mode::SyncUpdate);
+    }
+
+    #[inline]
+    fn parse<H, W>(&mut self, handler: &mut H, byte: u8, writer: &mut W)
+    where
+        H: Handler + TermInfo,
+        W: io::Write,
+    {
+        self.state.track_dcs_header(byte);
+        let byte = self.state.track_subparameters(byte);
         let mut performer = Performer::new(&mut self.state, handler, writer);
         self.parser.advance(&mut performer, byte);
     }
@@ -158,9 +437,15 @@ pub trait Handler {
     /// OSC to set window title
     fn set_title(&mut self, _: &str) {}
 
+    /// OSC to set icon name
+    fn set_icon_name(&mut self, _: &str) {}
+
     /// Set the cursor style
     fn set_cursor_style(&mut self, _: Option<CursorStyle>) {}
 
+    /// Set the cursor shape, keeping its blinking state
+    fn set_cursor_shape(&mut self, _: CursorShape) {}
+
     /// A character to be displayed
     fn input(&mut self, _c: char) {}
 
@@ -270,6 +555,36 @@ pub trait Handler {
     /// Clear screen
     fn clear_screen(&mut self, _mode: ClearMode) {}
 
+    /// Clear line, skipping cells protected by DECSCA
+    fn selective_clear_line(&mut self, _mode: LineClearMode) {}
+
+    /// Clear screen, skipping cells protected by DECSCA
+    fn selective_clear_screen(&mut self, _mode: ClearMode) {}
+
+    /// Protect the following characters from selective erasure
+    fn set_character_protection(&mut self, _protected: bool) {}
+
+    /// DECCRA - Copy a rectangular area, `line` and `col` are its new top left corner
+    fn copy_rectangle(&mut self, _area: Rectangle, _line: Line, _col: Column) {}
+
+    /// DECFRA - Fill a rectangular area with a character
+    fn fill_rectangle(&mut self, _c: char, _area: Rectangle) {}
+
+    /// DECERA - Erase a rectangular area
+    fn erase_rectangle(&mut self, _area: Rectangle) {}
+
+    /// DECCARA - Change the attributes of a rectangular area
+    fn change_rectangle_attributes(&mut self, _area: Rectangle, _attrs: &[Attr]) {}
+
+    /// DECRARA - Reverse the attributes of a rectangular area
+    fn reverse_rectangle_attributes(&mut self, _area: Rectangle, _attrs: &[Attr]) {}
+
+    /// DECSACE - Select which cells are changed by DECCARA and DECRARA
+    fn set_attribute_change_extent(&mut self, _extent: AttributeChangeExtent) {}
+
+    /// DECRQCRA - Report the checksum of a rectangular area
+    fn report_rectangle_checksum<W: io::Write>(&mut self, _: &mut W, _id: i64, _: Rectangle) {}
+
     /// Clear tab stops
     fn clear_tabs(&mut self, _mode: TabulationClearMode) {}
 
@@ -292,9 +607,41 @@ pub trait Handler {
     /// Unset mode
     fn unset_mode(&mut self, _: Mode) {}
 
+    /// Push keyboard modes of the kitty keyboard protocol onto the stack
+    fn push_keyboard_mode(&mut self, _: KeyboardModes) {}
+
+    /// Pop keyboard modes of the kitty keyboard protocol from the stack
+    fn pop_keyboard_modes(&mut self, _: u16) {}
+
+    /// Change the active keyboard modes of the kitty keyboard protocol
+    fn set_keyboard_mode(&mut self, _: KeyboardModes, _: KeyboardModesApplyBehavior) {}
+
+    /// Report the active keyboard modes of the kitty keyboard protocol
+    fn report_keyboard_mode<W: io::Write>(&mut self, _: &mut W) {}
+
+    /// Set xterm's modifyOtherKeys resource
+    fn set_modify_other_keys(&mut self, _: ModifyOtherKeys) {}
+
+    /// DECRQM - State of a mode
+    fn mode_state(&self, _: &Mode) -> ModeState {
+        ModeState::NotRecognized
+    }
+
+    /// DECRQSS - Value of a setting, formatted as the sequence which changes it
+    fn status_string(&self, _: StatusRequest) -> Option<String> {
+        None
+    }
+
     /// DECSTBM - Set the terminal scrolling region
     fn set_scrolling_region(&mut self, _top: usize, _bottom: usize) {}
 
+    /// DECSLRM - Set the left and right margins
+    ///
+    /// Without left and right margin mode the same sequence saves the cursor position instead.
+    fn set_left_right_margins(&mut self, _left: usize, _right: usize) {
+        self.save_cursor_position();
+    }
+
     /// DECKPAM - Set keypad to applications mode (ESCape instead of digits)
     fn set_keypad_application_mode(&mut self) {}
 
@@ -313,6 +660,9 @@ pub trait Handler {
     /// later be 'invoked' by `set_active_charset`
     fn configure_charset(&mut self, _: CharsetIndex, _: StandardCharset) {}
 
+    /// SS2/SS3 - Invoke G2 or G3 for the next character only
+    fn single_shift(&mut self, _: CharsetIndex) {}
+
     /// Set an indexed color value
     fn set_color(&mut self, _: usize, _: Rgb) {}
 
@@ -331,16 +681,82 @@ pub trait Handler {
     /// Run the decaln routine.
     fn decaln(&mut self) {}
 
+    /// DECSWL/DECDWL/DECDHL - Change the size of the characters on the cursor's line
+    fn set_line_size(&mut self, _: LineSize) {}
+
     /// Push a title onto the stack
     fn push_title(&mut self) {}
 
     /// Pop the last title from the stack
     fn pop_title(&mut self) {}
+
+    /// Report a property of the window
+    fn report_window<W: io::Write>(&mut self, _: &mut W, _: WindowReport) {}
+
+    /// Request a change to the window
+    fn window_operation(&mut self, _: WindowOperation) {}
+
+    /// Set the hyperlink of the following characters, `None` ends the current hyperlink
+    fn set_hyperlink(&mut self, _: Option<Hyperlink>) {}
+
+    /// Set the working directory reported by the shell, `None` if it is unknown
+    fn set_working_directory(&mut self, _: Option<WorkingDirectory>) {}
+
+    /// Mark the current line with a shell integration boundary
+    fn set_prompt_mark(&mut self, _: PromptMark) {}
+
+    /// Show a desktop notification
+    fn desktop_notification(&mut self, _: Notification) {}
+
+    /// Insert a graphic at the cursor position
+    fn insert_graphic(&mut self, _: Image) {}
+}
+
+/// Hyperlink set with OSC 8
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Hyperlink {
+    /// Explicit identifier shared by all parts of the same hyperlink
+    pub id: Option<String>,
+
+    /// Target of the hyperlink
+    pub uri: String,
+}
+
+/// Working directory reported with OSC 7
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WorkingDirectory {
+    /// Host the directory is located on, `None` for the local machine
+    pub host: Option<String>,
+
+    /// Percent-decoded path of the directory
+    pub path: PathBuf,
+}
+
+/// Desktop notification requested with OSC 9 or OSC 777
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    /// Summary of the notification, `None` if the application did not provide one
+    pub title: Option<String>,
+
+    pub body: String,
+}
+
+/// Shell integration boundaries reported with OSC 133
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PromptMark {
+    /// Start of the prompt
+    PromptStart,
+    /// End of the prompt and start of the command input
+    CommandStart,
+    /// Start of the command's output
+    OutputStart,
+    /// End of the command, with its exit status if it was reported
+    CommandEnd(Option<i32>),
 }
 
 /// Describes shape of cursor
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Hash, Deserialize)]
-pub enum CursorStyle {
+pub enum CursorShape {
     /// Cursor is a block like `▒`
     Block,
 
@@ -357,9 +773,165 @@ pub enum CursorStyle {
     Hidden,
 }
 
-impl Default for CursorStyle {
-    fn default() -> CursorStyle {
-        CursorStyle::Block
+impl Default for CursorShape {
+    fn default() -> CursorShape {
+        CursorShape::Block
+    }
+}
+
+/// Shape of the cursor and whether it is blinking
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone, Hash)]
+pub struct CursorStyle {
+    pub shape: CursorShape,
+    pub blinking: bool,
+}
+
+/// State of a mode, as reported by DECRPM
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ModeState {
+    NotRecognized = 0,
+    Set = 1,
+    Reset = 2,
+    PermanentlySet = 3,
+    PermanentlyReset = 4,
+}
+
+impl From<bool> for ModeState {
+    fn from(set: bool) -> ModeState {
+        if set {
+            ModeState::Set
+        } else {
+            ModeState::Reset
+        }
+    }
+}
+
+/// Window properties which can be requested with XTWINOPS
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum WindowReport {
+    /// Size of the text area in pixels
+    TextAreaSizePixels,
+    /// Size of a cell in pixels
+    CellSizePixels,
+    /// Size of the text area in characters
+    TextAreaSizeChars,
+    /// Size of the screen in characters
+    ScreenSizeChars,
+    /// Icon label of the window
+    IconLabel,
+    /// Title of the window
+    Title,
+}
+
+/// Changes to the window which can be requested with XTWINOPS
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum WindowOperation {
+    /// Restore a minimized window
+    Deiconify,
+    /// Minimize the window
+    Iconify,
+    /// Raise the window to the front of the stacking order
+    Raise,
+    /// Resize the text area in pixels, `None` keeps the current size
+    ResizePixels { width: Option<usize>, height: Option<usize> },
+    /// Resize the text area in characters, `None` keeps the current size
+    ResizeChars { lines: Option<usize>, cols: Option<usize> },
+}
+
+/// Area of the screen changed by the rectangular area operations
+///
+/// Bounds are inclusive and relative to the origin, `None` extends the area to the edge of the
+/// screen.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Rectangle {
+    pub top: Line,
+    pub left: Column,
+    pub bottom: Option<Line>,
+    pub right: Option<Column>,
+}
+
+impl Rectangle {
+    /// Parse the `Pt ; Pl ; Pb ; Pr` parameters of a rectangular area operation
+    fn from_args(args: &[i64]) -> Rectangle {
+        // Zero and missing parameters select the default
+        let arg = |idx: usize| args.get(idx).filter(|&&v| v > 0).map(|&v| v as usize - 1);
+
+        Rectangle {
+            top: Line(arg(0).unwrap_or(0)),
+            left: Column(arg(1).unwrap_or(0)),
+            bottom: arg(2).map(Line),
+            right: arg(3).map(Column),
+        }
+    }
+}
+
+/// Cells affected by changing the attributes of a rectangular area
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AttributeChangeExtent {
+    /// All cells from the top left to the bottom right corner, wrapping at the end of lines
+    Stream,
+    /// Only the cells inside the rectangle
+    Rectangle,
+}
+
+impl Default for AttributeChangeExtent {
+    fn default() -> AttributeChangeExtent {
+        AttributeChangeExtent::Stream
+    }
+}
+
+/// Size of the characters on a line
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum LineSize {
+    /// DECSWL - Single width and height
+    Normal,
+    /// DECDWL - Double width
+    DoubleWidth,
+    /// DECDHL - Upper half of double width and height characters
+    DoubleHeightTop,
+    /// DECDHL - Lower half of double width and height characters
+    DoubleHeightBottom,
+}
+
+impl LineSize {
+    /// Check if every character of the line covers two columns
+    #[inline]
+    pub fn is_double_width(self) -> bool {
+        self != LineSize::Normal
+    }
+}
+
+impl Default for LineSize {
+    fn default() -> LineSize {
+        LineSize::Normal
+    }
+}
+
+/// Settings which can be requested with DECRQSS
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum StatusRequest {
+    /// SGR - Graphic rendition
+    Sgr,
+    /// DECSTBM - Top and bottom margins
+    ScrollingRegion,
+    /// DECSCUSR - Cursor style
+    CursorStyle,
+    /// DECSCA - Character protection
+    CharacterProtection,
+    /// DECSACE - Extent of rectangular attribute changes
+    AttributeChangeExtent,
+}
+
+impl StatusRequest {
+    fn from_payload(payload: &[u8]) -> Option<StatusRequest> {
+        match payload {
+            b"m" => Some(StatusRequest::Sgr),
+            b"r" => Some(StatusRequest::ScrollingRegion),
+            b" q" => Some(StatusRequest::CursorStyle),
+            b"\"q" => Some(StatusRequest::CharacterProtection),
+            b"*x" => Some(StatusRequest::AttributeChangeExtent),
+            _ => None,
+        }
     }
 }
 
@@ -391,6 +963,8 @@ pub enum Mode {
     Origin = 6,
     /// ?7
     LineWrap = 7,
+    /// ?69
+    LeftRightMargin = 69,
     /// ?12
     BlinkingCursor = 12,
     /// 20
@@ -414,10 +988,16 @@ pub enum Mode {
     SgrMouse = 1006,
     /// ?1007
     AlternateScroll = 1007,
+    /// ?1015
+    UrxvtMouse = 1015,
+    /// ?1016
+    SgrPixelMouse = 1016,
     /// ?1049
     SwapScreenAndSetRestoreCursor = 1049,
     /// ?2004
     BracketedPaste = 2004,
+    /// ?2026
+    SyncUpdate = 2026,
 }
 
 impl Mode {
@@ -437,6 +1017,7 @@ impl Mode {
                 3 => Mode::DECCOLM,
                 6 => Mode::Origin,
                 7 => Mode::LineWrap,
+                69 => Mode::LeftRightMargin,
                 12 => Mode::BlinkingCursor,
                 25 => Mode::ShowCursor,
                 1000 => Mode::ReportMouseClicks,
@@ -446,8 +1027,11 @@ impl Mode {
                 1005 => Mode::Utf8Mouse,
                 1006 => Mode::SgrMouse,
                 1007 => Mode::AlternateScroll,
+                1015 => Mode::UrxvtMouse,
+                1016 => Mode::SgrPixelMouse,
                 1049 => Mode::SwapScreenAndSetRestoreCursor,
                 2004 => Mode::BracketedPaste,
+                2026 => Mode::SyncUpdate,
                 _ => {
                     trace!("[unimplemented] primitive mode: {}", num);
                     return None;
@@ -463,10 +1047,43 @@ impl Mode {
     }
 }
 
+bitflags! {
+    /// Progressive enhancements of the kitty keyboard protocol
+    pub struct KeyboardModes: u8 {
+        const NO_MODE                = 0b0000;
+        const DISAMBIGUATE_ESC_CODES = 0b0001;
+        const REPORT_EVENT_TYPES     = 0b0010;
+        const REPORT_ALTERNATE_KEYS  = 0b0100;
+        const REPORT_ALL_KEYS_AS_ESC = 0b1000;
+    }
+}
+
+/// How the keyboard modes of `CSI = flags ; mode u` are applied to the active modes
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KeyboardModesApplyBehavior {
+    /// Replace the active modes
+    Replace,
+    /// Add the modes to the active ones
+    Union,
+    /// Remove the modes from the active ones
+    Difference,
+}
+
+/// Level of xterm's modifyOtherKeys resource
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ModifyOtherKeys {
+    /// Send the regular sequences for modified keys
+    Reset,
+    /// Report modified keys, unless they already have a well-known meaning
+    EnableExceptWellDefined,
+    /// Report all modified keys
+    EnableAll,
+}
+
 /// Mode for clearing line
 ///
 /// Relative to cursor
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum LineClearMode {
     /// Clear right of cursor
     Right,
@@ -479,7 +1096,7 @@ pub enum LineClearMode {
 /// Mode for clearing terminal
 ///
 /// Relative to cursor
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum ClearMode {
     /// Clear below cursor
     Below,
@@ -624,7 +1241,7 @@ pub enum Color {
 }
 
 /// Terminal character attributes
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Attr {
     /// Clear all special abilities
     Reset,
@@ -636,9 +1253,9 @@ pub enum Attr {
     Italic,
     /// Underline text
     Underline,
-    /// Blink cursor slowly
+    /// Blink text slowly
     BlinkSlow,
-    /// Blink cursor fast
+    /// Blink text fast
     BlinkFast,
     /// Invert colors
     Reverse,
@@ -646,6 +1263,16 @@ pub enum Attr {
     Hidden,
     /// Strikeout text
     Strike,
+    /// Double underline text
+    DoubleUnderline,
+    /// Curly underline text
+    Undercurl,
+    /// Dotted underline text
+    DottedUnderline,
+    /// Dashed underline text
+    DashedUnderline,
+    /// Overline text
+    Overline,
     /// Cancel bold
     CancelBold,
     /// Cancel bold and dim
@@ -662,10 +1289,14 @@ pub enum Attr {
     CancelHidden,
     /// Cancel strikeout
     CancelStrike,
+    /// Cancel overline
+    CancelOverline,
     /// Set indexed foreground color
     Foreground(Color),
     /// Set indexed background color
     Background(Color),
+    /// Set underline color, `None` uses the foreground color
+    UnderlineColor(Option<Color>),
 }
 
 /// Identifiers which can be assigned to a graphic character set
@@ -689,6 +1320,51 @@ impl Default for CharsetIndex {
 pub enum StandardCharset {
     Ascii,
     SpecialCharacterAndLineDrawing,
+    DecSupplementalGraphics,
+    DecTechnical,
+
+    /// National replacement character sets
+    British,
+    Dutch,
+    Finnish,
+    French,
+    FrenchCanadian,
+    German,
+    Italian,
+    NorwegianDanish,
+    Portuguese,
+    Spanish,
+    Swedish,
+    Swiss,
+}
+
+impl StandardCharset {
+    /// Character set selected by the final bytes of an SCS sequence
+    ///
+    /// The intermediates are the ones following the intermediate which selects G0-G3.
+    fn from_designator(intermediates: &[u8], byte: u8) -> Option<StandardCharset> {
+        let charset = match (intermediates, byte) {
+            (b"", b'B') => StandardCharset::Ascii,
+            (b"", b'0') => StandardCharset::SpecialCharacterAndLineDrawing,
+            (b"", b'<') | (b"%", b'5') => StandardCharset::DecSupplementalGraphics,
+            (b"", b'>') => StandardCharset::DecTechnical,
+            (b"", b'A') => StandardCharset::British,
+            (b"", b'4') => StandardCharset::Dutch,
+            (b"", b'C') | (b"", b'5') => StandardCharset::Finnish,
+            (b"", b'R') | (b"", b'f') => StandardCharset::French,
+            (b"", b'Q') | (b"", b'9') => StandardCharset::FrenchCanadian,
+            (b"", b'K') => StandardCharset::German,
+            (b"", b'Y') => StandardCharset::Italian,
+            (b"", b'E') | (b"", b'6') | (b"", b'`') => StandardCharset::NorwegianDanish,
+            (b"%", b'6') => StandardCharset::Portuguese,
+            (b"", b'Z') => StandardCharset::Spanish,
+            (b"", b'H') | (b"", b'7') => StandardCharset::Swedish,
+            (b"", b'=') => StandardCharset::Swiss,
+            _ => return None,
+        };
+
+        Some(charset)
+    }
 }
 
 impl Default for StandardCharset {
@@ -719,6 +1395,8 @@ where
             C0::SUB => self.handler.substitute(),
             C0::SI => self.handler.set_active_charset(CharsetIndex::G0),
             C0::SO => self.handler.set_active_charset(CharsetIndex::G1),
+            C1::SS2 => self.handler.single_shift(CharsetIndex::G2),
+            C1::SS3 => self.handler.single_shift(CharsetIndex::G3),
             C1::NEL => self.handler.newline(),
             C1::HTS => self.handler.set_horizontal_tabstop(),
             C1::DECID => self.handler.identify_terminal(self.writer),
@@ -728,20 +1406,86 @@ where
 
     #[inline]
     fn hook(&mut self, params: &[i64], intermediates: &[u8], ignore: bool) {
+        let action = self.state.dcs_action();
+        match (intermediates, action) {
+            // Sixel graphics (`DCS P1 ; P2 ; P3 q`)
+            (b"", Some(b'q')) if !ignore => {
+                self.state.dcs = Some(Dcs::Sixel(sixel::Parser::new()));
+                return;
+            },
+            // DECRQSS (`DCS $ q Pt ST`)
+            (b"$", Some(b'q')) if !ignore => {
+                self.state.dcs = Some(Dcs::RequestStatus(Vec::new()));
+                return;
+            },
+            // XTGETTCAP (`DCS + q Pt ST`)
+            (b"+", Some(b'q')) if !ignore => {
+                self.state.dcs = Some(Dcs::RequestTermcap(Vec::new()));
+                return;
+            },
+            // Synchronized update (`DCS = 1 s` to begin and `DCS = 2 s` to end)
+            (b"=", Some(b's')) if !ignore => match self.state.dcs_last_param() {
+                Some(1) => {
+                    self.state.sync_started = true;
+                    self.handler.set_mode(Mode::SyncUpdate);
+                    return;
+                },
+                Some(2) => {
+                    self.handler.unset_mode(Mode::SyncUpdate);
+                    return;
+                },
+                _ => (),
+            },
+            _ => (),
+        }
+
         debug!(
-            "[unhandled hook] params={:?}, ints: {:?}, ignore: {:?}",
-            params, intermediates, ignore
+            "[unhandled hook] params={:?}, ints: {:?}, ignore: {:?}, action: {:?}",
+            params,
+            intermediates,
+            ignore,
+            action.map(char::from)
         );
     }
 
     #[inline]
     fn put(&mut self, byte: u8) {
-        debug!("[unhandled put] byte={:?}", byte);
+        match self.state.dcs {
+            Some(Dcs::Sixel(ref mut parser)) => parser.put(byte),
+            Some(Dcs::RequestStatus(ref mut payload))
+            | Some(Dcs::RequestTermcap(ref mut payload)) => {
+                if payload.len() < MAX_DCS_REQUEST_LEN {
+                    payload.push(byte);
+                }
+            },
+            None => debug!("[unhandled put] byte={:?}", byte),
+        }
     }
 
     #[inline]
     fn unhook(&mut self) {
-        debug!("[unhandled unhook]");
+        match self.state.dcs.take() {
+            Some(Dcs::Sixel(parser)) => {
+                if let Some(image) = parser.finish() {
+                    self.handler.insert_graphic(image);
+                }
+            },
+            Some(Dcs::RequestStatus(payload)) => {
+                let status = StatusRequest::from_payload(&payload)
+                    .and_then(|request| self.handler.status_string(request));
+                let response = match status {
+                    Some(status) => format!("\x1bP1$r{}\x1b\\", status),
+                    None => String::from("\x1bP0$r\x1b\\"),
+                };
+                let _ = self.writer.write_all(response.as_bytes());
+            },
+            Some(Dcs::RequestTermcap(payload)) => {
+                for name in payload.split(|&byte| byte == b';') {
+                    let _ = self.writer.write_all(termcap_response(name).as_bytes());
+                }
+            },
+            None => debug!("[unhandled unhook]"),
+        }
     }
 
     // TODO replace OSC parsing with parser combinators
@@ -766,23 +1510,53 @@ where
         }
 
         match params[0] {
-            // Set window title
-            b"0" | b"2" => {
+            // Set icon name and window title
+            b"0" | b"1" | b"2" => {
                 if params.len() >= 2 {
                     let title = params[1..]
                         .iter()
                         .flat_map(|x| str::from_utf8(x))
                         .collect::<Vec<&str>>()
                         .join(";");
-                    self.handler.set_title(&title);
+                    if params[0] != b"2" {
+                        self.handler.set_icon_name(&title);
+                    }
+                    if params[0] != b"1" {
+                        self.handler.set_title(&title);
+                    }
                     return;
                 }
                 unhandled(params);
             },
 
-            // Set icon name
-            // This is ignored, since alacritty has no concept of tabs
-            b"1" => (),
+            // Set or end hyperlink
+            b"8" => {
+                if params.len() < 3 {
+                    return unhandled(params);
+                }
+
+                // URIs may contain semicolons, so everything after the link parameters is part
+                // of the URI
+                let uri = params[2..]
+                    .iter()
+                    .flat_map(|x| str::from_utf8(x))
+                    .collect::<Vec<&str>>()
+                    .join(";");
+
+                if uri.is_empty() {
+                    self.handler.set_hyperlink(None);
+                    return;
+                }
+
+                let id = params[1]
+                    .split(|&b| b == b':')
+                    .filter(|param| param.starts_with(b"id=") && param.len() > 3)
+                    .flat_map(|param| str::from_utf8(&param[3..]))
+                    .next()
+                    .map(String::from);
+
+                self.handler.set_hyperlink(Some(Hyperlink { id, uri }));
+            },
 
             // Set color index
             b"4" => {
@@ -799,26 +1573,104 @@ where
                 unhandled(params);
             },
 
-            // Get/set Foreground, Background, Cursor colors
-            b"10" | b"11" | b"12" => {
-                if params.len() >= 2 {
-                    if let Some(mut dynamic_code) = parse_number(params[0]) {
-                        for param in &params[1..] {
-                            // 10 is the first dynamic color, also the foreground
-                            let offset = dynamic_code as usize - 10;
-                            let index = NamedColor::Foreground as usize + offset;
+            // Desktop notification
+            b"9" => {
+                // Numeric parameters are ConEmu subcommands like progress reports, not messages
+                let subcommand = params.get(1).map_or(true, |param| {
+                    !param.is_empty() && param.iter().all(u8::is_ascii_digit)
+                });
+                if subcommand {
+                    return unhandled(params);
+                }
 
-                            // End of setting dynamic colors
-                            if index > NamedColor::Cursor as usize {
-                                unhandled(params);
-                                break;
-                            }
+                let body = params[1..]
+                    .iter()
+                    .flat_map(|x| str::from_utf8(x))
+                    .collect::<Vec<&str>>()
+                    .join(";");
+                self.handler.desktop_notification(Notification { title: None, body });
+            },
 
-                            if let Some(color) = xparse_color(param) {
-                                self.handler.set_color(index, color);
-                            } else if param == b"?" {
-                                self.handler.dynamic_color_sequence(writer, dynamic_code, index);
-                            } else {
+            // Desktop notification with title
+            b"777" => {
+                if params.len() < 3 || params[1] != b"notify" {
+                    return unhandled(params);
+                }
+
+                let title = match str::from_utf8(params[2]) {
+                    Ok(title) => String::from(title),
+                    Err(_) => return unhandled(params),
+                };
+                let body = params[3..]
+                    .iter()
+                    .flat_map(|x| str::from_utf8(x))
+                    .collect::<Vec<&str>>()
+                    .join(";");
+                self.handler.desktop_notification(Notification { title: Some(title), body });
+            },
+
+            // Set working directory
+            b"7" => {
+                // Paths may contain semicolons, so all remaining parameters are part of the URI
+                let uri = params[1..]
+                    .iter()
+                    .flat_map(|x| str::from_utf8(x))
+                    .collect::<Vec<&str>>()
+                    .join(";");
+
+                if uri.is_empty() {
+                    self.handler.set_working_directory(None);
+                    return;
+                }
+
+                match parse_working_directory(&uri) {
+                    Some(directory) => self.handler.set_working_directory(Some(directory)),
+                    None => unhandled(params),
+                }
+            },
+
+            // Shell integration marks
+            b"133" => {
+                if params.len() < 2 {
+                    return unhandled(params);
+                }
+
+                let mark = match params[1] {
+                    b"A" => PromptMark::PromptStart,
+                    b"B" => PromptMark::CommandStart,
+                    b"C" => PromptMark::OutputStart,
+                    b"D" => {
+                        let status = params
+                            .get(2)
+                            .and_then(|status| str::from_utf8(status).ok())
+                            .and_then(|status| status.parse().ok());
+                        PromptMark::CommandEnd(status)
+                    },
+                    _ => return unhandled(params),
+                };
+                self.handler.set_prompt_mark(mark);
+            },
+
+            // Get/set Foreground, Background, Cursor colors
+            b"10" | b"11" | b"12" => {
+                if params.len() >= 2 {
+                    if let Some(mut dynamic_code) = parse_number(params[0]) {
+                        for param in &params[1..] {
+                            // 10 is the first dynamic color, also the foreground
+                            let offset = dynamic_code as usize - 10;
+                            let index = NamedColor::Foreground as usize + offset;
+
+                            // End of setting dynamic colors
+                            if index > NamedColor::Cursor as usize {
+                                unhandled(params);
+                                break;
+                            }
+
+                            if let Some(color) = xparse_color(param) {
+                                self.handler.set_color(index, color);
+                            } else if param == b"?" {
+                                self.handler.dynamic_color_sequence(writer, dynamic_code, index);
+                            } else {
                                 unhandled(params);
                             }
                             dynamic_code += 1;
@@ -835,13 +1687,13 @@ where
                     && params[1].len() >= 13
                     && params[1][0..12] == *b"CursorShape="
                 {
-                    let style = match params[1][12] as char {
-                        '0' => CursorStyle::Block,
-                        '1' => CursorStyle::Beam,
-                        '2' => CursorStyle::Underline,
+                    let shape = match params[1][12] as char {
+                        '0' => CursorShape::Block,
+                        '1' => CursorShape::Beam,
+                        '2' => CursorShape::Underline,
                         _ => return unhandled(params),
                     };
-                    self.handler.set_cursor_style(Some(style));
+                    self.handler.set_cursor_shape(shape);
                     return;
                 }
                 unhandled(params);
@@ -916,7 +1768,29 @@ where
             };
         }
 
-        if has_ignored_intermediates || intermediates.len() > 1 {
+        // DECRQM (CSI ? Ps $ p / CSI Ps $ p) -- Request Mode
+        if action == 'p'
+            && (intermediates == b"?$" || intermediates == b"$")
+            && !has_ignored_intermediates
+        {
+            let private = intermediates[0] == b'?';
+            let num = arg_or_default!(idx: 0, default: 0);
+            let intermediate = if private { Some(&b'?') } else { None };
+            let state = Mode::from_primitive(intermediate, num)
+                .map(|mode| self.handler.mode_state(&mode))
+                .unwrap_or(ModeState::NotRecognized);
+            let prefix = if private { "?" } else { "" };
+            let response = format!("\x1b[{}{};{}$y", prefix, num, state as u8);
+            let _ = self.writer.write_all(response.as_bytes());
+            return;
+        }
+
+        // Only SGR supports colon separated subparameters
+        let subparameters = self.state.subparameters;
+        if has_ignored_intermediates
+            || intermediates.len() > 1
+            || (subparameters != 0 && action != 'm')
+        {
             unhandled!();
             return;
         }
@@ -979,21 +1853,26 @@ where
                 handler.goto(Line(y - 1), Column(x - 1));
             },
             ('I', None) => handler.move_forward_tabs(arg_or_default!(idx: 0, default: 1)),
-            ('J', None) => {
+            ('J', intermediate @ None) | ('J', intermediate @ Some(b'?')) => {
+                let selective = intermediate.is_some();
                 let mode = match arg_or_default!(idx: 0, default: 0) {
                     0 => ClearMode::Below,
                     1 => ClearMode::Above,
                     2 => ClearMode::All,
-                    3 => ClearMode::Saved,
+                    3 if !selective => ClearMode::Saved,
                     _ => {
                         unhandled!();
                         return;
                     },
                 };
 
-                handler.clear_screen(mode);
+                if selective {
+                    handler.selective_clear_screen(mode);
+                } else {
+                    handler.clear_screen(mode);
+                }
             },
-            ('K', None) => {
+            ('K', intermediate @ None) | ('K', intermediate @ Some(b'?')) => {
                 let mode = match arg_or_default!(idx: 0, default: 0) {
                     0 => LineClearMode::Right,
                     1 => LineClearMode::Left,
@@ -1004,13 +1883,61 @@ where
                     },
                 };
 
-                handler.clear_line(mode);
+                if intermediate.is_some() {
+                    handler.selective_clear_line(mode);
+                } else {
+                    handler.clear_line(mode);
+                }
             },
             ('S', None) => handler.scroll_up(Line(arg_or_default!(idx: 0, default: 1) as usize)),
-            ('t', None) => match arg_or_default!(idx: 0, default: 1) as usize {
-                22 => handler.push_title(),
-                23 => handler.pop_title(),
-                _ => unhandled!(),
+            ('t', None) => {
+                // Size parameters of zero keep the current size
+                let size = |idx: usize| args.get(idx).filter(|&&v| v > 0).map(|&v| v as usize);
+
+                match arg_or_default!(idx: 0, default: 1) as usize {
+                    1 => handler.window_operation(WindowOperation::Deiconify),
+                    2 => handler.window_operation(WindowOperation::Iconify),
+                    4 => handler.window_operation(WindowOperation::ResizePixels {
+                        width: size(2),
+                        height: size(1),
+                    }),
+                    5 => handler.window_operation(WindowOperation::Raise),
+                    8 => handler.window_operation(WindowOperation::ResizeChars {
+                        lines: size(1),
+                        cols: size(2),
+                    }),
+                    14 => handler.report_window(writer, WindowReport::TextAreaSizePixels),
+                    16 => handler.report_window(writer, WindowReport::CellSizePixels),
+                    18 => handler.report_window(writer, WindowReport::TextAreaSizeChars),
+                    19 => handler.report_window(writer, WindowReport::ScreenSizeChars),
+                    20 => handler.report_window(writer, WindowReport::IconLabel),
+                    21 => handler.report_window(writer, WindowReport::Title),
+                    22 => handler.push_title(),
+                    23 => handler.pop_title(),
+                    _ => unhandled!(),
+                }
+            },
+            ('t', Some(b'$')) => {
+                // DECRARA (CSI Pt ; Pl ; Pb ; Pr ; Ps $ t) -- Reverse Attributes in Rectangular Area
+                let mut attrs = Vec::new();
+                for arg in rectangle_attr_args(args) {
+                    match arg {
+                        0 => attrs.extend_from_slice(&[
+                            Attr::Bold,
+                            Attr::Underline,
+                            Attr::BlinkSlow,
+                            Attr::Reverse,
+                        ]),
+                        1 => attrs.push(Attr::Bold),
+                        4 => attrs.push(Attr::Underline),
+                        5 => attrs.push(Attr::BlinkSlow),
+                        7 => attrs.push(Attr::Reverse),
+                        8 => attrs.push(Attr::Hidden),
+                        _ => unhandled!(),
+                    }
+                }
+
+                handler.reverse_rectangle_attributes(Rectangle::from_args(args), &attrs);
             },
             ('T', None) => handler.scroll_down(Line(arg_or_default!(idx: 0, default: 1) as usize)),
             ('L', None) => {
@@ -1041,6 +1968,10 @@ where
             ('h', intermediate) => {
                 for arg in args {
                     match Mode::from_primitive(intermediate, *arg) {
+                        Some(Mode::SyncUpdate) => {
+                            self.state.sync_started = true;
+                            handler.set_mode(Mode::SyncUpdate);
+                        },
                         Some(mode) => handler.set_mode(mode),
                         None => {
                             unhandled!();
@@ -1049,11 +1980,30 @@ where
                     }
                 }
             },
+            ('m', Some(b'>')) => {
+                // XTMODKEYS (CSI > Pp ; Pv m) -- Set key modifier options
+                if args.get(0) != Some(&4) {
+                    unhandled!();
+                    return;
+                }
+
+                let modify_other_keys = match args.get(1).unwrap_or(&0) {
+                    0 => ModifyOtherKeys::Reset,
+                    1 => ModifyOtherKeys::EnableExceptWellDefined,
+                    2 => ModifyOtherKeys::EnableAll,
+                    _ => {
+                        unhandled!();
+                        return;
+                    },
+                };
+
+                handler.set_modify_other_keys(modify_other_keys);
+            },
             ('m', None) => {
                 if args.is_empty() {
                     handler.terminal_attribute(Attr::Reset);
                 } else {
-                    for attr in attrs_from_sgr_parameters(args) {
+                    for attr in attrs_from_sgr_parameters(args, subparameters) {
                         match attr {
                             Some(attr) => handler.terminal_attribute(attr),
                             None => {
@@ -1069,27 +2019,108 @@ where
             },
             ('q', Some(b' ')) => {
                 // DECSCUSR (CSI Ps SP q) -- Set Cursor Style
-                let style = match arg_or_default!(idx: 0, default: 0) {
+                let ps = arg_or_default!(idx: 0, default: 0);
+                let shape = match ps {
                     0 => None,
-                    1 | 2 => Some(CursorStyle::Block),
-                    3 | 4 => Some(CursorStyle::Underline),
-                    5 | 6 => Some(CursorStyle::Beam),
+                    1 | 2 => Some(CursorShape::Block),
+                    3 | 4 => Some(CursorShape::Underline),
+                    5 | 6 => Some(CursorShape::Beam),
                     _ => {
                         unhandled!();
                         return;
                     },
                 };
 
+                // Odd values select a blinking cursor
+                let style = shape.map(|shape| CursorStyle { shape, blinking: ps % 2 == 1 });
                 handler.set_cursor_style(style);
             },
+            ('q', Some(b'"')) => {
+                // DECSCA (CSI Ps " q) -- Select Character Protection Attribute
+                match arg_or_default!(idx: 0, default: 0) {
+                    0 | 2 => handler.set_character_protection(false),
+                    1 => handler.set_character_protection(true),
+                    _ => unhandled!(),
+                }
+            },
             ('r', None) => {
                 let top = arg_or_default!(idx: 0, default: 1) as usize;
                 let bottom = arg_or_default!(idx: 1, default: handler.lines().0 as _) as usize;
 
                 handler.set_scrolling_region(top, bottom);
             },
-            ('s', None) => handler.save_cursor_position(),
+            ('r', Some(b'$')) => {
+                // DECCARA (CSI Pt ; Pl ; Pb ; Pr ; Ps $ r) -- Change Attributes in Rectangular Area
+                let attrs = rectangle_attrs_from_sgr_parameters(rectangle_attr_args(args));
+                handler.change_rectangle_attributes(Rectangle::from_args(args), &attrs);
+            },
+            ('s', None) => {
+                let left = arg_or_default!(idx: 0, default: 1) as usize;
+                let right = arg_or_default!(idx: 1, default: handler.cols().0 as _) as usize;
+
+                handler.set_left_right_margins(left, right);
+            },
             ('u', None) => handler.restore_cursor_position(),
+            ('u', Some(b'?')) => handler.report_keyboard_mode(writer),
+            ('u', Some(b'=')) => {
+                let mode = KeyboardModes::from_bits_truncate(*args.get(0).unwrap_or(&0) as u8);
+                let behavior = match arg_or_default!(idx: 1, default: 1) {
+                    1 => KeyboardModesApplyBehavior::Replace,
+                    2 => KeyboardModesApplyBehavior::Union,
+                    3 => KeyboardModesApplyBehavior::Difference,
+                    _ => {
+                        unhandled!();
+                        return;
+                    },
+                };
+
+                handler.set_keyboard_mode(mode, behavior);
+            },
+            ('u', Some(b'>')) => {
+                let mode = KeyboardModes::from_bits_truncate(*args.get(0).unwrap_or(&0) as u8);
+                handler.push_keyboard_mode(mode);
+            },
+            ('u', Some(b'<')) => {
+                handler.pop_keyboard_modes(arg_or_default!(idx: 0, default: 1) as u16)
+            },
+            ('v', Some(b'$')) => {
+                // DECCRA (CSI Pts ; Pls ; Pbs ; Prs ; Pps ; Ptd ; Pld ; Ppd $ v) -- Copy
+                // Rectangular Area
+                let line = arg_or_default!(idx: 5, default: 1) as usize;
+                let col = arg_or_default!(idx: 6, default: 1) as usize;
+                handler.copy_rectangle(Rectangle::from_args(args), Line(line - 1), Column(col - 1));
+            },
+            ('x', Some(b'$')) => {
+                // DECFRA (CSI Pch ; Pt ; Pl ; Pb ; Pr $ x) -- Fill Rectangular Area
+                let c = match args.get(0) {
+                    Some(&c) if (32..=126).contains(&c) || (160..=255).contains(&c) => c as u8,
+                    _ => {
+                        unhandled!();
+                        return;
+                    },
+                };
+
+                handler.fill_rectangle(c as char, Rectangle::from_args(&args[1..]));
+            },
+            ('x', Some(b'*')) => {
+                // DECSACE (CSI Ps * x) -- Select Attribute Change Extent
+                match arg_or_default!(idx: 0, default: 0) {
+                    0 | 1 => handler.set_attribute_change_extent(AttributeChangeExtent::Stream),
+                    2 => handler.set_attribute_change_extent(AttributeChangeExtent::Rectangle),
+                    _ => unhandled!(),
+                }
+            },
+            ('y', Some(b'*')) => {
+                // DECRQCRA (CSI Pid ; Pp ; Pt ; Pl ; Pb ; Pr * y) -- Request Checksum of
+                // Rectangular Area
+                let id = args.get(0).copied().unwrap_or(0);
+                let area = Rectangle::from_args(args.get(2..).unwrap_or(&[]));
+                handler.report_rectangle_checksum(writer, id, area);
+            },
+            ('z', Some(b'$')) => {
+                // DECERA (CSI Pt ; Pl ; Pb ; Pr $ z) -- Erase Rectangular Area
+                handler.erase_rectangle(Rectangle::from_args(args));
+            },
             _ => unhandled!(),
         }
     }
@@ -1106,7 +2137,7 @@ where
         }
 
         macro_rules! configure_charset {
-            ($charset:path, $intermediate:expr) => {{
+            ($charset:expr, $intermediate:expr) => {{
                 let index: CharsetIndex = match $intermediate {
                     Some(b'(') => CharsetIndex::G0,
                     Some(b')') => CharsetIndex::G1,
@@ -1122,7 +2153,6 @@ where
         }
 
         match (byte, intermediates.get(0)) {
-            (b'B', intermediate) => configure_charset!(StandardCharset::Ascii, intermediate),
             (b'D', None) => self.handler.linefeed(),
             (b'E', None) => {
                 self.handler.linefeed();
@@ -1130,24 +2160,66 @@ where
             },
             (b'H', None) => self.handler.set_horizontal_tabstop(),
             (b'M', None) => self.handler.reverse_index(),
+            (b'N', None) => self.handler.single_shift(CharsetIndex::G2),
+            (b'O', None) => self.handler.single_shift(CharsetIndex::G3),
             (b'Z', None) => self.handler.identify_terminal(self.writer),
             (b'c', None) => self.handler.reset_state(),
-            (b'0', intermediate) => {
-                configure_charset!(StandardCharset::SpecialCharacterAndLineDrawing, intermediate)
-            },
+            (b'n', None) => self.handler.set_active_charset(CharsetIndex::G2),
+            (b'o', None) => self.handler.set_active_charset(CharsetIndex::G3),
             (b'7', None) => self.handler.save_cursor_position(),
+            (b'3', Some(b'#')) => self.handler.set_line_size(LineSize::DoubleHeightTop),
+            (b'4', Some(b'#')) => self.handler.set_line_size(LineSize::DoubleHeightBottom),
+            (b'5', Some(b'#')) => self.handler.set_line_size(LineSize::Normal),
+            (b'6', Some(b'#')) => self.handler.set_line_size(LineSize::DoubleWidth),
             (b'8', Some(b'#')) => self.handler.decaln(),
             (b'8', None) => self.handler.restore_cursor_position(),
             (b'=', None) => self.handler.set_keypad_application_mode(),
             (b'>', None) => self.handler.unset_keypad_application_mode(),
             // String terminator, do nothing (parser handles as string terminator)
             (b'\\', None) => (),
+            (_, intermediate @ Some(b'('..=b'+')) => {
+                match StandardCharset::from_designator(&intermediates[1..], byte) {
+                    Some(charset) => configure_charset!(charset, intermediate),
+                    None => unhandled!(),
+                }
+            },
             _ => unhandled!(),
         }
     }
 }
 
-fn attrs_from_sgr_parameters(parameters: &[i64]) -> Vec<Option<Attr>> {
+/// Attribute parameters of DECCARA and DECRARA, which default to zero
+fn rectangle_attr_args(args: &[i64]) -> &[i64] {
+    match args.get(4..) {
+        Some(parameters) if !parameters.is_empty() => parameters,
+        _ => &[0],
+    }
+}
+
+/// Parse the attributes which DECCARA can change
+fn rectangle_attrs_from_sgr_parameters(parameters: &[i64]) -> Vec<Attr> {
+    attrs_from_sgr_parameters(parameters, 0)
+        .into_iter()
+        .filter_map(|attr| match attr {
+            Some(attr @ Attr::Reset)
+            | Some(attr @ Attr::Bold)
+            | Some(attr @ Attr::Underline)
+            | Some(attr @ Attr::BlinkSlow)
+            | Some(attr @ Attr::Reverse)
+            | Some(attr @ Attr::Hidden)
+            | Some(attr @ Attr::CancelBoldDim)
+            | Some(attr @ Attr::CancelUnderline)
+            | Some(attr @ Attr::CancelBlink)
+            | Some(attr @ Attr::CancelReverse)
+            | Some(attr @ Attr::CancelHidden) => Some(attr),
+            _ => None,
+        })
+        .collect()
+}
+
+fn attrs_from_sgr_parameters(parameters: &[i64], subparameters: u32) -> Vec<Option<Attr>> {
+    let is_subparameter = |index: usize| index < 32 && subparameters & (1 << index) != 0;
+
     // Sometimes a C-style for loop is just what you need
     let mut i = 0; // C-for initializer
     let mut attrs = Vec::with_capacity(parameters.len());
@@ -1157,12 +2229,27 @@ fn attrs_from_sgr_parameters(parameters: &[i64]) -> Vec<Option<Attr>> {
             break;
         }
 
+        // Colon separated subparameters of the current parameter
+        let mut end = i + 1;
+        while end < parameters.len() && is_subparameter(end) {
+            end += 1;
+        }
+        let subparams = &parameters[i + 1..end];
+
         let attr = match parameters[i] {
             0 => Some(Attr::Reset),
             1 => Some(Attr::Bold),
             2 => Some(Attr::Dim),
             3 => Some(Attr::Italic),
-            4 => Some(Attr::Underline),
+            4 => match subparams.get(0) {
+                None | Some(1) => Some(Attr::Underline),
+                Some(0) => Some(Attr::CancelUnderline),
+                Some(2) => Some(Attr::DoubleUnderline),
+                Some(3) => Some(Attr::Undercurl),
+                Some(4) => Some(Attr::DottedUnderline),
+                Some(5) => Some(Attr::DashedUnderline),
+                _ => None,
+            },
             5 => Some(Attr::BlinkSlow),
             6 => Some(Attr::BlinkFast),
             7 => Some(Attr::Reverse),
@@ -1184,6 +2271,9 @@ fn attrs_from_sgr_parameters(parameters: &[i64]) -> Vec<Option<Attr>> {
             35 => Some(Attr::Foreground(Color::Named(NamedColor::Magenta))),
             36 => Some(Attr::Foreground(Color::Named(NamedColor::Cyan))),
             37 => Some(Attr::Foreground(Color::Named(NamedColor::White))),
+            38 if !subparams.is_empty() => {
+                parse_sgr_subparameter_color(subparams).map(Attr::Foreground)
+            },
             38 => {
                 let mut start = 0;
                 if let Some(color) = parse_sgr_color(&parameters[i..], &mut start) {
@@ -1202,6 +2292,9 @@ fn attrs_from_sgr_parameters(parameters: &[i64]) -> Vec<Option<Attr>> {
             45 => Some(Attr::Background(Color::Named(NamedColor::Magenta))),
             46 => Some(Attr::Background(Color::Named(NamedColor::Cyan))),
             47 => Some(Attr::Background(Color::Named(NamedColor::White))),
+            48 if !subparams.is_empty() => {
+                parse_sgr_subparameter_color(subparams).map(Attr::Background)
+            },
             48 => {
                 let mut start = 0;
                 if let Some(color) = parse_sgr_color(&parameters[i..], &mut start) {
@@ -1212,6 +2305,20 @@ fn attrs_from_sgr_parameters(parameters: &[i64]) -> Vec<Option<Attr>> {
                 }
             },
             49 => Some(Attr::Background(Color::Named(NamedColor::Background))),
+            53 => Some(Attr::Overline),
+            55 => Some(Attr::CancelOverline),
+            58 if !subparams.is_empty() => parse_sgr_subparameter_color(subparams)
+                .map(|color| Attr::UnderlineColor(Some(color))),
+            58 => {
+                let mut start = 0;
+                if let Some(color) = parse_sgr_color(&parameters[i..], &mut start) {
+                    i += start;
+                    Some(Attr::UnderlineColor(Some(color)))
+                } else {
+                    None
+                }
+            },
+            59 => Some(Attr::UnderlineColor(None)),
             90 => Some(Attr::Foreground(Color::Named(NamedColor::BrightBlack))),
             91 => Some(Attr::Foreground(Color::Named(NamedColor::BrightRed))),
             92 => Some(Attr::Foreground(Color::Named(NamedColor::BrightGreen))),
@@ -1233,7 +2340,7 @@ fn attrs_from_sgr_parameters(parameters: &[i64]) -> Vec<Option<Attr>> {
 
         attrs.push(attr);
 
-        i += 1; // C-for expr
+        i = max(i + 1, end); // C-for expr
     }
     attrs
 }
@@ -1289,6 +2396,26 @@ fn parse_sgr_color(attrs: &[i64], i: &mut usize) -> Option<Color> {
     }
 }
 
+/// Parse a color specified with colon separated subparameters, like `38:2::255:0:0`
+fn parse_sgr_subparameter_color(subparams: &[i64]) -> Option<Color> {
+    // Rebuild the semicolon separated form, the first parameter is ignored by `parse_sgr_color`
+    let mut start = 0;
+    match subparams.get(0) {
+        // The color space ID in front of the RGB components is optional
+        Some(2) if subparams.len() >= 5 => {
+            parse_sgr_color(&[0, 2, subparams[2], subparams[3], subparams[4]], &mut start)
+        },
+        Some(2) if subparams.len() == 4 => {
+            parse_sgr_color(&[0, 2, subparams[1], subparams[2], subparams[3]], &mut start)
+        },
+        Some(5) if subparams.len() >= 2 => parse_sgr_color(&[0, 5, subparams[1]], &mut start),
+        _ => {
+            debug!("Unexpected color subparameters: {:?}", subparams);
+            None
+        },
+    }
+}
+
 /// C0 set of 7-bit control characters (from ANSI X3.4-1977).
 #[allow(non_snake_case)]
 pub mod C0 {
@@ -1439,21 +2566,102 @@ pub mod C1 {
 #[cfg(test)]
 mod tests {
     use super::{
-        parse_number, xparse_color, Attr, CharsetIndex, Color, Handler, Processor, StandardCharset,
-        TermInfo,
+        parse_number, parse_working_directory, xparse_color, Attr, AttributeChangeExtent,
+        CharsetIndex, ClearMode, Color, CursorShape, CursorStyle, Handler, Hyperlink, Image,
+        KeyboardModes, KeyboardModesApplyBehavior, LineClearMode, LineSize, Mode, ModeState,
+        ModifyOtherKeys, Notification, Processor, PromptMark, Rectangle, StandardCharset,
+        StatusRequest, TermInfo, WindowOperation, WindowReport, WorkingDirectory,
     };
     use crate::index::{Column, Line};
     use crate::term::color::Rgb;
     use std::io;
+    use std::path::PathBuf;
 
     struct MockHandler {
         index: CharsetIndex,
         charset: StandardCharset,
         attr: Option<Attr>,
         identity_reported: bool,
+        hyperlink: Option<Hyperlink>,
+        graphic: Option<Image>,
+        sync_update: bool,
+        keyboard_modes: Vec<KeyboardModes>,
+        modify_other_keys: Option<ModifyOtherKeys>,
+        cursor_style: Option<CursorStyle>,
+        single_shift: Option<CharsetIndex>,
+        working_directory: Option<WorkingDirectory>,
+        prompt_marks: Vec<PromptMark>,
+        notifications: Vec<Notification>,
+        window_reports: Vec<WindowReport>,
+        window_operations: Vec<WindowOperation>,
+        title: Option<String>,
+        icon_name: Option<String>,
+        character_protection: bool,
+        selective_line_clears: Vec<LineClearMode>,
+        selective_screen_clears: Vec<ClearMode>,
+        copied_rectangle: Option<(Rectangle, Line, Column)>,
+        filled_rectangle: Option<(char, Rectangle)>,
+        erased_rectangle: Option<Rectangle>,
+        changed_attrs: Vec<Attr>,
+        reversed_attrs: Vec<Attr>,
+        attribute_change_extent: Option<AttributeChangeExtent>,
+        checksum_request: Option<(i64, Rectangle)>,
+        line_sizes: Vec<LineSize>,
     }
 
     impl Handler for MockHandler {
+        fn set_title(&mut self, title: &str) {
+            self.title = Some(title.to_owned());
+        }
+
+        fn set_icon_name(&mut self, icon_name: &str) {
+            self.icon_name = Some(icon_name.to_owned());
+        }
+
+        fn selective_clear_line(&mut self, mode: LineClearMode) {
+            self.selective_line_clears.push(mode);
+        }
+
+        fn selective_clear_screen(&mut self, mode: ClearMode) {
+            self.selective_screen_clears.push(mode);
+        }
+
+        fn set_character_protection(&mut self, protected: bool) {
+            self.character_protection = protected;
+        }
+
+        fn copy_rectangle(&mut self, area: Rectangle, line: Line, col: Column) {
+            self.copied_rectangle = Some((area, line, col));
+        }
+
+        fn fill_rectangle(&mut self, c: char, area: Rectangle) {
+            self.filled_rectangle = Some((c, area));
+        }
+
+        fn erase_rectangle(&mut self, area: Rectangle) {
+            self.erased_rectangle = Some(area);
+        }
+
+        fn change_rectangle_attributes(&mut self, _: Rectangle, attrs: &[Attr]) {
+            self.changed_attrs = attrs.to_vec();
+        }
+
+        fn reverse_rectangle_attributes(&mut self, _: Rectangle, attrs: &[Attr]) {
+            self.reversed_attrs = attrs.to_vec();
+        }
+
+        fn set_attribute_change_extent(&mut self, extent: AttributeChangeExtent) {
+            self.attribute_change_extent = Some(extent);
+        }
+
+        fn report_rectangle_checksum<W: io::Write>(&mut self, _: &mut W, id: i64, area: Rectangle) {
+            self.checksum_request = Some((id, area));
+        }
+
+        fn set_line_size(&mut self, size: LineSize) {
+            self.line_sizes.push(size);
+        }
+
         fn terminal_attribute(&mut self, attr: Attr) {
             self.attr = Some(attr);
         }
@@ -1467,6 +2675,10 @@ mod tests {
             self.index = index;
         }
 
+        fn single_shift(&mut self, index: CharsetIndex) {
+            self.single_shift = Some(index);
+        }
+
         fn identify_terminal<W: io::Write>(&mut self, _: &mut W) {
             self.identity_reported = true;
         }
@@ -1474,6 +2686,87 @@ mod tests {
         fn reset_state(&mut self) {
             *self = Self::default();
         }
+
+        fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
+            self.hyperlink = hyperlink;
+        }
+
+        fn set_working_directory(&mut self, directory: Option<WorkingDirectory>) {
+            self.working_directory = directory;
+        }
+
+        fn set_prompt_mark(&mut self, mark: PromptMark) {
+            self.prompt_marks.push(mark);
+        }
+
+        fn desktop_notification(&mut self, notification: Notification) {
+            self.notifications.push(notification);
+        }
+
+        fn report_window<W: io::Write>(&mut self, _: &mut W, report: WindowReport) {
+            self.window_reports.push(report);
+        }
+
+        fn window_operation(&mut self, operation: WindowOperation) {
+            self.window_operations.push(operation);
+        }
+
+        fn insert_graphic(&mut self, image: Image) {
+            self.graphic = Some(image);
+        }
+
+        fn set_mode(&mut self, mode: Mode) {
+            if mode == Mode::SyncUpdate {
+                self.sync_update = true;
+            }
+        }
+
+        fn unset_mode(&mut self, mode: Mode) {
+            if mode == Mode::SyncUpdate {
+                self.sync_update = false;
+            }
+        }
+
+        fn push_keyboard_mode(&mut self, mode: KeyboardModes) {
+            self.keyboard_modes.push(mode);
+        }
+
+        fn pop_keyboard_modes(&mut self, to_pop: u16) {
+            for _ in 0..to_pop {
+                self.keyboard_modes.pop();
+            }
+        }
+
+        fn set_keyboard_mode(&mut self, mode: KeyboardModes, behavior: KeyboardModesApplyBehavior) {
+            let active_mode = self.keyboard_modes.pop().unwrap_or(KeyboardModes::NO_MODE);
+            self.keyboard_modes.push(match behavior {
+                KeyboardModesApplyBehavior::Replace => mode,
+                KeyboardModesApplyBehavior::Union => active_mode | mode,
+                KeyboardModesApplyBehavior::Difference => active_mode - mode,
+            });
+        }
+
+        fn set_modify_other_keys(&mut self, modify_other_keys: ModifyOtherKeys) {
+            self.modify_other_keys = Some(modify_other_keys);
+        }
+
+        fn set_cursor_style(&mut self, style: Option<CursorStyle>) {
+            self.cursor_style = style;
+        }
+
+        fn mode_state(&self, mode: &Mode) -> ModeState {
+            match mode {
+                Mode::SyncUpdate => ModeState::from(self.sync_update),
+                _ => ModeState::PermanentlyReset,
+            }
+        }
+
+        fn status_string(&self, request: StatusRequest) -> Option<String> {
+            match request {
+                StatusRequest::Sgr => Some(String::from("0;1m")),
+                _ => None,
+            }
+        }
     }
 
     impl TermInfo for MockHandler {
@@ -1493,6 +2786,31 @@ mod tests {
                 charset: StandardCharset::Ascii,
                 attr: None,
                 identity_reported: false,
+                hyperlink: None,
+                graphic: None,
+                sync_update: false,
+                keyboard_modes: Vec::new(),
+                modify_other_keys: None,
+                cursor_style: None,
+                single_shift: None,
+                working_directory: None,
+                prompt_marks: Vec::new(),
+                notifications: Vec::new(),
+                window_reports: Vec::new(),
+                window_operations: Vec::new(),
+                title: None,
+                icon_name: None,
+                character_protection: false,
+                selective_line_clears: Vec::new(),
+                selective_screen_clears: Vec::new(),
+                copied_rectangle: None,
+                filled_rectangle: None,
+                erased_rectangle: None,
+                changed_attrs: Vec::new(),
+                reversed_attrs: Vec::new(),
+                attribute_change_extent: None,
+                checksum_request: None,
+                line_sizes: Vec::new(),
             }
         }
     }
@@ -1589,6 +2907,529 @@ mod tests {
         assert_eq!(handler.attr, Some(Attr::Foreground(Color::Spec(spec))));
     }
 
+    #[test]
+    fn parse_underline_styles() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        let mut parse = |bytes: &[u8]| {
+            for byte in bytes {
+                parser.advance(&mut handler, *byte, &mut io::sink());
+            }
+            handler.attr.take()
+        };
+
+        assert_eq!(parse(b"\x1b[4m"), Some(Attr::Underline));
+        assert_eq!(parse(b"\x1b[4:0m"), Some(Attr::CancelUnderline));
+        assert_eq!(parse(b"\x1b[4:2m"), Some(Attr::DoubleUnderline));
+        assert_eq!(parse(b"\x1b[4:3m"), Some(Attr::Undercurl));
+        assert_eq!(parse(b"\x1b[4:4m"), Some(Attr::DottedUnderline));
+        assert_eq!(parse(b"\x1b[4:5m"), Some(Attr::DashedUnderline));
+        assert_eq!(parse(b"\x1b[53m"), Some(Attr::Overline));
+        assert_eq!(parse(b"\x1b[1;4:3m"), Some(Attr::Undercurl));
+
+        // Subparameters must not be mistaken for separate attributes
+        assert_eq!(parse(b"\x1b[4:3;3m"), Some(Attr::Italic));
+        assert_eq!(parse(b"\x1b[4;3m"), Some(Attr::Italic));
+    }
+
+    #[test]
+    fn parse_underline_color() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        let mut parse = |bytes: &[u8]| {
+            for byte in bytes {
+                parser.advance(&mut handler, *byte, &mut io::sink());
+            }
+            handler.attr.take()
+        };
+
+        let spec = Color::Spec(Rgb { r: 255, g: 0, b: 128 });
+        assert_eq!(parse(b"\x1b[58:2::255:0:128m"), Some(Attr::UnderlineColor(Some(spec))));
+        assert_eq!(parse(b"\x1b[58:2:255:0:128m"), Some(Attr::UnderlineColor(Some(spec))));
+        assert_eq!(parse(b"\x1b[58;2;255;0;128m"), Some(Attr::UnderlineColor(Some(spec))));
+        assert_eq!(parse(b"\x1b[58:5:3m"), Some(Attr::UnderlineColor(Some(Color::Indexed(3)))));
+        assert_eq!(parse(b"\x1b[59m"), Some(Attr::UnderlineColor(None)));
+        assert_eq!(
+            parse(b"\x1b[38:2::1:2:3m"),
+            Some(Attr::Foreground(Color::Spec(Rgb { r: 1, g: 2, b: 3 })))
+        );
+    }
+
+    #[test]
+    fn parse_osc8_hyperlink() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b]8;foo=bar:id=1;https://example.org/?a=1;b=2\x07" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let hyperlink = Hyperlink {
+            id: Some(String::from("1")),
+            uri: String::from("https://example.org/?a=1;b=2"),
+        };
+        assert_eq!(handler.hyperlink, Some(hyperlink));
+
+        for byte in b"\x1b]8;;\x1b\\" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.hyperlink, None);
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn parse_osc7_working_directory() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b]7;file:///home/user/a%20b;c\x07" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let directory = WorkingDirectory { host: None, path: PathBuf::from("/home/user/a b;c") };
+        assert_eq!(handler.working_directory, Some(directory));
+
+        for byte in b"\x1b]7;file://remote.example.org/tmp/%C3%A9\x1b\\" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let directory = WorkingDirectory {
+            host: Some(String::from("remote.example.org")),
+            path: PathBuf::from("/tmp/é"),
+        };
+        assert_eq!(handler.working_directory, Some(directory));
+
+        for byte in b"\x1b]7;file://localhost/tmp\x07" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let directory = WorkingDirectory { host: None, path: PathBuf::from("/tmp") };
+        assert_eq!(handler.working_directory, Some(directory));
+
+        for byte in b"\x1b]7;\x07" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.working_directory, None);
+    }
+
+    #[test]
+    fn parse_osc7_invalid_uri() {
+        assert_eq!(parse_working_directory("http://example.org/tmp"), None);
+        assert_eq!(parse_working_directory("/tmp"), None);
+        assert_eq!(parse_working_directory("file://host"), None);
+        assert_eq!(parse_working_directory("file:///tmp/%C"), None);
+        assert_eq!(parse_working_directory("file:///tmp/%FF"), None);
+    }
+
+    #[test]
+    fn parse_osc133_prompt_marks() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        let bytes: &[u8] = b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07\x1b]133;D;1\x07\
+                             \x1b]133;D\x07\x1b]133;D;x;aid=1\x1b\\\x1b]133;E\x07";
+        for byte in bytes {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.prompt_marks, vec![
+            PromptMark::PromptStart,
+            PromptMark::CommandStart,
+            PromptMark::OutputStart,
+            PromptMark::CommandEnd(Some(1)),
+            PromptMark::CommandEnd(None),
+            PromptMark::CommandEnd(None),
+        ]);
+    }
+
+    #[test]
+    fn parse_desktop_notifications() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        let bytes: &[u8] = b"\x1b]9;Build done; 0 errors\x07\x1b]777;notify;make;Build failed\x1b\\\
+                             \x1b]777;other;x\x07\x1b]9\x07";
+        for byte in bytes {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.notifications, vec![
+            Notification { title: None, body: String::from("Build done; 0 errors") },
+            Notification { title: Some(String::from("make")), body: String::from("Build failed") },
+        ]);
+    }
+
+    #[test]
+    fn ignore_conemu_subcommands() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b]9;4;1;50\x07\x1b]9;4;0\x1b\\\x1b]9;12\x07" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert!(handler.notifications.is_empty());
+    }
+
+    #[test]
+    fn parse_title_and_icon_name() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b]0;both\x07\x1b]2;title;with;semicolons\x07" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.title, Some(String::from("title;with;semicolons")));
+        assert_eq!(handler.icon_name, Some(String::from("both")));
+
+        for byte in b"\x1b]1;icon\x07" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.title, Some(String::from("title;with;semicolons")));
+        assert_eq!(handler.icon_name, Some(String::from("icon")));
+    }
+
+    #[test]
+    fn parse_selective_erase() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b[1\"q" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert!(handler.character_protection);
+
+        for byte in b"\x1b[?J\x1b[?1J\x1b[?2J\x1b[?3J\x1b[?K\x1b[?1K\x1b[?2K\x1b[J\x1b[K" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.selective_screen_clears, vec![
+            ClearMode::Below,
+            ClearMode::Above,
+            ClearMode::All,
+        ]);
+        assert_eq!(handler.selective_line_clears, vec![
+            LineClearMode::Right,
+            LineClearMode::Left,
+            LineClearMode::All,
+        ]);
+
+        for byte in b"\x1b[2\"q" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert!(!handler.character_protection);
+    }
+
+    #[test]
+    fn parse_rectangle_operations() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        let bytes: &[u8] = b"\x1b[2;3;4;5;1;6;7;1$v\x1b[88;2;;4$x\x1b[$z\x1b[;;;;1;24;0$r\
+                             \x1b[1;1;1;1$t\x1b[2*x\x1b[9;1;2;2;3;3*y";
+        for byte in bytes {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let area = |top, left, bottom: Option<usize>, right: Option<usize>| Rectangle {
+            top: Line(top),
+            left: Column(left),
+            bottom: bottom.map(Line),
+            right: right.map(Column),
+        };
+
+        assert_eq!(
+            handler.copied_rectangle,
+            Some((area(1, 2, Some(3), Some(4)), Line(5), Column(6)))
+        );
+        assert_eq!(handler.filled_rectangle, Some(('X', area(1, 0, Some(3), None))));
+        assert_eq!(handler.erased_rectangle, Some(area(0, 0, None, None)));
+        assert_eq!(handler.changed_attrs, vec![Attr::Bold, Attr::CancelUnderline, Attr::Reset]);
+        assert_eq!(handler.reversed_attrs, vec![
+            Attr::Bold,
+            Attr::Underline,
+            Attr::BlinkSlow,
+            Attr::Reverse,
+        ]);
+        assert_eq!(handler.attribute_change_extent, Some(AttributeChangeExtent::Rectangle));
+        assert_eq!(handler.checksum_request, Some((9, area(1, 1, Some(2), Some(2)))));
+    }
+
+    #[test]
+    fn parse_line_sizes() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b#3\x1b#4\x1b#6\x1b#5" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.line_sizes, vec![
+            LineSize::DoubleHeightTop,
+            LineSize::DoubleHeightBottom,
+            LineSize::DoubleWidth,
+            LineSize::Normal,
+        ]);
+    }
+
+    #[test]
+    fn parse_window_reports() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b[14t\x1b[16t\x1b[18t\x1b[19t\x1b[20t\x1b[21t\x1b[17t" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.window_reports, vec![
+            WindowReport::TextAreaSizePixels,
+            WindowReport::CellSizePixels,
+            WindowReport::TextAreaSizeChars,
+            WindowReport::ScreenSizeChars,
+            WindowReport::IconLabel,
+            WindowReport::Title,
+        ]);
+    }
+
+    #[test]
+    fn parse_window_operations() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b[2t\x1b[1t\x1b[5t\x1b[4;300;400t\x1b[8;0;80t\x1b[8t" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.window_operations, vec![
+            WindowOperation::Iconify,
+            WindowOperation::Deiconify,
+            WindowOperation::Raise,
+            WindowOperation::ResizePixels { width: Some(400), height: Some(300) },
+            WindowOperation::ResizeChars { lines: None, cols: Some(80) },
+            WindowOperation::ResizeChars { lines: None, cols: None },
+        ]);
+    }
+
+    #[test]
+    fn parse_sixel_graphic() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1bP0;1;0q\"1;1;2;6#0;2;100;100;100~~\x1b\\" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let image = Image { width: 2, height: 6, pixels: vec![255; 2 * 6 * 4] };
+        assert_eq!(handler.graphic, Some(image));
+    }
+
+    #[test]
+    fn ignore_non_sixel_dcs() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        // Payloads of DECUDK and tmux passthrough are also valid sixel data
+        for byte in b"\x1bP0;1|17/abcd\x1b\\\x1bPtmux;abcd\x1b\\" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.graphic, None);
+    }
+
+    #[test]
+    fn parse_sync_update() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b[?2026h" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert!(handler.sync_update);
+
+        for byte in b"\x1b[?2026l" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert!(!handler.sync_update);
+
+        for byte in b"\x1bP=1s\x1b\\" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert!(handler.sync_update);
+        assert_eq!(handler.graphic, None);
+
+        // Repeated beginnings do not end the update
+        for byte in b"\x1bP=1s\x1b\\\x1b[?2026h" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert!(handler.sync_update);
+
+        for byte in b"\x1bP=2s\x1b\\" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert!(!handler.sync_update);
+
+        // Ending an update which is not in progress does not begin one
+        for byte in b"\x1bP=2s\x1b\\\x1b[?2026l" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert!(!handler.sync_update);
+    }
+
+    #[test]
+    fn hold_back_sync_update() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b[?2026h\x1b]2;title\x07" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.title, None);
+
+        for byte in b"\x1b[?2026l" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.title, Some(String::from("title")));
+        assert!(!handler.sync_update);
+
+        // Updates which are not finished are processed once they time out
+        for byte in b"\x1bP=1s\x1b\\\x1b]2;other\x07" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.title, Some(String::from("title")));
+
+        parser.stop_sync(&mut handler, &mut io::sink());
+        assert_eq!(handler.title, Some(String::from("other")));
+        assert!(!handler.sync_update);
+    }
+
+    #[test]
+    fn parse_keyboard_modes() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b[>1u\x1b[>11u\x1b[=4;2u" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let disambiguate = KeyboardModes::DISAMBIGUATE_ESC_CODES;
+        let all_keys = KeyboardModes::from_bits_truncate(11) | KeyboardModes::REPORT_ALTERNATE_KEYS;
+        assert_eq!(handler.keyboard_modes, vec![disambiguate, all_keys]);
+
+        for byte in b"\x1b[<u" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.keyboard_modes, vec![disambiguate]);
+
+        for byte in b"\x1b[<5u" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert!(handler.keyboard_modes.is_empty());
+
+        // Reporting associated text is not supported
+        for byte in b"\x1b[>31u" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.keyboard_modes[0].bits(), 15);
+    }
+
+    #[test]
+    fn parse_modify_other_keys() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b[>4;2m" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.modify_other_keys, Some(ModifyOtherKeys::EnableAll));
+
+        for byte in b"\x1b[>4m" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.modify_other_keys, Some(ModifyOtherKeys::Reset));
+        assert_eq!(handler.attr, None);
+    }
+
+    #[test]
+    fn parse_cursor_style() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b[3 q" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        let style = CursorStyle { shape: CursorShape::Underline, blinking: true };
+        assert_eq!(handler.cursor_style, Some(style));
+
+        for byte in b"\x1b[6 q" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        let style = CursorStyle { shape: CursorShape::Beam, blinking: false };
+        assert_eq!(handler.cursor_style, Some(style));
+
+        for byte in b"\x1b[0 q" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.cursor_style, None);
+    }
+
+    #[test]
+    fn parse_mode_requests() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+        let mut response = Vec::new();
+
+        for byte in b"\x1b[?2026$p\x1b[?2026h\x1b[?2026$p\x1b[4$p\x1b[?9999$p\x1b[9999$p" {
+            parser.advance(&mut handler, *byte, &mut response);
+        }
+
+        assert_eq!(
+            String::from_utf8(response).unwrap(),
+            "\x1b[?2026;2$y\x1b[?2026;1$y\x1b[4;4$y\x1b[?9999;0$y\x1b[9999;0$y"
+        );
+    }
+
+    #[test]
+    fn parse_status_string_requests() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+        let mut response = Vec::new();
+
+        for byte in b"\x1bP$qm\x1b\\\x1bP$qr\x1b\\\x1bP$qx\x1b\\" {
+            parser.advance(&mut handler, *byte, &mut response);
+        }
+
+        assert_eq!(
+            String::from_utf8(response).unwrap(),
+            "\x1bP1$r0;1m\x1b\\\x1bP0$r\x1b\\\x1bP0$r\x1b\\"
+        );
+        assert_eq!(handler.graphic, None);
+    }
+
+    #[test]
+    fn parse_termcap_requests() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+        let mut response = Vec::new();
+
+        for byte in b"\x1bP+q544e;616D;636F6C6F7273;78;Z\x1b\\" {
+            parser.advance(&mut handler, *byte, &mut response);
+        }
+
+        assert_eq!(
+            String::from_utf8(response).unwrap(),
+            "\x1bP1+r544E=616C61637269747479\x1b\\\x1bP1+r616D\x1b\\\
+             \x1bP1+r636F6C6F7273=323536\x1b\\\x1bP0+r78\x1b\\\x1bP0+r\x1b\\"
+        );
+    }
+
     /// No exactly a test; useful for debugging
     #[test]
     fn parse_zsh_startup() {
@@ -1649,6 +3490,93 @@ mod tests {
         assert_eq!(handler.index, CharsetIndex::G1);
     }
 
+    #[test]
+    fn parse_designate_g2_g3_and_shift() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b*>" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.index, CharsetIndex::G2);
+        assert_eq!(handler.charset, StandardCharset::DecTechnical);
+
+        for byte in b"\x1b+%5" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.index, CharsetIndex::G3);
+        assert_eq!(handler.charset, StandardCharset::DecSupplementalGraphics);
+
+        // Locking shifts
+        for byte in b"\x1bn" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.index, CharsetIndex::G2);
+
+        for byte in b"\x1bo" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.index, CharsetIndex::G3);
+
+        // Single shifts
+        for byte in b"\x1bN" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.single_shift, Some(CharsetIndex::G2));
+
+        for byte in b"\x1bO" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.single_shift, Some(CharsetIndex::G3));
+    }
+
+    #[test]
+    fn parse_designate_national_charsets() {
+        let designations: &[(&[u8], StandardCharset)] = &[
+            (b"B", StandardCharset::Ascii),
+            (b"0", StandardCharset::SpecialCharacterAndLineDrawing),
+            (b"<", StandardCharset::DecSupplementalGraphics),
+            (b"%5", StandardCharset::DecSupplementalGraphics),
+            (b">", StandardCharset::DecTechnical),
+            (b"A", StandardCharset::British),
+            (b"4", StandardCharset::Dutch),
+            (b"C", StandardCharset::Finnish),
+            (b"5", StandardCharset::Finnish),
+            (b"R", StandardCharset::French),
+            (b"f", StandardCharset::French),
+            (b"Q", StandardCharset::FrenchCanadian),
+            (b"9", StandardCharset::FrenchCanadian),
+            (b"K", StandardCharset::German),
+            (b"Y", StandardCharset::Italian),
+            (b"E", StandardCharset::NorwegianDanish),
+            (b"6", StandardCharset::NorwegianDanish),
+            (b"`", StandardCharset::NorwegianDanish),
+            (b"%6", StandardCharset::Portuguese),
+            (b"Z", StandardCharset::Spanish),
+            (b"H", StandardCharset::Swedish),
+            (b"7", StandardCharset::Swedish),
+            (b"=", StandardCharset::Swiss),
+        ];
+
+        for (designator, charset) in designations {
+            let mut parser = Processor::new();
+            let mut handler = MockHandler {
+                index: CharsetIndex::G3,
+                charset: StandardCharset::SpecialCharacterAndLineDrawing,
+                ..MockHandler::default()
+            };
+
+            for byte in b"\x1b(".iter().chain(designator.iter()) {
+                parser.advance(&mut handler, *byte, &mut io::sink());
+            }
+
+            assert_eq!(handler.index, CharsetIndex::G0);
+            assert_eq!(handler.charset, *charset);
+        }
+    }
+
     #[test]
     fn parse_valid_rgb_colors() {
         assert_eq!(xparse_color(b"rgb:f/e/d"), Some(Rgb { r: 0xff, g: 0xee, b: 0xdd }));
@@ -0,0 +1,614 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers for drawing box drawing, block element, braille and powerline glyphs from font metrics
+//!
+//! Glyphs of these characters have to connect seamlessly with the glyphs in neighboring cells,
+//! which glyphs from the font rarely do when its metrics don't match the cell exactly.
+
+use std::cmp;
+use std::slice;
+
+use font::{Metrics, RasterizedGlyph};
+
+use crate::config::Delta;
+
+/// Weights of the lines from the center to the top, right, bottom and left edge of the cell
+///
+/// Every hex digit is the weight of one line, from `0` for no line to `3` for a double line.
+const BOX_LINES: [u16; 128] = [
+    0x0101, 0x0202, 0x1010, 0x2020, 0x0101, 0x0202, 0x1010, 0x2020, // U+2500
+    0x0101, 0x0202, 0x1010, 0x2020, 0x0110, 0x0210, 0x0120, 0x0220, // U+2508
+    0x0011, 0x0012, 0x0021, 0x0022, 0x1100, 0x1200, 0x2100, 0x2200, // U+2510
+    0x1001, 0x1002, 0x2001, 0x2002, 0x1110, 0x1210, 0x2110, 0x1120, // U+2518
+    0x2120, 0x2210, 0x1220, 0x2220, 0x1011, 0x1012, 0x2011, 0x1021, // U+2520
+    0x2021, 0x2012, 0x1022, 0x2022, 0x0111, 0x0112, 0x0211, 0x0212, // U+2528
+    0x0121, 0x0122, 0x0221, 0x0222, 0x1101, 0x1102, 0x1201, 0x1202, // U+2530
+    0x2101, 0x2102, 0x2201, 0x2202, 0x1111, 0x1112, 0x1211, 0x1212, // U+2538
+    0x2111, 0x1121, 0x2121, 0x2112, 0x2211, 0x1122, 0x1221, 0x2212, // U+2540
+    0x1222, 0x2122, 0x2221, 0x2222, 0x0101, 0x0202, 0x1010, 0x2020, // U+2548
+    0x0303, 0x3030, 0x0310, 0x0130, 0x0330, 0x0013, 0x0031, 0x0033, // U+2550
+    0x1300, 0x3100, 0x3300, 0x1003, 0x3001, 0x3003, 0x1310, 0x3130, // U+2558
+    0x3330, 0x1013, 0x3031, 0x3033, 0x0313, 0x0131, 0x0333, 0x1303, // U+2560
+    0x3101, 0x3303, 0x1313, 0x3131, 0x3333, 0x0110, 0x0011, 0x1001, // U+2568
+    0x1100, 0x0000, 0x0000, 0x0000, 0x0001, 0x1000, 0x0100, 0x0010, // U+2570
+    0x0002, 0x2000, 0x0200, 0x0020, 0x0201, 0x1020, 0x0102, 0x2010, // U+2578
+];
+
+/// Quadrants of the block elements from U+2596 to U+259F
+///
+/// The bits are the upper left, upper right, lower left and lower right quadrant.
+const QUADRANTS: [u8; 10] =
+    [0b0100, 0b1000, 0b0001, 0b1101, 0b1001, 0b0111, 0b1011, 0b0010, 0b0110, 0b1110];
+
+/// Column and row of the braille dots, in the order of their bits
+const BRAILLE_DOTS: [(i32, i32); 8] =
+    [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2), (0, 3), (1, 3)];
+
+/// Check if a character is drawn by the built-in font instead of the configured one
+pub fn is_builtin_glyph(c: char) -> bool {
+    match c {
+        '\u{2500}'..='\u{259F}' | '\u{2800}'..='\u{28FF}' | '\u{E0B0}'..='\u{E0B3}' => true,
+        _ => false,
+    }
+}
+
+/// Create a glyph which covers the whole cell
+///
+/// Returns `None` for characters which aren't drawn by the built-in font.
+pub fn get_builtin_glyph(c: char, metrics: Metrics, offset: Delta<i8>) -> Option<RasterizedGlyph> {
+    // Calculate the cell metrics
+    let width = cmp::max(metrics.average_advance as i32 + i32::from(offset.x), 1);
+    let height = cmp::max(metrics.line_height as i32 + i32::from(offset.y), 1);
+
+    // Light lines are as thick as the underline, but double lines still have to fit the cell
+    let light = cmp::max(metrics.underline_thickness.round() as i32, 1);
+    let light = cmp::max(cmp::min(light, cmp::min(width, height) / 3), 1);
+
+    let mut canvas = Canvas::new(width, height);
+    match c {
+        '\u{2500}'..='\u{257F}' => box_drawing(&mut canvas, c, light),
+        '\u{2580}'..='\u{259F}' => block_element(&mut canvas, c),
+        '\u{2800}'..='\u{28FF}' => braille(&mut canvas, c),
+        '\u{E0B0}'..='\u{E0B3}' => powerline(&mut canvas, c, light),
+        _ => return None,
+    }
+
+    Some(canvas.into_glyph(c))
+}
+
+/// Coverage of the pixels of a cell
+struct Canvas {
+    width: i32,
+    height: i32,
+    buf: Vec<f32>,
+}
+
+impl Canvas {
+    fn new(width: i32, height: i32) -> Canvas {
+        Canvas { width, height, buf: vec![0.; (width * height) as usize] }
+    }
+
+    /// Cover a pixel, overlapping shapes keep the larger coverage
+    fn put(&mut self, x: i32, y: i32, alpha: f32) {
+        if x >= 0 && y >= 0 && x < self.width && y < self.height {
+            let pixel = &mut self.buf[(y * self.width + x) as usize];
+            *pixel = pixel.max(alpha);
+        }
+    }
+
+    /// Fill the rectangle from the start up to, but excluding, the end coordinates
+    fn fill_rect(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, alpha: f32) {
+        for y in y0..y1 {
+            for x in x0..x1 {
+                self.put(x, y, alpha);
+            }
+        }
+    }
+
+    /// Fill pixels with the coverage at their center
+    fn fill_with<F: Fn(f32, f32) -> f32>(&mut self, coverage: F) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let alpha = coverage(x as f32 + 0.5, y as f32 + 0.5);
+                self.put(x, y, alpha.max(0.).min(1.));
+            }
+        }
+    }
+
+    /// Fill the inside of a shape, sampling every pixel at multiple positions for smooth edges
+    fn fill_shape<F: Fn(f32, f32) -> bool>(&mut self, inside: F) {
+        const SAMPLES: i32 = 4;
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let mut covered = 0;
+                for sample_y in 0..SAMPLES {
+                    for sample_x in 0..SAMPLES {
+                        let sample_x = x as f32 + (sample_x as f32 + 0.5) / SAMPLES as f32;
+                        let sample_y = y as f32 + (sample_y as f32 + 0.5) / SAMPLES as f32;
+                        if inside(sample_x, sample_y) {
+                            covered += 1;
+                        }
+                    }
+                }
+
+                self.put(x, y, covered as f32 / (SAMPLES * SAMPLES) as f32);
+            }
+        }
+    }
+
+    /// Draw a straight line between two points
+    fn draw_line(&mut self, from: (f32, f32), to: (f32, f32), thickness: f32) {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let length_squared = dx * dx + dy * dy;
+
+        self.fill_with(|x, y| {
+            // Distance to the closest point of the line
+            let t = ((x - from.0) * dx + (y - from.1) * dy) / length_squared;
+            let t = t.max(0.).min(1.);
+            let distance = (x - from.0 - t * dx).hypot(y - from.1 - t * dy);
+
+            thickness / 2. + 0.5 - distance
+        });
+    }
+
+    fn into_glyph(self, c: char) -> RasterizedGlyph {
+        let mut buf = Vec::with_capacity(self.buf.len() * 3);
+        for alpha in self.buf {
+            let value = (alpha * 255.).round() as u8;
+            buf.extend_from_slice(&[value; 3]);
+        }
+
+        RasterizedGlyph {
+            c,
+            top: self.height,
+            left: 0,
+            height: self.height,
+            width: self.width,
+            buf,
+            colored: false,
+        }
+    }
+}
+
+/// Weight of a line of a box drawing character
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Weight {
+    None,
+    Light,
+    Heavy,
+    Double,
+}
+
+impl Weight {
+    fn from_digit(digit: u16) -> Weight {
+        match digit & 0xF {
+            1 => Weight::Light,
+            2 => Weight::Heavy,
+            3 => Weight::Double,
+            _ => Weight::None,
+        }
+    }
+}
+
+/// How far a line from the edge of the cell reaches toward the other edge
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Reach {
+    /// Center of the cell
+    Center,
+    /// Nearest line of a perpendicular double line
+    Near,
+    /// Farthest line of a perpendicular double line
+    Far,
+    /// Other side of a perpendicular line
+    Across(Weight),
+}
+
+impl Reach {
+    /// Reach of one stroke of a line
+    ///
+    /// The perpendicular lines are the ones on the side of the stroke, which matters for
+    /// double lines, and on its other side.
+    fn new(weight: Weight, side: Weight, other_side: Weight, opposite: Weight) -> Reach {
+        let perpendicular = cmp::max(side, other_side);
+
+        if weight == Weight::Double {
+            match (side, other_side) {
+                (Weight::Double, _) => Reach::Near,
+                (_, Weight::Double) => Reach::Far,
+                _ if perpendicular != Weight::None => Reach::Across(perpendicular),
+                _ => Reach::Center,
+            }
+        } else if perpendicular == Weight::Double {
+            // Close corners, but stop at the near line of tees
+            if opposite == Weight::None && side != Weight::None && other_side != Weight::None {
+                Reach::Near
+            } else {
+                Reach::Far
+            }
+        } else if perpendicular != Weight::None {
+            Reach::Across(perpendicular)
+        } else {
+            Reach::Center
+        }
+    }
+}
+
+/// Position of the strokes of lines along one axis of the cell
+struct Axis {
+    len: i32,
+    light: (i32, i32),
+    heavy: (i32, i32),
+    double: [(i32, i32); 2],
+}
+
+impl Axis {
+    fn new(len: i32, light: i32) -> Axis {
+        let centered = |thickness: i32| {
+            let start = (len - thickness) / 2;
+            (start, start + thickness)
+        };
+
+        let double = centered(3 * light).0;
+        Axis {
+            len,
+            light: centered(light),
+            heavy: centered(2 * light),
+            double: [(double, double + light), (double + 2 * light, double + 3 * light)],
+        }
+    }
+
+    /// Start and end of the strokes of a line
+    fn strokes(&self, weight: Weight) -> &[(i32, i32)] {
+        match weight {
+            Weight::None => &[],
+            Weight::Light => slice::from_ref(&self.light),
+            Weight::Heavy => slice::from_ref(&self.heavy),
+            Weight::Double => &self.double,
+        }
+    }
+
+    /// End of a line from the start of the axis
+    fn end(&self, reach: Reach, weight: Weight) -> i32 {
+        match reach {
+            Reach::Near => self.double[0].1,
+            Reach::Far => self.double[1].1,
+            Reach::Across(perpendicular) => self.strokes(perpendicular)[0].1,
+            Reach::Center if weight == Weight::Double => self.len / 2,
+            Reach::Center => self.strokes(weight)[0].1,
+        }
+    }
+
+    /// Start of a line to the end of the axis
+    fn start(&self, reach: Reach, weight: Weight) -> i32 {
+        match reach {
+            Reach::Near => self.double[1].0,
+            Reach::Far => self.double[0].0,
+            Reach::Across(perpendicular) => self.strokes(perpendicular)[0].0,
+            Reach::Center if weight == Weight::Double => self.len / 2,
+            Reach::Center => self.strokes(weight)[0].0,
+        }
+    }
+}
+
+/// Draw box drawing characters from U+2500 to U+257F
+fn box_drawing(canvas: &mut Canvas, c: char, light: i32) {
+    match c {
+        '\u{2504}'..='\u{250B}' | '\u{254C}'..='\u{254F}' => return dashes(canvas, c, light),
+        '\u{256D}'..='\u{2570}' => return arc(canvas, c, light),
+        '\u{2571}'..='\u{2573}' => return diagonals(canvas, c, light),
+        _ => (),
+    }
+
+    let lines = BOX_LINES[c as usize - 0x2500];
+    let up = Weight::from_digit(lines >> 12);
+    let right = Weight::from_digit(lines >> 8);
+    let down = Weight::from_digit(lines >> 4);
+    let left = Weight::from_digit(lines);
+
+    // Positions of the vertical lines along the width and of the horizontal lines along the height
+    let horizontal = Axis::new(canvas.width, light);
+    let vertical = Axis::new(canvas.height, light);
+
+    // The first stroke of double lines is on the left or top side
+    let sides = |index: usize, first: Weight, second: Weight| {
+        if index == 0 {
+            (first, second)
+        } else {
+            (second, first)
+        }
+    };
+
+    for (i, &(x0, x1)) in horizontal.strokes(up).iter().enumerate() {
+        let (side, other_side) = sides(i, left, right);
+        let end = vertical.end(Reach::new(up, side, other_side, down), up);
+        canvas.fill_rect(x0, 0, x1, end, 1.);
+    }
+
+    for (i, &(x0, x1)) in horizontal.strokes(down).iter().enumerate() {
+        let (side, other_side) = sides(i, left, right);
+        let start = vertical.start(Reach::new(down, side, other_side, up), down);
+        canvas.fill_rect(x0, start, x1, canvas.height, 1.);
+    }
+
+    for (i, &(y0, y1)) in vertical.strokes(left).iter().enumerate() {
+        let (side, other_side) = sides(i, up, down);
+        let end = horizontal.end(Reach::new(left, side, other_side, right), left);
+        canvas.fill_rect(0, y0, end, y1, 1.);
+    }
+
+    for (i, &(y0, y1)) in vertical.strokes(right).iter().enumerate() {
+        let (side, other_side) = sides(i, up, down);
+        let start = horizontal.start(Reach::new(right, side, other_side, left), right);
+        canvas.fill_rect(start, y0, canvas.width, y1, 1.);
+    }
+}
+
+/// Draw dashed lines
+fn dashes(canvas: &mut Canvas, c: char, light: i32) {
+    let (count, index) = match c {
+        '\u{2504}'..='\u{2507}' => (3, c as u32 - 0x2504),
+        '\u{2508}'..='\u{250B}' => (4, c as u32 - 0x2508),
+        _ => (2, c as u32 - 0x254C),
+    };
+    let weight = if index % 2 == 0 { Weight::Light } else { Weight::Heavy };
+    let is_vertical = index >= 2;
+
+    let (len, across) = if is_vertical {
+        (canvas.height, Axis::new(canvas.width, light))
+    } else {
+        (canvas.width, Axis::new(canvas.height, light))
+    };
+    let (stroke_start, stroke_end) = across.strokes(weight)[0];
+
+    for i in 0..count {
+        let start = i * len / count;
+        let end = (i + 1) * len / count;
+
+        // Split the gap between both ends of the dash, so the dashes of neighboring cells are
+        // just as far apart
+        let gap = cmp::max((end - start) / 3, 1);
+        let (dash_start, dash_end) = (start + gap / 2, end - (gap - gap / 2));
+
+        if is_vertical {
+            canvas.fill_rect(stroke_start, dash_start, stroke_end, dash_end, 1.);
+        } else {
+            canvas.fill_rect(dash_start, stroke_start, dash_end, stroke_end, 1.);
+        }
+    }
+}
+
+/// Draw rounded corners
+fn arc(canvas: &mut Canvas, c: char, light: i32) {
+    let horizontal = Axis::new(canvas.width, light);
+    let vertical = Axis::new(canvas.height, light);
+
+    // Direction of the lines, which continue to the right or left and to the bottom or top
+    let (dir_x, dir_y) = match c {
+        '\u{256D}' => (1., 1.),
+        '\u{256E}' => (-1., 1.),
+        '\u{256F}' => (-1., -1.),
+        _ => (1., -1.),
+    };
+
+    // Center of the light lines
+    let x = (horizontal.light.0 + horizontal.light.1) as f32 / 2.;
+    let y = (vertical.light.0 + vertical.light.1) as f32 / 2.;
+
+    let (width, height) = (canvas.width as f32, canvas.height as f32);
+    let radius = x.min(width - x).min(y).min(height - y);
+    let center = (x + dir_x * radius, y + dir_y * radius);
+
+    // Quarter of a circle between the ends of the straight lines
+    canvas.fill_with(|px, py| {
+        if (px - center.0) * dir_x > 0. || (py - center.1) * dir_y > 0. {
+            return 0.;
+        }
+
+        let distance = (px - center.0).hypot(py - center.1);
+        light as f32 / 2. + 0.5 - (distance - radius).abs()
+    });
+
+    let (x0, x1) = horizontal.light;
+    let (y0, y1) = vertical.light;
+    if dir_y > 0. {
+        canvas.fill_rect(x0, center.1.floor() as i32, x1, canvas.height, 1.);
+    } else {
+        canvas.fill_rect(x0, 0, x1, center.1.ceil() as i32, 1.);
+    }
+
+    if dir_x > 0. {
+        canvas.fill_rect(center.0.floor() as i32, y0, canvas.width, y1, 1.);
+    } else {
+        canvas.fill_rect(0, y0, center.0.ceil() as i32, y1, 1.);
+    }
+}
+
+/// Draw diagonal lines between the corners of the cell
+fn diagonals(canvas: &mut Canvas, c: char, light: i32) {
+    let (width, height) = (canvas.width as f32, canvas.height as f32);
+
+    if c != '\u{2572}' {
+        canvas.draw_line((width, 0.), (0., height), light as f32);
+    }
+
+    if c != '\u{2571}' {
+        canvas.draw_line((0., 0.), (width, height), light as f32);
+    }
+}
+
+/// Draw block elements from U+2580 to U+259F
+fn block_element(canvas: &mut Canvas, c: char) {
+    let (width, height) = (canvas.width, canvas.height);
+
+    // Position of an eighth of the cell, rounded to the closest pixel
+    let x = |eighths: i32| (width * eighths + 4) / 8;
+    let y = |eighths: i32| (height * eighths + 4) / 8;
+
+    match c {
+        // Upper half
+        '\u{2580}' => canvas.fill_rect(0, 0, width, y(4), 1.),
+        // Lower eighths
+        '\u{2581}'..='\u{2588}' => {
+            let eighths = c as i32 - 0x2580;
+            canvas.fill_rect(0, y(8 - eighths), width, height, 1.);
+        },
+        // Left eighths
+        '\u{2589}'..='\u{258F}' => {
+            let eighths = 0x2590 - c as i32;
+            canvas.fill_rect(0, 0, x(eighths), height, 1.);
+        },
+        // Right half
+        '\u{2590}' => canvas.fill_rect(x(4), 0, width, height, 1.),
+        // Light, medium and dark shade
+        '\u{2591}'..='\u{2593}' => {
+            let alpha = (c as u32 - 0x2590) as f32 / 4.;
+            canvas.fill_rect(0, 0, width, height, alpha);
+        },
+        // Upper eighth
+        '\u{2594}' => canvas.fill_rect(0, 0, width, y(1), 1.),
+        // Right eighth
+        '\u{2595}' => canvas.fill_rect(x(7), 0, width, height, 1.),
+        // Quadrants
+        _ => {
+            let quadrants = QUADRANTS[c as usize - 0x2596];
+            let (center_x, center_y) = (x(4), y(4));
+
+            if quadrants & 0b0001 != 0 {
+                canvas.fill_rect(0, 0, center_x, center_y, 1.);
+            }
+            if quadrants & 0b0010 != 0 {
+                canvas.fill_rect(center_x, 0, width, center_y, 1.);
+            }
+            if quadrants & 0b0100 != 0 {
+                canvas.fill_rect(0, center_y, center_x, height, 1.);
+            }
+            if quadrants & 0b1000 != 0 {
+                canvas.fill_rect(center_x, center_y, width, height, 1.);
+            }
+        },
+    }
+}
+
+/// Draw braille patterns from U+2800 to U+28FF
+fn braille(canvas: &mut Canvas, c: char) {
+    let (width, height) = (canvas.width, canvas.height);
+
+    // Square dots in the center of a grid of two columns and four rows
+    let size = cmp::max(cmp::min(width / 2, height / 4) / 2, 1);
+
+    let dots = c as u32 - 0x2800;
+    for (bit, &(col, row)) in BRAILLE_DOTS.iter().enumerate() {
+        if dots & (1 << bit) == 0 {
+            continue;
+        }
+
+        let x = (2 * col + 1) * width / 4 - size / 2;
+        let y = (2 * row + 1) * height / 8 - size / 2;
+        canvas.fill_rect(x, y, x + size, y + size, 1.);
+    }
+}
+
+/// Draw the powerline separators from U+E0B0 to U+E0B3
+fn powerline(canvas: &mut Canvas, c: char, light: i32) {
+    let (width, height) = (canvas.width as f32, canvas.height as f32);
+
+    // Horizontal extent of the triangle at a height
+    let extent = move |y: f32| width * (1. - (2. * y / height - 1.).abs());
+
+    match c {
+        '\u{E0B0}' => canvas.fill_shape(|x, y| x <= extent(y)),
+        '\u{E0B2}' => canvas.fill_shape(|x, y| width - x <= extent(y)),
+        '\u{E0B1}' => {
+            canvas.draw_line((0., 0.), (width, height / 2.), light as f32);
+            canvas.draw_line((width, height / 2.), (0., height), light as f32);
+        },
+        _ => {
+            canvas.draw_line((width, 0.), (0., height / 2.), light as f32);
+            canvas.draw_line((0., height / 2.), (width, height), light as f32);
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use font::Metrics;
+
+    use super::{get_builtin_glyph, is_builtin_glyph};
+    use crate::config::Delta;
+
+    /// Render the glyph of a character as text
+    ///
+    /// Covered pixels are `#`, partially covered ones `+` and empty ones `.`.
+    fn snapshot(c: char, width: i32, height: i32) -> String {
+        let metrics = Metrics {
+            average_advance: f64::from(width),
+            line_height: f64::from(height),
+            descent: 0.,
+            underline_position: 0.,
+            underline_thickness: 1.,
+            strikeout_position: 0.,
+            strikeout_thickness: 1.,
+        };
+        let glyph = get_builtin_glyph(c, metrics, Delta::default()).unwrap();
+
+        assert_eq!((glyph.width, glyph.height, glyph.top, glyph.left), (width, height, height, 0));
+
+        let mut text = String::new();
+        for row in glyph.buf.chunks(width as usize * 3) {
+            for pixel in row.chunks(3) {
+                text.push(match pixel[0] {
+                    0 => '.',
+                    255 => '#',
+                    _ => '+',
+                });
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    #[test]
+    fn box_drawing() {
+        assert_eq!(snapshot('┼', 5, 5), "..#..\n..#..\n#####\n..#..\n..#..\n");
+        assert_eq!(snapshot('┫', 6, 6), "..##..\n..##..\n####..\n####..\n..##..\n..##..\n");
+        assert_eq!(
+            snapshot('╔', 7, 7),
+            ".......\n.......\n..#####\n..#....\n..#.###\n..#.#..\n..#.#..\n"
+        );
+        assert_eq!(snapshot('╌', 8, 3), "........\n###.###.\n........\n");
+    }
+
+    #[test]
+    fn block_elements() {
+        assert_eq!(snapshot('▚', 4, 4), "##..\n##..\n..##\n..##\n");
+        assert_eq!(snapshot('▃', 4, 8), "....\n....\n....\n....\n....\n####\n####\n####\n");
+    }
+
+    #[test]
+    fn braille() {
+        assert_eq!(snapshot('⢕', 4, 8), "....\n.#..\n....\n...#\n....\n.#..\n....\n...#\n");
+    }
+
+    #[test]
+    fn builtin_characters() {
+        assert!(is_builtin_glyph('─'));
+        assert!(is_builtin_glyph('▟'));
+        assert!(is_builtin_glyph('⣿'));
+        assert!(is_builtin_glyph('\u{E0B0}'));
+        assert!(!is_builtin_glyph('a'));
+        assert!(!is_builtin_glyph('\u{25A0}'));
+    }
+}
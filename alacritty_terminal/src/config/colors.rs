@@ -12,8 +12,12 @@ pub struct Colors {
     #[serde(deserialize_with = "failure_default")]
     pub cursor: CursorColors,
     #[serde(deserialize_with = "failure_default")]
+    pub vi_mode_cursor: CursorColors,
+    #[serde(deserialize_with = "failure_default")]
     pub selection: SelectionColors,
     #[serde(deserialize_with = "failure_default")]
+    pub search: SearchColors,
+    #[serde(deserialize_with = "failure_default")]
     normal: NormalColors,
     #[serde(deserialize_with = "failure_default")]
     bright: BrightColors,
@@ -90,6 +94,51 @@ pub struct SelectionColors {
     pub background: Option<Rgb>,
 }
 
+#[serde(default)]
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SearchColors {
+    #[serde(deserialize_with = "failure_default")]
+    pub matches: SearchMatchColors,
+    #[serde(default = "default_search_bar", deserialize_with = "failure_default")]
+    pub bar: Rgb,
+}
+
+impl Default for SearchColors {
+    fn default() -> Self {
+        SearchColors { matches: Default::default(), bar: default_search_bar() }
+    }
+}
+
+fn default_search_bar() -> Rgb {
+    Rgb { r: 0xc5, g: 0xc8, b: 0xc6 }
+}
+
+#[serde(default)]
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SearchMatchColors {
+    #[serde(default = "default_search_match_text", deserialize_with = "failure_default")]
+    pub text: Rgb,
+    #[serde(default = "default_search_match_background", deserialize_with = "failure_default")]
+    pub background: Rgb,
+}
+
+impl Default for SearchMatchColors {
+    fn default() -> Self {
+        SearchMatchColors {
+            text: default_search_match_text(),
+            background: default_search_match_background(),
+        }
+    }
+}
+
+fn default_search_match_text() -> Rgb {
+    Rgb { r: 0, g: 0, b: 0 }
+}
+
+fn default_search_match_background() -> Rgb {
+    Rgb { r: 0xff, g: 0xff, b: 0xff }
+}
+
 #[serde(default)]
 #[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct PrimaryColors {
@@ -1,13 +1,11 @@
 use std::fmt;
 
-use font::Size;
+use font::{Feature, Size};
 use log::error;
 use serde::de::Visitor;
 use serde::{Deserialize, Deserializer};
 
-#[cfg(target_os = "macos")]
-use crate::config::DefaultTrueBool;
-use crate::config::{failure_default, Delta, LOG_TARGET_CONFIG};
+use crate::config::{failure_default, DefaultTrueBool, Delta, LOG_TARGET_CONFIG};
 
 /// Font config
 ///
@@ -46,6 +44,14 @@ pub struct Font {
     #[serde(deserialize_with = "failure_default")]
     pub glyph_offset: Delta<i8>,
 
+    /// Ligatures and other OpenType features applied by text shaping
+    #[serde(deserialize_with = "failure_default")]
+    pub ligatures: Ligatures,
+
+    /// Draw box drawing, block element, braille and powerline characters without the font
+    #[serde(deserialize_with = "failure_default")]
+    builtin_box_drawing: DefaultTrueBool,
+
     #[cfg(target_os = "macos")]
     #[serde(deserialize_with = "failure_default")]
     use_thin_strokes: DefaultTrueBool,
@@ -61,6 +67,8 @@ impl Default for Font {
             bold_italic: Default::default(),
             glyph_offset: Default::default(),
             offset: Default::default(),
+            ligatures: Default::default(),
+            builtin_box_drawing: Default::default(),
             #[cfg(target_os = "macos")]
             use_thin_strokes: Default::default(),
         }
@@ -93,6 +101,11 @@ impl Font {
         self.bold_italic.desc(&self.normal)
     }
 
+    #[inline]
+    pub fn builtin_box_drawing(&self) -> bool {
+        self.builtin_box_drawing.0
+    }
+
     #[cfg(target_os = "macos")]
     pub fn use_thin_strokes(&self) -> bool {
         self.use_thin_strokes.0
@@ -151,6 +164,49 @@ impl SecondaryFontDescription {
     }
 }
 
+/// Text shaping settings
+#[serde(default)]
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+pub struct Ligatures {
+    /// Combine characters into ligatures
+    #[serde(deserialize_with = "failure_default")]
+    enabled: DefaultTrueBool,
+
+    /// OpenType features enabled or disabled in addition to the defaults of the font
+    #[serde(deserialize_with = "deserialize_features")]
+    pub features: Vec<Feature>,
+}
+
+impl Ligatures {
+    #[inline]
+    pub fn enabled(&self) -> bool {
+        self.enabled.0
+    }
+}
+
+fn deserialize_features<'a, D>(deserializer: D) -> Result<Vec<Feature>, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    let values = Vec::<String>::deserialize(deserializer).unwrap_or_else(|err| {
+        error!(target: LOG_TARGET_CONFIG, "Problem with config: {}; using default value", err);
+        Vec::new()
+    });
+
+    // Skip invalid features instead of dropping all of them
+    let mut features = Vec::with_capacity(values.len());
+    for value in values {
+        match value.parse() {
+            Ok(feature) => features.push(feature),
+            Err(err) => {
+                error!(target: LOG_TARGET_CONFIG, "Problem with config: {}; ignoring it", err);
+            },
+        }
+    }
+
+    Ok(features)
+}
+
 trait DeserializeSize: Sized {
     fn deserialize<'a, D>(_: D) -> ::std::result::Result<Self, D::Error>
     where
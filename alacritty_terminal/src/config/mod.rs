@@ -16,6 +16,7 @@ use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use log::error;
 use serde::{Deserialize, Deserializer};
@@ -25,15 +26,17 @@ mod colors;
 mod debug;
 mod font;
 mod scrolling;
+mod unicode;
 mod visual_bell;
 mod window;
 
-use crate::ansi::{Color, CursorStyle, NamedColor};
+use crate::ansi::{Color, CursorShape, CursorStyle, NamedColor};
 
 pub use crate::config::colors::Colors;
 pub use crate::config::debug::Debug;
-pub use crate::config::font::{Font, FontDescription};
+pub use crate::config::font::{Font, FontDescription, Ligatures};
 pub use crate::config::scrolling::Scrolling;
+pub use crate::config::unicode::{AmbiguousWidth, Unicode, UnicodeVersion, WidthOverride};
 pub use crate::config::visual_bell::{VisualBellAnimation, VisualBellConfig};
 pub use crate::config::window::{Decorations, Dimensions, StartupMode, WindowConfig, DEFAULT_NAME};
 use crate::term::color::Rgb;
@@ -108,6 +111,10 @@ pub struct Config<T> {
     #[serde(default, deserialize_with = "failure_default")]
     pub cursor: Cursor,
 
+    /// Width of characters
+    #[serde(default, deserialize_with = "failure_default")]
+    pub unicode: Unicode,
+
     /// Enable experimental conpty backend instead of using winpty.
     /// Will only take effect on Windows 10 Oct 2018 and later.
     #[cfg(windows)]
@@ -187,6 +194,18 @@ impl<T> Config<T> {
         self.colors.cursor.cursor.map(|_| Color::Named(NamedColor::Cursor))
     }
 
+    /// Vi mode cursor foreground color
+    #[inline]
+    pub fn vi_mode_cursor_text_color(&self) -> Option<Rgb> {
+        self.colors.vi_mode_cursor.text
+    }
+
+    /// Vi mode cursor background color
+    #[inline]
+    pub fn vi_mode_cursor_cursor_color(&self) -> Option<Color> {
+        self.colors.vi_mode_cursor.cursor.map(Color::Spec)
+    }
+
     #[inline]
     pub fn set_dynamic_title(&mut self, dynamic_title: bool) {
         self.dynamic_title.0 = dynamic_title;
@@ -248,14 +267,26 @@ impl Default for EscapeChars {
 #[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
 pub struct Cursor {
     #[serde(deserialize_with = "failure_default")]
-    pub style: CursorStyle,
+    pub style: CursorShape,
+    #[serde(deserialize_with = "failure_default")]
+    pub vi_mode_style: CursorShape,
+    #[serde(deserialize_with = "failure_default")]
+    pub blinking: CursorBlinking,
+    #[serde(deserialize_with = "failure_default")]
+    blink_interval: BlinkInterval,
     #[serde(deserialize_with = "failure_default")]
     unfocused_hollow: DefaultTrueBool,
 }
 
 impl Default for Cursor {
     fn default() -> Self {
-        Self { style: Default::default(), unfocused_hollow: Default::default() }
+        Self {
+            style: Default::default(),
+            vi_mode_style: Default::default(),
+            blinking: Default::default(),
+            blink_interval: Default::default(),
+            unfocused_hollow: Default::default(),
+        }
     }
 }
 
@@ -263,6 +294,53 @@ impl Cursor {
     pub fn unfocused_hollow(self) -> bool {
         self.unfocused_hollow.0
     }
+
+    /// Cursor style used when applications have not changed it
+    pub fn default_style(self) -> CursorStyle {
+        let blinking = match self.blinking {
+            CursorBlinking::On | CursorBlinking::Always => true,
+            CursorBlinking::Off | CursorBlinking::Never => false,
+        };
+        CursorStyle { shape: self.style, blinking }
+    }
+
+    /// Time between blinks of text and the cursor
+    ///
+    /// Returns `None` if blinking is disabled.
+    pub fn blink_interval(self) -> Option<Duration> {
+        match self.blink_interval.0 {
+            0 => None,
+            interval => Some(Duration::from_millis(interval)),
+        }
+    }
+}
+
+/// Blinking behavior of the cursor
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub enum CursorBlinking {
+    /// Never blink, even if requested by applications
+    Never,
+    /// Don't blink unless requested by applications
+    Off,
+    /// Blink unless disabled by applications
+    On,
+    /// Always blink, even if disabled by applications
+    Always,
+}
+
+impl Default for CursorBlinking {
+    fn default() -> Self {
+        CursorBlinking::Off
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+struct BlinkInterval(u64);
+
+impl Default for BlinkInterval {
+    fn default() -> Self {
+        BlinkInterval(750)
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
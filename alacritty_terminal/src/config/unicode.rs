@@ -0,0 +1,127 @@
+use log::error;
+use serde::{Deserialize, Deserializer};
+
+use crate::config::{failure_default, DefaultTrueBool, LOG_TARGET_CONFIG};
+
+/// Settings for the width of characters
+#[serde(default)]
+#[derive(Deserialize, Clone, Default, Debug, PartialEq, Eq)]
+pub struct Unicode {
+    /// Width of East Asian ambiguous-width characters
+    #[serde(deserialize_with = "failure_default")]
+    pub ambiguous_width: AmbiguousWidth,
+
+    /// Unicode version of the width tables
+    #[serde(deserialize_with = "failure_default")]
+    pub version: UnicodeVersion,
+
+    /// Display emoji presentation sequences with two cells
+    #[serde(deserialize_with = "failure_default")]
+    emoji_presentation: DefaultTrueBool,
+
+    /// Custom widths for ranges of characters
+    #[serde(deserialize_with = "deserialize_width_overrides")]
+    pub width_overrides: Vec<WidthOverride>,
+}
+
+impl Unicode {
+    #[inline]
+    pub fn emoji_presentation(&self) -> bool {
+        self.emoji_presentation.0
+    }
+
+    #[inline]
+    pub fn set_emoji_presentation(&mut self, emoji_presentation: bool) {
+        self.emoji_presentation.0 = emoji_presentation;
+    }
+}
+
+/// Width of East Asian ambiguous-width characters
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AmbiguousWidth {
+    /// One cell, like in western locales
+    Narrow,
+    /// Two cells, like in CJK locales
+    Wide,
+}
+
+impl Default for AmbiguousWidth {
+    fn default() -> Self {
+        AmbiguousWidth::Narrow
+    }
+}
+
+/// Unicode version of the width tables
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum UnicodeVersion {
+    /// Emoji which only became wide in Unicode 9 occupy a single cell
+    Unicode8,
+    /// Widths of the most recent supported Unicode version
+    Latest,
+}
+
+impl Default for UnicodeVersion {
+    fn default() -> Self {
+        UnicodeVersion::Latest
+    }
+}
+
+/// Width of all characters between `start` and `end`
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct WidthOverride {
+    #[serde(deserialize_with = "deserialize_code_point")]
+    pub start: char,
+    #[serde(deserialize_with = "deserialize_code_point")]
+    pub end: char,
+    pub width: u8,
+}
+
+impl WidthOverride {
+    #[inline]
+    pub fn contains(&self, c: char) -> bool {
+        self.start <= c && c <= self.end
+    }
+}
+
+fn deserialize_code_point<'a, D>(deserializer: D) -> Result<char, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    let code_point = u32::deserialize(deserializer)?;
+    std::char::from_u32(code_point)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid code point 0x{:X}", code_point)))
+}
+
+fn deserialize_width_overrides<'a, D>(deserializer: D) -> Result<Vec<WidthOverride>, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    let values = Vec::<serde_yaml::Value>::deserialize(deserializer).unwrap_or_else(|err| {
+        error!(target: LOG_TARGET_CONFIG, "Problem with config: {}; using default value", err);
+        Vec::new()
+    });
+
+    // Skip invalid overrides instead of dropping all of them
+    let mut overrides = Vec::with_capacity(values.len());
+    for value in values {
+        match WidthOverride::deserialize(value) {
+            Ok(width_override) if width_override.width > 2 => {
+                error!(
+                    target: LOG_TARGET_CONFIG,
+                    "Problem with config: width override of {} columns, but expected a maximum \
+                     of 2; ignoring it",
+                    width_override.width,
+                );
+            },
+            Ok(width_override) => overrides.push(width_override),
+            Err(err) => {
+                error!(
+                    target: LOG_TARGET_CONFIG,
+                    "Problem with config: {}; ignoring width override", err
+                );
+            },
+        }
+    }
+
+    Ok(overrides)
+}
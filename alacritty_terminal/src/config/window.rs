@@ -41,6 +41,10 @@ pub struct WindowConfig {
     #[serde(default = "default_title")]
     pub title: String,
 
+    /// Template for the window title, combining the title set by applications with other state
+    #[serde(deserialize_with = "failure_default")]
+    pub title_template: Option<String>,
+
     /// Window class
     #[serde(deserialize_with = "from_string_or_deserialize")]
     pub class: Class,
@@ -53,6 +57,14 @@ pub struct WindowConfig {
     #[serde(deserialize_with = "option_explicit_none")]
     pub gtk_theme_variant: Option<String>,
 
+    /// Allow applications to resize, minimize and raise the window
+    #[serde(deserialize_with = "failure_default")]
+    pub allow_window_ops: bool,
+
+    /// Allow applications to read the window title
+    #[serde(deserialize_with = "failure_default")]
+    pub allow_title_reports: bool,
+
     /// TODO: DEPRECATED
     #[serde(deserialize_with = "failure_default")]
     pub start_maximized: Option<bool>,
@@ -83,8 +95,11 @@ impl Default for WindowConfig {
             class: Default::default(),
             embed: Default::default(),
             gtk_theme_variant: Default::default(),
+            allow_window_ops: Default::default(),
+            allow_title_reports: Default::default(),
             start_maximized: Default::default(),
             title: default_title(),
+            title_template: Default::default(),
         }
     }
 }
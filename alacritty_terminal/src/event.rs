@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 use std::path::PathBuf;
 
+use crate::ansi::{Notification, WindowOperation, WorkingDirectory};
 use crate::message_bar::Message;
 use crate::term::SizeInfo;
 
@@ -10,6 +11,10 @@ pub enum Event {
     MouseCursorDirty,
     Message(Message),
     Title(String),
+    IconName(String),
+    WorkingDirectory(Option<WorkingDirectory>),
+    Notification(Notification),
+    WindowOperation(WindowOperation),
     Wakeup,
     Urgent,
     Exit,
@@ -5,6 +5,7 @@ use std::fs::File;
 use std::io::{self, ErrorKind, Read, Write};
 use std::marker::Send;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use log::error;
 #[cfg(not(windows))]
@@ -16,13 +17,16 @@ use crate::ansi;
 use crate::config::Config;
 use crate::event::{self, Event, EventListener};
 use crate::sync::FairMutex;
-use crate::term::Term;
+use crate::term::{Term, TermMode};
 use crate::tty;
 use crate::util::thread;
 
 /// Max bytes to read from the PTY
 const MAX_READ: usize = 0x10_000;
 
+/// Maximum time a synchronized update is held back
+const SYNC_UPDATE_TIMEOUT: Duration = Duration::from_millis(150);
+
 /// Messages that may be sent to the `EventLoop`
 #[derive(Debug)]
 pub enum Msg {
@@ -62,6 +66,9 @@ pub struct State {
     write_list: VecDeque<Cow<'static, [u8]>>,
     writing: Option<Writing>,
     parser: ansi::Processor,
+
+    /// Time at which an open synchronized update is forcefully ended
+    sync_deadline: Option<Instant>,
 }
 
 pub struct Notifier(pub Sender<Msg>);
@@ -84,7 +91,12 @@ impl event::Notify for Notifier {
 
 impl Default for State {
     fn default() -> State {
-        State { write_list: VecDeque::new(), parser: ansi::Processor::new(), writing: None }
+        State {
+            write_list: VecDeque::new(),
+            parser: ansi::Processor::new(),
+            writing: None,
+            sync_deadline: None,
+        }
     }
 }
 
@@ -115,6 +127,19 @@ impl State {
     fn set_current(&mut self, new: Option<Writing>) {
         self.writing = new;
     }
+
+    /// Time left until the open synchronized update times out
+    #[inline]
+    fn sync_timeout(&self) -> Option<Duration> {
+        self.sync_deadline.map(|deadline| {
+            let now = Instant::now();
+            if deadline > now {
+                deadline - now
+            } else {
+                Duration::from_secs(0)
+            }
+        })
+    }
 }
 
 impl Writing {
@@ -236,6 +261,9 @@ where
                         state.parser.advance(&mut **terminal, *byte, &mut self.pty.writer());
                     }
 
+                    // Search matches have to be found again in the new content
+                    terminal.invalidate_search_matches();
+
                     // Exit if we've processed enough bytes
                     if processed > MAX_READ {
                         break;
@@ -250,7 +278,17 @@ where
             }
         }
 
-        if processed > 0 {
+        let sync_update =
+            terminal.map_or(false, |terminal| terminal.mode().contains(TermMode::SYNC_UPDATE));
+
+        if sync_update {
+            // Hold back redraws until the update is finished or timed out
+            if state.sync_deadline.is_none() {
+                state.sync_deadline = Some(Instant::now() + SYNC_UPDATE_TIMEOUT);
+            }
+        } else if processed > 0 {
+            state.sync_deadline = None;
+
             // Queue terminal redraw
             self.event_proxy.send_event(Event::Wakeup);
         }
@@ -314,13 +352,25 @@ where
             };
 
             'event_loop: loop {
-                if let Err(err) = self.poll.poll(&mut events, None) {
+                if let Err(err) = self.poll.poll(&mut events, state.sync_timeout()) {
                     match err.kind() {
                         ErrorKind::Interrupted => continue,
                         _ => panic!("EventLoop polling error: {:?}", err),
                     }
                 }
 
+                // End synchronized updates which have not been finished in time
+                if state.sync_timeout() == Some(Duration::from_secs(0)) {
+                    state.sync_deadline = None;
+
+                    let mut terminal = self.terminal.lock();
+                    state.parser.stop_sync(&mut *terminal, &mut self.pty.writer());
+                    terminal.invalidate_search_matches();
+                    drop(terminal);
+
+                    self.event_proxy.send_event(Event::Wakeup);
+                }
+
                 for event in events.iter() {
                     match event.token() {
                         token if token == channel_token => {
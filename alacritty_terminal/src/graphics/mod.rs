@@ -0,0 +1,204 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Images displayed inside the terminal grid
+use std::collections::HashMap;
+use std::mem;
+
+use serde::{Deserialize, Serialize};
+
+pub mod sixel;
+
+/// Decoded image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+
+    /// RGBA pixels, row by row
+    pub pixels: Vec<u8>,
+}
+
+/// Unique identifier of a graphic
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct GraphicId(u64);
+
+/// Part of a graphic covering a single cell
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GraphicCell {
+    pub id: GraphicId,
+
+    /// Column of the cell inside the graphic
+    pub col: u16,
+
+    /// Line of the cell inside the graphic
+    pub line: u16,
+}
+
+/// Graphic which has not been uploaded to the renderer yet
+#[derive(Debug)]
+pub struct Graphic {
+    pub id: GraphicId,
+    pub image: Image,
+
+    /// Cell dimensions at the time the graphic was inserted, in pixels
+    pub cell_width: usize,
+    pub cell_height: usize,
+}
+
+/// Changes to the graphics since the renderer was last updated
+#[derive(Debug, Default)]
+pub struct GraphicUpdates {
+    pub added: Vec<Graphic>,
+    pub removed: Vec<GraphicId>,
+}
+
+/// Lowest line of the grid which displays a graphic
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Anchor {
+    /// Whether the graphic is displayed on the alternate screen
+    pub alt_screen: bool,
+
+    /// Buffer line, counted upwards from the bottom of the screen
+    pub line: usize,
+}
+
+/// Graphics referenced by the cells of the grid
+#[derive(Debug, Default)]
+pub struct Graphics {
+    next_id: u64,
+
+    /// Graphics which are still referenced and where their last line is displayed
+    live: HashMap<GraphicId, Option<Anchor>>,
+
+    updates: GraphicUpdates,
+}
+
+impl Graphics {
+    /// Store a new graphic
+    pub fn insert(&mut self, image: Image, cell_width: usize, cell_height: usize) -> GraphicId {
+        let id = GraphicId(self.next_id);
+        self.next_id += 1;
+
+        self.live.insert(id, None);
+        self.updates.added.push(Graphic { id, image, cell_width, cell_height });
+
+        id
+    }
+
+    /// Record the lowest line displaying a graphic
+    pub fn set_anchor(&mut self, id: GraphicId, anchor: Anchor) {
+        if let Some(live) = self.live.get_mut(&id) {
+            *live = Some(anchor);
+        }
+    }
+
+    /// Move the graphics of a screen up, after its lines have been scrolled
+    ///
+    /// Returns `true` if the last line of a graphic has left the screen and its history, which
+    /// means the graphic is most likely not referenced anymore.
+    pub fn scroll_up(&mut self, alt_screen: bool, positions: usize, total_lines: usize) -> bool {
+        let mut scrolled_out = false;
+        for anchor in self.live.values_mut().filter_map(Option::as_mut) {
+            if anchor.alt_screen == alt_screen {
+                anchor.line += positions;
+                scrolled_out |= anchor.line >= total_lines;
+            }
+        }
+        scrolled_out
+    }
+
+    /// Remove all graphics which are not referenced anymore
+    ///
+    /// The anchors of the remaining graphics are replaced with the ones in `referenced`.
+    pub fn retain(&mut self, mut referenced: HashMap<GraphicId, Anchor>) {
+        let mut removed = Vec::new();
+        for (id, anchor) in self.live.iter_mut() {
+            match referenced.remove(id) {
+                Some(new_anchor) => *anchor = Some(new_anchor),
+                None => removed.push(*id),
+            }
+        }
+
+        if removed.is_empty() {
+            return;
+        }
+
+        for id in &removed {
+            self.live.remove(id);
+        }
+
+        let live = &self.live;
+        self.updates.added.retain(|graphic| live.contains_key(&graphic.id));
+        self.updates.removed.extend(removed);
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.live.is_empty()
+    }
+
+    /// Take the changes which have to be applied to the renderer
+    pub fn take_updates(&mut self) -> GraphicUpdates {
+        mem::replace(&mut self.updates, GraphicUpdates::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{Anchor, Graphics, Image};
+
+    fn image() -> Image {
+        Image { width: 1, height: 1, pixels: vec![0, 0, 0, 255] }
+    }
+
+    #[test]
+    fn retain_referenced_graphics() {
+        let mut graphics = Graphics::default();
+        let first = graphics.insert(image(), 1, 1);
+        let second = graphics.insert(image(), 1, 1);
+
+        let updates = graphics.take_updates();
+        assert_eq!(updates.added.len(), 2);
+        assert!(updates.removed.is_empty());
+
+        let mut referenced = HashMap::new();
+        referenced.insert(second, Anchor { alt_screen: false, line: 0 });
+        graphics.retain(referenced);
+
+        let updates = graphics.take_updates();
+        assert!(updates.added.is_empty());
+        assert_eq!(updates.removed, vec![first]);
+
+        graphics.retain(HashMap::new());
+        assert!(graphics.is_empty());
+    }
+
+    #[test]
+    fn scroll_anchors() {
+        let mut graphics = Graphics::default();
+        let primary = graphics.insert(image(), 1, 1);
+        let alternate = graphics.insert(image(), 1, 1);
+        graphics.set_anchor(primary, Anchor { alt_screen: false, line: 3 });
+        graphics.set_anchor(alternate, Anchor { alt_screen: true, line: 3 });
+
+        assert!(!graphics.scroll_up(false, 1, 5));
+        assert!(graphics.scroll_up(false, 1, 5));
+
+        // Graphics of the other screen are not moved
+        assert!(!graphics.scroll_up(true, 1, 5));
+    }
+}
@@ -0,0 +1,374 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoder for Sixel graphics
+use std::cmp::{max, min};
+
+use log::debug;
+
+use crate::graphics::Image;
+use crate::term::color::Rgb;
+
+/// Maximum width and height of a decoded image in pixels
+pub const MAX_GRAPHIC_DIMENSIONS: usize = 4096;
+
+/// Number of color registers
+const PALETTE_SIZE: usize = 256;
+
+/// Maximum number of parameters of a single command
+const MAX_PARAMS: usize = 5;
+
+/// Default color registers of the VT340, in RGB percentages
+const VT340_PALETTE: [(u32, u32, u32); 16] = [
+    (0, 0, 0),
+    (20, 20, 80),
+    (80, 13, 13),
+    (20, 80, 20),
+    (80, 20, 80),
+    (20, 80, 80),
+    (80, 80, 20),
+    (53, 53, 53),
+    (26, 26, 26),
+    (33, 33, 60),
+    (60, 26, 26),
+    (33, 60, 33),
+    (60, 33, 60),
+    (33, 60, 60),
+    (60, 60, 33),
+    (80, 80, 80),
+];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum State {
+    /// Sixel data and single character commands
+    Ground,
+
+    /// Parameters of the raster attributes command `"`
+    RasterAttributes,
+
+    /// Parameters of the color introducer `#`
+    ColorIntroducer,
+
+    /// Count of the graphics repeat introducer `!`
+    RepeatIntroducer,
+}
+
+/// Incremental Sixel decoder fed with the payload of a DCS sequence
+#[derive(Debug)]
+pub struct Parser {
+    state: State,
+    params: Vec<u32>,
+    palette: Vec<Rgb>,
+    color: usize,
+
+    /// Position of the next sixel, `y` is the top row of the current band
+    x: usize,
+    y: usize,
+
+    /// Size of the image
+    width: usize,
+    height: usize,
+
+    /// Width of a row in `pixels`, grows ahead of `width` to reduce reallocations
+    stride: usize,
+
+    /// RGBA pixels, unset pixels are transparent
+    pixels: Vec<u8>,
+}
+
+impl Default for Parser {
+    fn default() -> Parser {
+        let mut palette = vec![Rgb::default(); PALETTE_SIZE];
+        for (color, &(r, g, b)) in palette.iter_mut().zip(VT340_PALETTE.iter()) {
+            *color = Rgb { r: percent(r), g: percent(g), b: percent(b) };
+        }
+
+        Parser {
+            state: State::Ground,
+            params: Vec::with_capacity(MAX_PARAMS),
+            palette,
+            color: 0,
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+            stride: 0,
+            pixels: Vec::new(),
+        }
+    }
+}
+
+impl Parser {
+    pub fn new() -> Parser {
+        Parser::default()
+    }
+
+    /// Process the next byte of the Sixel payload
+    pub fn put(&mut self, byte: u8) {
+        match (self.state, byte) {
+            (State::Ground, _) => self.ground(byte),
+            (_, b'0'..=b'9') => {
+                if self.params.is_empty() {
+                    self.params.push(0);
+                }
+
+                let param = self.params.last_mut().unwrap();
+                *param = param.saturating_mul(10).saturating_add(u32::from(byte - b'0'));
+            },
+            (_, b';') => {
+                if self.params.is_empty() {
+                    self.params.push(0);
+                }
+
+                if self.params.len() < MAX_PARAMS {
+                    self.params.push(0);
+                }
+            },
+            (State::RepeatIntroducer, b'?'..=b'~') => {
+                let count = self.params.first().map(|&count| max(count, 1)).unwrap_or(1);
+                self.state = State::Ground;
+                self.draw_sixel(byte - b'?', count as usize);
+            },
+            _ => {
+                self.finish_command();
+                self.ground(byte);
+            },
+        }
+    }
+
+    /// Complete the image
+    ///
+    /// Returns `None` if no pixels have been drawn.
+    pub fn finish(mut self) -> Option<Image> {
+        self.finish_command();
+
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+
+        let width = self.width;
+        let pixels = self
+            .pixels
+            .chunks(self.stride * 4)
+            .flat_map(|row| row[..width * 4].iter().copied())
+            .collect();
+
+        Some(Image { width, height: self.height, pixels })
+    }
+
+    fn ground(&mut self, byte: u8) {
+        match byte {
+            b'"' => self.start_command(State::RasterAttributes),
+            b'#' => self.start_command(State::ColorIntroducer),
+            b'!' => self.start_command(State::RepeatIntroducer),
+            b'$' => self.x = 0,
+            b'-' => {
+                self.x = 0;
+                self.y = self.y.saturating_add(6);
+            },
+            b'?'..=b'~' => self.draw_sixel(byte - b'?', 1),
+            _ => (),
+        }
+    }
+
+    fn start_command(&mut self, state: State) {
+        self.state = state;
+        self.params.clear();
+    }
+
+    /// Apply the parameters of the current command
+    fn finish_command(&mut self) {
+        let params = &self.params;
+        match self.state {
+            State::RasterAttributes if params.len() >= 4 => {
+                self.resize(params[2] as usize, params[3] as usize);
+            },
+            State::ColorIntroducer if params.len() == 5 => {
+                let color = params[0] as usize % PALETTE_SIZE;
+                let (x, y, z) = (params[2], params[3], params[4]);
+                match params[1] {
+                    1 => self.palette[color] = hls_to_rgb(x, y, z),
+                    2 => self.palette[color] = Rgb { r: percent(x), g: percent(y), b: percent(z) },
+                    space => debug!("Unknown sixel color space: {}", space),
+                }
+                self.color = color;
+            },
+            State::ColorIntroducer if !params.is_empty() => {
+                self.color = params[0] as usize % PALETTE_SIZE;
+            },
+            _ => (),
+        }
+
+        self.state = State::Ground;
+    }
+
+    /// Draw a column of six pixels `count` times
+    fn draw_sixel(&mut self, bits: u8, count: usize) {
+        let start = self.x;
+        self.x = self.x.saturating_add(count);
+
+        if bits == 0 {
+            return;
+        }
+
+        let end = min(self.x, MAX_GRAPHIC_DIMENSIONS);
+        let last_bit = 7 - bits.leading_zeros() as usize;
+        self.resize(end, self.y.saturating_add(last_bit + 1));
+
+        let color = self.palette[self.color];
+        for bit in (0..6).filter(|bit| bits & (1 << bit) != 0) {
+            let y = self.y.saturating_add(bit);
+            if y >= self.height {
+                break;
+            }
+
+            for x in start..end {
+                let index = (y * self.stride + x) * 4;
+                self.pixels[index..index + 4].copy_from_slice(&[color.r, color.g, color.b, 255]);
+            }
+        }
+    }
+
+    /// Grow the image to at least the specified size
+    fn resize(&mut self, width: usize, height: usize) {
+        let width = min(width, MAX_GRAPHIC_DIMENSIONS);
+        let height = min(height, MAX_GRAPHIC_DIMENSIONS);
+
+        if width > self.stride {
+            let stride = min(max(width, self.stride * 2), MAX_GRAPHIC_DIMENSIONS);
+            let mut pixels = vec![0; stride * self.height * 4];
+            for line in 0..self.height {
+                let src = line * self.stride * 4;
+                let dst = line * stride * 4;
+                pixels[dst..dst + self.width * 4]
+                    .copy_from_slice(&self.pixels[src..src + self.width * 4]);
+            }
+            self.pixels = pixels;
+            self.stride = stride;
+        }
+
+        if height > self.height {
+            self.pixels.resize(self.stride * height * 4, 0);
+            self.height = height;
+        }
+
+        self.width = max(self.width, width);
+    }
+}
+
+/// Convert a percentage to a color component
+fn percent(value: u32) -> u8 {
+    ((min(value, 100) * 255 + 50) / 100) as u8
+}
+
+/// Convert a color in the HLS color space of the VT340 to RGB
+///
+/// Unlike the usual HLS definition, a hue of 0 degrees is blue.
+fn hls_to_rgb(hue: u32, lightness: u32, saturation: u32) -> Rgb {
+    let hue = ((hue % 360 + 240) % 360) as f32 / 60.;
+    let lightness = min(lightness, 100) as f32 / 100.;
+    let saturation = min(saturation, 100) as f32 / 100.;
+
+    let chroma = (1. - (2. * lightness - 1.).abs()) * saturation;
+    let x = chroma * (1. - (hue % 2. - 1.).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.),
+        1 => (x, chroma, 0.),
+        2 => (0., chroma, x),
+        3 => (0., x, chroma),
+        4 => (x, 0., chroma),
+        _ => (chroma, 0., x),
+    };
+
+    let m = lightness - chroma / 2.;
+    let component = |value: f32| ((value + m) * 255.).round() as u8;
+    Rgb { r: component(r), g: component(g), b: component(b) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hls_to_rgb, Parser, MAX_GRAPHIC_DIMENSIONS};
+    use crate::graphics::Image;
+    use crate::term::color::Rgb;
+
+    fn decode(data: &[u8]) -> Option<Image> {
+        let mut parser = Parser::new();
+        for byte in data {
+            parser.put(*byte);
+        }
+        parser.finish()
+    }
+
+    fn pixel(image: &Image, x: usize, y: usize) -> [u8; 4] {
+        let index = (y * image.width + x) * 4;
+        [
+            image.pixels[index],
+            image.pixels[index + 1],
+            image.pixels[index + 2],
+            image.pixels[index + 3],
+        ]
+    }
+
+    #[test]
+    fn decode_colors() {
+        let image = decode(b"#1;2;100;0;0#1~~#2;2;0;0;100A").unwrap();
+
+        assert_eq!(image.width, 3);
+        assert_eq!(image.height, 6);
+        assert_eq!(pixel(&image, 0, 0), [255, 0, 0, 255]);
+        assert_eq!(pixel(&image, 1, 5), [255, 0, 0, 255]);
+        assert_eq!(pixel(&image, 2, 1), [0, 0, 255, 255]);
+        assert_eq!(pixel(&image, 2, 0), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn decode_repeat_and_new_line() {
+        let image = decode(b"#0;2;0;100;0!3@-?$!2@").unwrap();
+
+        assert_eq!(image.width, 3);
+        assert_eq!(image.height, 7);
+        assert_eq!(pixel(&image, 2, 0), [0, 255, 0, 255]);
+        assert_eq!(pixel(&image, 1, 6), [0, 255, 0, 255]);
+        assert_eq!(pixel(&image, 2, 6), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn decode_raster_attributes() {
+        let image = decode(b"\"1;1;10;12#0@").unwrap();
+
+        assert_eq!(image.width, 10);
+        assert_eq!(image.height, 12);
+        assert_eq!(image.pixels.len(), 10 * 12 * 4);
+    }
+
+    #[test]
+    fn decode_empty() {
+        assert_eq!(decode(b"#0;2;0;0;0"), None);
+    }
+
+    #[test]
+    fn decode_limits_size() {
+        let image = decode(b"!99999~").unwrap();
+
+        assert_eq!(image.width, MAX_GRAPHIC_DIMENSIONS);
+        assert_eq!(image.height, 6);
+    }
+
+    #[test]
+    fn hls_hue_starts_at_blue() {
+        assert_eq!(hls_to_rgb(0, 50, 100), Rgb { r: 0, g: 0, b: 255 });
+        assert_eq!(hls_to_rgb(120, 50, 100), Rgb { r: 255, g: 0, b: 0 });
+        assert_eq!(hls_to_rgb(240, 50, 100), Rgb { r: 0, g: 255, b: 0 });
+    }
+}
@@ -23,7 +23,7 @@ use crate::index::{self, Column, IndexRange, Line, Point};
 use crate::selection::Selection;
 
 mod row;
-pub use self::row::Row;
+pub use self::row::{PromptMarks, Row};
 
 #[cfg(test)]
 mod tests;
@@ -70,6 +70,13 @@ pub trait GridCell {
     fn is_empty(&self) -> bool;
     fn is_wrap(&self) -> bool;
     fn set_wrap(&mut self, wrap: bool);
+
+    /// Whether the cell holds a character occupying two columns
+    fn is_wide(&self) -> bool;
+
+    /// Whether the cell pads the end of a line before a wide character wrapped to the next line
+    fn is_leading_spacer(&self) -> bool;
+    fn set_leading_spacer(&mut self, spacer: bool);
 }
 
 /// Represents the terminal display contents
@@ -265,17 +272,38 @@ impl<T: GridCell + Copy + Clone> Grid<T> {
                     && last_row.len() < cols.0
                     && last_row.last().map(GridCell::is_wrap) == Some(true)
                 {
+                    // Wrapped wide characters might fit into the line now
+                    if last_row.last().map(GridCell::is_leading_spacer) == Some(true) {
+                        last_row.pop();
+                    }
+
                     // Remove wrap flag before appending additional cells
                     if let Some(cell) = last_row.last_mut() {
                         cell.set_wrap(false);
                     }
 
                     // Append as many cells from the next line as possible
-                    let len = min(row.len(), cols.0 - last_row.len());
+                    let mut len = min(row.len(), cols.0 - last_row.len());
+
+                    // Don't separate wide characters from their spacer
+                    let split_wide =
+                        len > 0 && len < row.len() && row[index::Column(len - 1)].is_wide();
+                    if split_wide {
+                        len -= 1;
+                    }
+
                     let mut cells = row.front_split_off(len);
+                    if split_wide {
+                        let mut spacer = *template;
+                        spacer.set_leading_spacer(true);
+                        cells.push(spacer);
+                    }
                     last_row.append(&mut cells);
 
                     if row.is_empty() {
+                        // Keep the marks of the line which is removed
+                        last_row.merge_marks(&row);
+
                         let raw_len = i + 1 + new_raw.len();
                         if raw_len < self.lines.0 || self.scroll_limit == 0 {
                             // Add new line and move lines up if we can't pull from history
@@ -327,17 +355,29 @@ impl<T: GridCell + Copy + Clone> Grid<T> {
         let mut new_raw = Vec::with_capacity(self.raw.len());
         let mut buffered = None;
         for (i, mut row) in self.raw.drain().enumerate().rev() {
-            if let Some(buffered) = buffered.take() {
+            if let Some((buffered, marks)) = buffered.take() {
                 row.append_front(buffered);
+                row.marks |= marks;
             }
 
-            let mut wrapped = row.shrink(cols);
+            let mut wrapped = if reflow {
+                Self::shrink_row(&mut row, cols, template)
+            } else {
+                row.shrink(cols)
+            };
             new_raw.push(row);
 
             while let (Some(mut wrapped_cells), true) = (wrapped.take(), reflow) {
-                // Set line as wrapped if cells got removed
-                if let Some(cell) = new_raw.last_mut().and_then(|r| r.last_mut()) {
-                    cell.set_wrap(true);
+                let mut end_mark = PromptMarks::empty();
+                if let Some(row) = new_raw.last_mut() {
+                    // Set line as wrapped if cells got removed
+                    if let Some(cell) = row.last_mut() {
+                        cell.set_wrap(true);
+                    }
+
+                    // The end of a command's output moves along with its last cells
+                    end_mark = row.marks & PromptMarks::OUTPUT_END;
+                    row.marks.remove(PromptMarks::OUTPUT_END);
                 }
 
                 if Some(true) == wrapped_cells.last().map(|c| c.is_wrap() && i >= 1)
@@ -349,7 +389,7 @@ impl<T: GridCell + Copy + Clone> Grid<T> {
                     }
 
                     // Add removed cells to start of next row
-                    buffered = Some(wrapped_cells);
+                    buffered = Some((wrapped_cells, end_mark));
                 } else {
                     // Make sure viewport doesn't move if line is outside of the visible area
                     if i < self.display_offset {
@@ -362,9 +402,10 @@ impl<T: GridCell + Copy + Clone> Grid<T> {
                         wrapped_cells.append(&mut vec![*template; cols.0 - occ]);
                     }
                     let mut row = Row::from_vec(wrapped_cells, occ);
+                    row.marks = end_mark;
 
                     // Since inserted might exceed cols, we need to check it again
-                    wrapped = row.shrink(cols);
+                    wrapped = Self::shrink_row(&mut row, cols, template);
 
                     // Add new row with all removed cells
                     new_raw.push(row);
@@ -381,6 +422,40 @@ impl<T: GridCell + Copy + Clone> Grid<T> {
         self.cols = cols;
     }
 
+    /// Split off all cells of a row which don't fit into `cols` for reflow
+    ///
+    /// Wide characters are never separated from their spacer, instead they are moved to the
+    /// next line and a leading spacer takes their place.
+    fn shrink_row(row: &mut Row<T>, cols: index::Column, template: &T) -> Option<Vec<T>> {
+        let split_wide = cols.0 > 1 && row.len() > cols.0 && row[cols - 1].is_wide();
+        let mut wrapped = if split_wide {
+            let wrapped = row.shrink(cols - 1);
+
+            let mut spacer = *template;
+            spacer.set_leading_spacer(true);
+            row.append(&mut vec![spacer]);
+
+            wrapped
+        } else {
+            row.shrink(cols)
+        }?;
+
+        // Leading spacers are only required at the end of a line, the wide character they were
+        // padding for is already at the start of the next one
+        if wrapped.last().map(GridCell::is_leading_spacer) == Some(true) {
+            let spacer = wrapped.pop()?;
+            if let Some(cell) = wrapped.last_mut().or_else(|| row.last_mut()) {
+                cell.set_wrap(spacer.is_wrap());
+            }
+        }
+
+        if wrapped.is_empty() {
+            None
+        } else {
+            Some(wrapped)
+        }
+    }
+
     /// Remove lines from the visible area
     ///
     /// The behavior in Terminal.app and iTerm.app is to keep the cursor at the
@@ -501,6 +576,54 @@ impl<T: GridCell + Copy + Clone> Grid<T> {
         }
     }
 
+    /// Move the cells between the columns of a region downwards
+    ///
+    /// Unlike `scroll_down`, only the cells inside the region's columns are moved and the
+    /// scrollback history is never affected.
+    pub fn scroll_down_columns(
+        &mut self,
+        region: &Range<index::Line>,
+        columns: &Range<index::Column>,
+        positions: index::Line,
+        template: &T,
+    ) {
+        for line in IndexRange((region.start + positions)..region.end).rev() {
+            for col in IndexRange(columns.clone()) {
+                self[line][col] = self[line - positions][col];
+            }
+        }
+
+        for line in IndexRange(region.start..(region.start + positions)) {
+            for col in IndexRange(columns.clone()) {
+                self[line][col] = *template;
+            }
+        }
+    }
+
+    /// Move the cells between the columns of a region upwards
+    ///
+    /// Unlike `scroll_up`, only the cells inside the region's columns are moved and the
+    /// scrollback history is never affected.
+    pub fn scroll_up_columns(
+        &mut self,
+        region: &Range<index::Line>,
+        columns: &Range<index::Column>,
+        positions: index::Line,
+        template: &T,
+    ) {
+        for line in IndexRange(region.start..(region.end - positions)) {
+            for col in IndexRange(columns.clone()) {
+                self[line][col] = self[line + positions][col];
+            }
+        }
+
+        for line in IndexRange((region.end - positions)..region.end) {
+            for col in IndexRange(columns.clone()) {
+                self[line][col] = *template;
+            }
+        }
+    }
+
     // Completely reset the grid state
     pub fn reset(&mut self, template: &T) {
         // Explicitly purge all lines from history
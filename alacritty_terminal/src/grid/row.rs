@@ -19,11 +19,28 @@ use std::ops::{Index, IndexMut};
 use std::ops::{Range, RangeFrom, RangeFull, RangeTo, RangeToInclusive};
 use std::slice;
 
+use bitflags::bitflags;
 use serde::{Deserialize, Serialize};
 
+use crate::ansi::LineSize;
 use crate::grid::GridCell;
 use crate::index::Column;
 
+bitflags! {
+    /// Shell integration marks of a row
+    #[derive(Default, Serialize, Deserialize)]
+    pub struct PromptMarks: u8 {
+        /// First line of the prompt
+        const PROMPT     = 0b0001;
+        /// Line where the command input starts
+        const COMMAND    = 0b0010;
+        /// First line of the command's output
+        const OUTPUT     = 0b0100;
+        /// Last line of the command's output
+        const OUTPUT_END = 0b1000;
+    }
+}
+
 /// A row in the grid
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct Row<T> {
@@ -38,11 +55,23 @@ pub struct Row<T> {
     /// - 1 means there is a value at index zero, but nowhere else
     /// - `occ == inner.len` means every value is occupied
     pub(crate) occ: usize,
+
+    /// Shell integration marks
+    #[serde(default)]
+    pub(crate) marks: PromptMarks,
+
+    /// Exit status of the command whose prompt starts on this row
+    #[serde(default)]
+    pub(crate) exit_status: Option<i32>,
+
+    /// Size of the characters on this row
+    #[serde(default)]
+    pub(crate) line_size: LineSize,
 }
 
 impl<T: PartialEq> PartialEq for Row<T> {
     fn eq(&self, other: &Self) -> bool {
-        self.inner == other.inner
+        self.inner == other.inner && self.line_size == other.line_size
     }
 }
 
@@ -52,7 +81,13 @@ impl<T: Copy> Row<T> {
         T: GridCell,
     {
         let occ = if template.is_empty() { 0 } else { columns.0 };
-        Row { inner: vec![*template; columns.0], occ }
+        Row {
+            inner: vec![*template; columns.0],
+            occ,
+            marks: PromptMarks::empty(),
+            exit_status: None,
+            line_size: LineSize::Normal,
+        }
     }
 
     pub fn grow(&mut self, cols: Column, template: &T) {
@@ -97,6 +132,10 @@ impl<T: Copy> Row<T> {
         if template.is_empty() {
             self.occ = 0;
         }
+
+        self.marks = PromptMarks::empty();
+        self.exit_status = None;
+        self.line_size = LineSize::Normal;
     }
 }
 
@@ -104,7 +143,38 @@ impl<T: Copy> Row<T> {
 impl<T> Row<T> {
     #[inline]
     pub fn from_vec(vec: Vec<T>, occ: usize) -> Row<T> {
-        Row { inner: vec, occ }
+        Row {
+            inner: vec,
+            occ,
+            marks: PromptMarks::empty(),
+            exit_status: None,
+            line_size: LineSize::Normal,
+        }
+    }
+
+    /// Shell integration marks of this row
+    #[inline]
+    pub fn marks(&self) -> PromptMarks {
+        self.marks
+    }
+
+    /// Exit status of the command whose prompt starts on this row
+    #[inline]
+    pub fn exit_status(&self) -> Option<i32> {
+        self.exit_status
+    }
+
+    /// Size of the characters on this row
+    #[inline]
+    pub fn line_size(&self) -> LineSize {
+        self.line_size
+    }
+
+    /// Move the shell integration marks of another row into this one
+    #[inline]
+    pub(crate) fn merge_marks(&mut self, other: &Row<T>) {
+        self.marks |= other.marks;
+        self.exit_status = self.exit_status.or(other.exit_status);
     }
 
     #[inline]
@@ -132,6 +202,13 @@ impl<T> Row<T> {
         self.inner.append(vec);
     }
 
+    #[inline]
+    pub fn pop(&mut self) -> Option<T> {
+        let cell = self.inner.pop();
+        self.occ = min(self.occ, self.inner.len());
+        cell
+    }
+
     #[inline]
     pub fn append_front(&mut self, mut vec: Vec<T>) {
         self.occ += vec.len();
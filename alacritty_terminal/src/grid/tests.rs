@@ -14,7 +14,7 @@
 
 //! Tests for the Grid
 
-use super::{BidirectionalIterator, Grid};
+use super::{BidirectionalIterator, Grid, PromptMarks};
 use crate::grid::GridCell;
 use crate::index::{Column, Line, Point};
 use crate::term::cell::{Cell, Flags};
@@ -29,6 +29,16 @@ impl GridCell for usize {
     }
 
     fn set_wrap(&mut self, _wrap: bool) {}
+
+    fn is_wide(&self) -> bool {
+        false
+    }
+
+    fn is_leading_spacer(&self) -> bool {
+        false
+    }
+
+    fn set_leading_spacer(&mut self, _spacer: bool) {}
 }
 
 // Scroll up moves lines upwards
@@ -63,6 +73,22 @@ fn scroll_up() {
     assert_eq!(grid[Line(9)].occ, 0);
 }
 
+// Prompt marks move with their lines into the history
+#[test]
+fn scroll_up_keeps_marks() {
+    let mut grid = Grid::new(Line(3), Column(1), 5, 0);
+    grid[Line(0)].marks = PromptMarks::PROMPT;
+    grid[Line(0)].exit_status = Some(1);
+    grid[Line(2)].marks = PromptMarks::OUTPUT;
+
+    grid.scroll_up(&(Line(0)..Line(3)), Line(1), &0);
+
+    assert_eq!(grid[3].marks(), PromptMarks::PROMPT);
+    assert_eq!(grid[3].exit_status(), Some(1));
+    assert_eq!(grid[Line(1)].marks(), PromptMarks::OUTPUT);
+    assert_eq!(grid[Line(2)].marks(), PromptMarks::empty());
+}
+
 // Scroll down moves lines downwards
 #[test]
 fn scroll_down() {
@@ -95,6 +121,33 @@ fn scroll_down() {
     assert_eq!(grid[Line(9)].occ, 1);
 }
 
+// Scrolling columns only moves the cells between them
+#[test]
+fn scroll_columns() {
+    let mut grid = Grid::new(Line(4), Column(3), 0, 0);
+    for i in 0..4 {
+        for j in 0..3 {
+            grid[Line(i)][Column(j)] = i * 3 + j + 1;
+        }
+    }
+
+    grid.scroll_up_columns(&(Line(0)..Line(4)), &(Column(1)..Column(2)), Line(1), &0);
+
+    assert_eq!(grid[Line(0)][Column(0)], 1);
+    assert_eq!(grid[Line(0)][Column(1)], 5);
+    assert_eq!(grid[Line(2)][Column(1)], 11);
+    assert_eq!(grid[Line(3)][Column(1)], 0);
+    assert_eq!(grid[Line(3)][Column(2)], 12);
+
+    grid.scroll_down_columns(&(Line(1)..Line(3)), &(Column(0)..Column(1)), Line(1), &0);
+
+    assert_eq!(grid[Line(0)][Column(0)], 1);
+    assert_eq!(grid[Line(1)][Column(0)], 0);
+    assert_eq!(grid[Line(2)][Column(0)], 4);
+    assert_eq!(grid[Line(3)][Column(0)], 10);
+    assert_eq!(grid.history_size(), 0);
+}
+
 // Test that GridIterator works
 #[test]
 fn test_iter() {
@@ -225,6 +278,37 @@ fn shrink_reflow_empty_cell_inside_line() {
     assert_eq!(grid[0][Column(0)], cell('4'));
 }
 
+#[test]
+fn reflow_wide_char() {
+    let mut grid = Grid::new(Line(1), Column(3), 2, cell('x'));
+    grid[Line(0)][Column(0)] = cell('1');
+    grid[Line(0)][Column(1)] = wide_cell('\u{4E00}');
+    grid[Line(0)][Column(2)] = spacer_cell(Flags::WIDE_CHAR_SPACER);
+
+    grid.resize(true, Line(1), Column(2), &mut Point::new(Line(0), Column(0)), &Cell::default());
+
+    // Wide characters are moved to the next line instead of being split
+    assert_eq!(grid.len(), 2);
+
+    assert_eq!(grid[1].len(), 2);
+    assert_eq!(grid[1][Column(0)], cell('1'));
+    assert_eq!(grid[1][Column(1)], spacer_cell(Flags::LEADING_WIDE_CHAR_SPACER | Flags::WRAPLINE));
+
+    assert_eq!(grid[0].len(), 2);
+    assert_eq!(grid[0][Column(0)], wide_cell('\u{4E00}'));
+    assert_eq!(grid[0][Column(1)], spacer_cell(Flags::WIDE_CHAR_SPACER));
+
+    grid.resize(true, Line(1), Column(3), &mut Point::new(Line(0), Column(0)), &Cell::default());
+
+    // Leading spacers are removed once the wide character fits
+    assert_eq!(grid.len(), 1);
+
+    assert_eq!(grid[0].len(), 3);
+    assert_eq!(grid[0][Column(0)], cell('1'));
+    assert_eq!(grid[0][Column(1)], wide_cell('\u{4E00}'));
+    assert_eq!(grid[0][Column(2)], spacer_cell(Flags::WIDE_CHAR_SPACER));
+}
+
 #[test]
 fn grow_reflow() {
     let mut grid = Grid::new(Line(2), Column(2), 0, cell('x'));
@@ -249,6 +333,29 @@ fn grow_reflow() {
     assert_eq!(grid[0][Column(2)], Cell::default());
 }
 
+#[test]
+fn grow_reflow_keeps_marks() {
+    let mut grid = Grid::new(Line(2), Column(2), 1, cell('x'));
+    grid[Line(0)][Column(0)] = cell('1');
+    grid[Line(0)][Column(1)] = wrap_cell('2');
+    grid[Line(0)].marks = PromptMarks::OUTPUT;
+    grid[Line(1)][Column(0)] = cell('3');
+    grid[Line(1)][Column(1)] = Cell::default();
+    grid[Line(1)].marks = PromptMarks::OUTPUT_END;
+
+    grid.resize(true, Line(2), Column(3), &mut Point::new(Line(0), Column(0)), &Cell::default());
+
+    assert_eq!(grid[1].marks(), PromptMarks::OUTPUT | PromptMarks::OUTPUT_END);
+    assert_eq!(grid[0].marks(), PromptMarks::empty());
+
+    // The end of the output moves to the line with the wrapped cells
+    grid.resize(true, Line(2), Column(2), &mut Point::new(Line(0), Column(0)), &Cell::default());
+
+    assert_eq!(grid.len(), 3);
+    assert_eq!(grid[2].marks(), PromptMarks::OUTPUT);
+    assert_eq!(grid[1].marks(), PromptMarks::OUTPUT_END);
+}
+
 #[test]
 fn grow_reflow_multiline() {
     let mut grid = Grid::new(Line(3), Column(2), 0, cell('x'));
@@ -334,3 +441,15 @@ fn wrap_cell(c: char) -> Cell {
     cell.flags.insert(Flags::WRAPLINE);
     cell
 }
+
+fn wide_cell(c: char) -> Cell {
+    let mut cell = cell(c);
+    cell.flags.insert(Flags::WIDE_CHAR);
+    cell
+}
+
+fn spacer_cell(flags: Flags) -> Cell {
+    let mut cell = Cell::default();
+    cell.flags.insert(flags);
+    cell
+}
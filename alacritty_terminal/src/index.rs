@@ -103,8 +103,8 @@ impl From<Point> for Point<usize> {
     }
 }
 
-impl From<RenderableCell> for Point<Line> {
-    fn from(cell: RenderableCell) -> Self {
+impl<'a> From<&'a RenderableCell> for Point<Line> {
+    fn from(cell: &'a RenderableCell) -> Self {
         Point::new(cell.line, cell.column)
     }
 }
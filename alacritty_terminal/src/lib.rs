@@ -22,11 +22,13 @@
 extern crate objc;
 
 pub mod ansi;
+mod builtin_font;
 pub mod clipboard;
 pub mod config;
 mod cursor;
 pub mod event;
 pub mod event_loop;
+pub mod graphics;
 pub mod grid;
 pub mod index;
 pub mod locale;
@@ -37,8 +39,10 @@ pub mod renderer;
 pub mod selection;
 pub mod sync;
 pub mod term;
+pub mod termcap;
 pub mod tty;
 pub mod util;
+pub mod vi_mode;
 
 pub use crate::grid::Grid;
 pub use crate::term::Term;
@@ -22,17 +22,23 @@ use std::sync::mpsc;
 use std::time::Duration;
 
 use fnv::FnvHasher;
-use font::{self, FontDesc, FontKey, GlyphKey, Rasterize, RasterizedGlyph, Rasterizer};
+use font::{
+    self, FontDesc, FontKey, GlyphKey, KeyType, Rasterize, RasterizedGlyph, Rasterizer, ShapedGlyph,
+};
 use log::{error, info};
 use notify::{watcher, DebouncedEvent, RecursiveMode, Watcher};
+use unicode_width::UnicodeWidthChar;
 
+use crate::ansi::LineSize;
+use crate::builtin_font::{get_builtin_glyph, is_builtin_glyph};
 use crate::config::{self, Config, Delta, Font, StartupMode};
 use crate::cursor::{get_cursor_glyph, CursorKey};
 use crate::gl;
 use crate::gl::types::*;
+use crate::graphics::{GraphicId, GraphicUpdates};
 use crate::index::{Column, Line};
 use crate::renderer::rects::RenderRect;
-use crate::term::cell::{self, Flags};
+use crate::term::cell::Flags;
 use crate::term::color::Rgb;
 use crate::term::SizeInfo;
 use crate::term::{self, RenderableCell, RenderableCellContent};
@@ -45,6 +51,10 @@ static TEXT_SHADER_F_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../res/t
 static TEXT_SHADER_V_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../res/text.v.glsl");
 static RECT_SHADER_F_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../res/rect.f.glsl");
 static RECT_SHADER_V_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../res/rect.v.glsl");
+static GRAPHICS_SHADER_F_PATH: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/../res/graphics.f.glsl");
+static GRAPHICS_SHADER_V_PATH: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/../res/graphics.v.glsl");
 
 // Shader source which is used when live-shader-reload feature is disable
 static TEXT_SHADER_F: &str =
@@ -55,6 +65,10 @@ static RECT_SHADER_F: &str =
     include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../res/rect.f.glsl"));
 static RECT_SHADER_V: &str =
     include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../res/rect.v.glsl"));
+static GRAPHICS_SHADER_F: &str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../res/graphics.f.glsl"));
+static GRAPHICS_SHADER_V: &str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../res/graphics.v.glsl"));
 
 /// `LoadGlyph` allows for copying a rasterized glyph into graphics memory
 pub trait LoadGlyph {
@@ -135,6 +149,31 @@ pub struct RectShaderProgram {
     id: GLuint,
     /// Rectangle color
     u_color: GLint,
+    /// Shape drawn inside the rectangle
+    u_rect_kind: GLint,
+    /// Rectangle position and size in pixels
+    u_rect: GLint,
+    /// Cell width in pixels
+    u_cell_width: GLint,
+}
+
+/// Graphics drawing program
+#[derive(Debug)]
+pub struct GraphicsShaderProgram {
+    // Program id
+    id: GLuint,
+}
+
+/// Texture of a graphic
+#[derive(Debug)]
+struct GraphicTexture {
+    id: GLuint,
+    width: usize,
+    height: usize,
+
+    /// Cell dimensions at the time the graphic was inserted, in pixels
+    cell_width: usize,
+    cell_height: usize,
 }
 
 #[derive(Copy, Debug, Clone)]
@@ -148,16 +187,24 @@ pub struct Glyph {
     uv_left: f32,
     uv_width: f32,
     uv_height: f32,
+    colored: bool,
 }
 
+/// Maximum number of shaped runs of text kept in the cache
+const SHAPE_CACHE_SIZE: usize = 4096;
+
 /// Naïve glyph cache
 ///
-/// Currently only keyed by `char`, and thus not possible to hold different
-/// representations of the same code point.
+/// Keyed by `char` for individually rendered characters and by glyph index for
+/// the glyphs of shaped text, like ligatures.
 pub struct GlyphCache {
     /// Cache of buffered glyphs
     cache: HashMap<GlyphKey, Glyph, BuildHasherDefault<FnvHasher>>,
 
+    /// Cache of shaped runs of text
+    shape_cache:
+        HashMap<(FontKey, String), Option<Vec<ShapedGlyph>>, BuildHasherDefault<FnvHasher>>,
+
     /// Cache of buffered cursor glyphs
     cursor_cache: HashMap<CursorKey, Glyph, BuildHasherDefault<FnvHasher>>,
 
@@ -182,6 +229,18 @@ pub struct GlyphCache {
     /// glyph offset
     glyph_offset: Delta<i8>,
 
+    /// extra spacing per character
+    font_offset: Delta<i8>,
+
+    /// draw box drawing characters without the font
+    builtin_box_drawing: bool,
+
+    /// shape text to allow ligatures
+    ligatures: bool,
+
+    /// OpenType features applied while shaping
+    font_features: Vec<font::Feature>,
+
     metrics: ::font::Metrics,
 }
 
@@ -199,12 +258,17 @@ impl GlyphCache {
         // Need to load at least one glyph for the face before calling metrics.
         // The glyph requested here ('m' at the time of writing) has no special
         // meaning.
-        rasterizer.get_glyph(GlyphKey { font_key: regular, c: 'm', size: font.size })?;
+        rasterizer.get_glyph(GlyphKey {
+            font_key: regular,
+            id: KeyType::Char('m'),
+            size: font.size,
+        })?;
 
         let metrics = rasterizer.metrics(regular, font.size)?;
 
         let mut cache = GlyphCache {
             cache: HashMap::default(),
+            shape_cache: HashMap::default(),
             cursor_cache: HashMap::default(),
             rasterizer,
             font_size: font.size,
@@ -213,6 +277,10 @@ impl GlyphCache {
             italic_key: italic,
             bold_italic_key: bold_italic,
             glyph_offset: font.glyph_offset,
+            font_offset: font.offset,
+            builtin_box_drawing: font.builtin_box_drawing(),
+            ligatures: font.ligatures.enabled(),
+            font_features: font.ligatures.features.clone(),
             metrics,
         };
 
@@ -227,7 +295,7 @@ impl GlyphCache {
     fn load_glyphs_for_font<L: LoadGlyph>(&mut self, font: FontKey, loader: &mut L) {
         let size = self.font_size;
         for i in 32u8..=126u8 {
-            self.get(GlyphKey { font_key: font, c: i as char, size }, loader);
+            self.get(GlyphKey { font_key: font, id: KeyType::Char(i as char), size }, loader);
         }
     }
 
@@ -291,9 +359,22 @@ impl GlyphCache {
         L: LoadGlyph,
     {
         let glyph_offset = self.glyph_offset;
+        let font_offset = self.font_offset;
+        let builtin_box_drawing = self.builtin_box_drawing;
         let rasterizer = &mut self.rasterizer;
         let metrics = &self.metrics;
         self.cache.entry(glyph_key).or_insert_with(|| {
+            // Built-in glyphs fill the cell exactly, so they're not offset like font glyphs
+            let builtin = match glyph_key.id {
+                KeyType::Char(c) if builtin_box_drawing => {
+                    get_builtin_glyph(c, *metrics, font_offset)
+                },
+                _ => None,
+            };
+            if let Some(rasterized) = builtin {
+                return loader.load_glyph(&rasterized);
+            }
+
             let mut rasterized =
                 rasterizer.get_glyph(glyph_key).unwrap_or_else(|_| Default::default());
 
@@ -305,6 +386,40 @@ impl GlyphCache {
         })
     }
 
+    /// Check if a character is drawn without the font
+    fn is_builtin(&self, c: char) -> bool {
+        self.builtin_box_drawing && is_builtin_glyph(c)
+    }
+
+    /// Font key for the style of a cell
+    fn key_for_flags(&self, flags: Flags) -> FontKey {
+        match flags & Flags::BOLD_ITALIC {
+            Flags::BOLD_ITALIC => self.bold_italic_key,
+            Flags::ITALIC => self.italic_key,
+            Flags::BOLD => self.bold_key,
+            _ => self.font_key,
+        }
+    }
+
+    /// Shape a run of text, returning `None` if the rasterizer can't shape text
+    fn shape(&mut self, text: String, font_key: FontKey) -> Option<Vec<ShapedGlyph>> {
+        let key = (font_key, text);
+        if let Some(glyphs) = self.shape_cache.get(&key) {
+            return glyphs.clone();
+        }
+
+        let glyphs = self.rasterizer.shape(&key.1, font_key, self.font_size, &self.font_features);
+
+        // Most runs are shaped again in the next frame, so it's enough to
+        // start over once the cache is full
+        if self.shape_cache.len() >= SHAPE_CACHE_SIZE {
+            self.shape_cache.clear();
+        }
+        self.shape_cache.insert(key, glyphs.clone());
+
+        glyphs
+    }
+
     pub fn update_font_size<L: LoadGlyph>(
         &mut self,
         font: config::Font,
@@ -314,6 +429,7 @@ impl GlyphCache {
         // Clear currently cached data in both GL and the registry
         loader.clear();
         self.cache = HashMap::default();
+        self.shape_cache = HashMap::default();
         self.cursor_cache = HashMap::default();
 
         // Update dpi scaling
@@ -323,7 +439,11 @@ impl GlyphCache {
         let (regular, bold, italic, bold_italic) =
             Self::compute_font_keys(&font, &mut self.rasterizer)?;
 
-        self.rasterizer.get_glyph(GlyphKey { font_key: regular, c: 'm', size: font.size })?;
+        self.rasterizer.get_glyph(GlyphKey {
+            font_key: regular,
+            id: KeyType::Char('m'),
+            size: font.size,
+        })?;
         let metrics = self.rasterizer.metrics(regular, font.size)?;
 
         info!("Font size changed to {:?} with DPR of {}", font.size, dpr);
@@ -333,6 +453,10 @@ impl GlyphCache {
         self.bold_key = bold;
         self.italic_key = italic;
         self.bold_italic_key = bold_italic;
+        self.font_offset = font.offset;
+        self.builtin_box_drawing = font.builtin_box_drawing();
+        self.ligatures = font.ligatures.enabled();
+        self.font_features = font.ligatures.features.clone();
         self.metrics = metrics;
 
         self.load_glyphs_for_font(regular, loader);
@@ -355,7 +479,11 @@ impl GlyphCache {
         let regular_desc =
             GlyphCache::make_desc(&font.normal(), font::Slant::Normal, font::Weight::Normal);
         let regular = rasterizer.load_font(&regular_desc, font.size)?;
-        rasterizer.get_glyph(GlyphKey { font_key: regular, c: 'm', size: font.size })?;
+        rasterizer.get_glyph(GlyphKey {
+            font_key: regular,
+            id: KeyType::Char('m'),
+            size: font.size,
+        })?;
 
         rasterizer.metrics(regular, font.size)
     }
@@ -416,6 +544,12 @@ struct InstanceData {
     bg_g: f32,
     bg_b: f32,
     bg_a: f32,
+    // line size
+    x_scale: f32,
+    y_scale: f32,
+    bottom_half: f32,
+    // glyph has its own colors
+    colored: f32,
 }
 
 #[derive(Debug)]
@@ -427,6 +561,10 @@ pub struct QuadRenderer {
     vbo_instance: GLuint,
     rect_vao: GLuint,
     rect_vbo: GLuint,
+    graphics_program: GraphicsShaderProgram,
+    graphics_vao: GLuint,
+    graphics_vbo: GLuint,
+    graphic_textures: HashMap<GraphicId, GraphicTexture>,
     atlas: Vec<Atlas>,
     current_atlas: usize,
     active_tex: GLuint,
@@ -469,7 +607,7 @@ impl Batch {
         Batch { tex: 0, instances: Vec::with_capacity(BATCH_MAX) }
     }
 
-    pub fn add_item(&mut self, cell: RenderableCell, glyph: &Glyph) {
+    pub fn add_item(&mut self, cell: &RenderableCell, glyph: &Glyph) {
         if self.is_empty() {
             self.tex = glyph.tex_id;
         }
@@ -496,6 +634,15 @@ impl Batch {
             bg_g: f32::from(cell.bg.g),
             bg_b: f32::from(cell.bg.b),
             bg_a: cell.bg_alpha,
+
+            x_scale: if cell.line_size.is_double_width() { 2. } else { 1. },
+            y_scale: match cell.line_size {
+                LineSize::DoubleHeightTop | LineSize::DoubleHeightBottom => 2.,
+                _ => 1.,
+            },
+            bottom_half: if cell.line_size == LineSize::DoubleHeightBottom { 1. } else { 0. },
+
+            colored: if glyph.colored { 1. } else { 0. },
         });
     }
 
@@ -538,6 +685,7 @@ impl QuadRenderer {
     pub fn new() -> Result<QuadRenderer, Error> {
         let program = TextShaderProgram::new()?;
         let rect_program = RectShaderProgram::new()?;
+        let graphics_program = GraphicsShaderProgram::new()?;
 
         let mut vao: GLuint = 0;
         let mut ebo: GLuint = 0;
@@ -548,6 +696,9 @@ impl QuadRenderer {
         let mut rect_vbo: GLuint = 0;
         let mut rect_ebo: GLuint = 0;
 
+        let mut graphics_vao: GLuint = 0;
+        let mut graphics_vbo: GLuint = 0;
+
         unsafe {
             gl::Enable(gl::BLEND);
             gl::BlendFunc(gl::SRC1_COLOR, gl::ONE_MINUS_SRC1_COLOR);
@@ -639,6 +790,28 @@ impl QuadRenderer {
             );
             gl::EnableVertexAttribArray(4);
             gl::VertexAttribDivisor(4, 1);
+            // line size
+            gl::VertexAttribPointer(
+                5,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                size_of::<InstanceData>() as i32,
+                (17 * size_of::<f32>()) as *const _,
+            );
+            gl::EnableVertexAttribArray(5);
+            gl::VertexAttribDivisor(5, 1);
+            // colored glyph
+            gl::VertexAttribPointer(
+                6,
+                1,
+                gl::FLOAT,
+                gl::FALSE,
+                size_of::<InstanceData>() as i32,
+                (20 * size_of::<f32>()) as *const _,
+            );
+            gl::EnableVertexAttribArray(6);
+            gl::VertexAttribDivisor(6, 1);
 
             // Rectangle setup
             gl::GenVertexArrays(1, &mut rect_vao);
@@ -654,6 +827,32 @@ impl QuadRenderer {
                 gl::STATIC_DRAW,
             );
 
+            // Graphics setup
+            gl::GenVertexArrays(1, &mut graphics_vao);
+            gl::GenBuffers(1, &mut graphics_vbo);
+            gl::BindVertexArray(graphics_vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, graphics_vbo);
+            // Position
+            gl::VertexAttribPointer(
+                0,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                (4 * size_of::<f32>()) as i32,
+                ptr::null(),
+            );
+            gl::EnableVertexAttribArray(0);
+            // Texture coordinates
+            gl::VertexAttribPointer(
+                1,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                (4 * size_of::<f32>()) as i32,
+                (2 * size_of::<f32>()) as *const _,
+            );
+            gl::EnableVertexAttribArray(1);
+
             // Cleanup
             gl::BindVertexArray(0);
             gl::BindBuffer(gl::ARRAY_BUFFER, 0);
@@ -699,6 +898,10 @@ impl QuadRenderer {
             vbo_instance,
             rect_vao,
             rect_vbo,
+            graphics_program,
+            graphics_vao,
+            graphics_vbo,
+            graphic_textures: HashMap::new(),
             atlas: Vec::new(),
             current_atlas: 0,
             active_tex: 0,
@@ -766,6 +969,152 @@ impl QuadRenderer {
         }
     }
 
+    /// Upload the textures of new graphics and delete those of removed ones
+    pub fn update_graphics(&mut self, updates: GraphicUpdates) {
+        for graphic in updates.added {
+            let mut id: GLuint = 0;
+            unsafe {
+                gl::GenTextures(1, &mut id);
+                gl::BindTexture(gl::TEXTURE_2D, id);
+                gl::TexImage2D(
+                    gl::TEXTURE_2D,
+                    0,
+                    gl::RGBA as i32,
+                    graphic.image.width as i32,
+                    graphic.image.height as i32,
+                    0,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    graphic.image.pixels.as_ptr() as *const _,
+                );
+
+                // Cells extending past the image are transparent
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_BORDER as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_BORDER as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+
+                gl::BindTexture(gl::TEXTURE_2D, 0);
+            }
+
+            let texture = GraphicTexture {
+                id,
+                width: graphic.image.width,
+                height: graphic.image.height,
+                cell_width: graphic.cell_width,
+                cell_height: graphic.cell_height,
+            };
+            self.graphic_textures.insert(graphic.id, texture);
+        }
+
+        for id in updates.removed {
+            if let Some(texture) = self.graphic_textures.remove(&id) {
+                unsafe {
+                    gl::DeleteTextures(1, &texture.id);
+                }
+            }
+        }
+
+        // Force rebinding the glyph atlas
+        self.active_tex = 0;
+    }
+
+    /// Draw the parts of graphics covering the specified cells
+    pub fn draw_graphics(&mut self, props: &term::SizeInfo, cells: &[RenderableCell]) {
+        // Collect the vertices of all cells, grouped by texture
+        let mut batches: HashMap<GLuint, Vec<f32>> = HashMap::new();
+        for cell in cells {
+            let graphic = match cell.graphic {
+                Some(graphic) => graphic,
+                None => continue,
+            };
+            let texture = match self.graphic_textures.get(&graphic.id) {
+                Some(texture) => texture,
+                None => continue,
+            };
+
+            // Calculate cell position
+            let center_x = props.width / 2.;
+            let center_y = props.height / 2.;
+            let x = props.cell_width.mul_add(cell.column.0 as f32, props.padding_x);
+            let y = props.cell_height.mul_add(cell.line.0 as f32, props.padding_y);
+            let x = (x - center_x) / center_x;
+            let y = -(y - center_y) / center_y;
+            let width = props.cell_width / center_x;
+            let height = props.cell_height / center_y;
+
+            // Calculate the part of the texture covered by the cell
+            let col = usize::from(graphic.col);
+            let line = usize::from(graphic.line);
+            let u = (col * texture.cell_width) as f32 / texture.width as f32;
+            let v = (line * texture.cell_height) as f32 / texture.height as f32;
+            let u_end = ((col + 1) * texture.cell_width) as f32 / texture.width as f32;
+            let v_end = ((line + 1) * texture.cell_height) as f32 / texture.height as f32;
+
+            #[rustfmt::skip]
+            let vertices = [
+                x, y, u, v,
+                x + width, y, u_end, v,
+                x, y - height, u, v_end,
+                x + width, y, u_end, v,
+                x + width, y - height, u_end, v_end,
+                x, y - height, u, v_end,
+            ];
+            batches.entry(texture.id).or_default().extend_from_slice(&vertices);
+        }
+
+        if batches.is_empty() {
+            return;
+        }
+
+        unsafe {
+            // Swap program
+            gl::UseProgram(self.graphics_program.id);
+
+            // Remove padding from viewport
+            gl::Viewport(0, 0, props.width as i32, props.height as i32);
+
+            // Change blending strategy
+            gl::BlendFuncSeparate(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA, gl::SRC_ALPHA, gl::ONE);
+
+            // Setup data and buffers
+            gl::BindVertexArray(self.graphics_vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.graphics_vbo);
+            gl::ActiveTexture(gl::TEXTURE0);
+
+            for (texture, vertices) in batches {
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+                gl::BufferData(
+                    gl::ARRAY_BUFFER,
+                    (size_of::<f32>() * vertices.len()) as _,
+                    vertices.as_ptr() as *const _,
+                    gl::STREAM_DRAW,
+                );
+                gl::DrawArrays(gl::TRIANGLES, 0, (vertices.len() / 4) as GLsizei);
+            }
+
+            // Reset blending strategy
+            gl::BlendFunc(gl::SRC1_COLOR, gl::ONE_MINUS_SRC1_COLOR);
+
+            // Reset data and buffers
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+
+            let padding_x = props.padding_x as i32;
+            let padding_y = props.padding_y as i32;
+            let width = props.width as i32;
+            let height = props.height as i32;
+            gl::Viewport(padding_x, padding_y, width - 2 * padding_x, height - 2 * padding_y);
+
+            // Disable program
+            gl::UseProgram(0);
+        }
+
+        // Force rebinding the glyph atlas
+        self.active_tex = 0;
+    }
+
     pub fn with_api<F, T, C>(&mut self, config: &Config<C>, props: &term::SizeInfo, func: F) -> T
     where
         F: FnOnce(RenderApi<'_, C>) -> T,
@@ -823,9 +1172,10 @@ impl QuadRenderer {
 
     pub fn reload_shaders(&mut self, props: &term::SizeInfo) {
         info!("Reloading shaders...");
-        let result = (TextShaderProgram::new(), RectShaderProgram::new());
-        let (program, rect_program) = match result {
-            (Ok(program), Ok(rect_program)) => {
+        let result =
+            (TextShaderProgram::new(), RectShaderProgram::new(), GraphicsShaderProgram::new());
+        let (program, rect_program, graphics_program) = match result {
+            (Ok(program), Ok(rect_program), Ok(graphics_program)) => {
                 unsafe {
                     gl::UseProgram(program.id);
                     program.update_projection(
@@ -838,9 +1188,9 @@ impl QuadRenderer {
                 }
 
                 info!("... successfully reloaded shaders");
-                (program, rect_program)
+                (program, rect_program, graphics_program)
             },
-            (Err(err), _) | (_, Err(err)) => {
+            (Err(err), ..) | (_, Err(err), _) | (.., Err(err)) => {
                 error!("{}", err);
                 return;
             },
@@ -849,6 +1199,7 @@ impl QuadRenderer {
         self.active_tex = 0;
         self.program = program;
         self.rect_program = rect_program;
+        self.graphics_program = graphics_program;
     }
 
     pub fn resize(&mut self, size: &SizeInfo) {
@@ -900,6 +1251,9 @@ impl QuadRenderer {
             // Color
             self.rect_program.set_color(rect.color, rect.alpha);
 
+            // Shape
+            self.rect_program.set_rect(rect, size);
+
             // Draw the rectangle
             gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, ptr::null());
         }
@@ -978,15 +1332,16 @@ impl<'a, C> RenderApi<'a, C> {
             .map(|(i, c)| RenderableCell {
                 line,
                 column: col + i,
-                inner: RenderableCellContent::Chars({
-                    let mut chars = [' '; cell::MAX_ZEROWIDTH_CHARS + 1];
-                    chars[0] = c;
-                    chars
-                }),
+                inner: RenderableCellContent::Chars(c, None),
                 bg: color.unwrap_or(Rgb { r: 0, g: 0, b: 0 }),
                 fg: Rgb { r: 0, g: 0, b: 0 },
                 flags: Flags::empty(),
                 bg_alpha,
+                hyperlink: None,
+                graphic: None,
+                underline: Rgb { r: 0, g: 0, b: 0 },
+                line_size: LineSize::Normal,
+                selected: false,
             })
             .collect::<Vec<_>>();
 
@@ -996,7 +1351,7 @@ impl<'a, C> RenderApi<'a, C> {
     }
 
     #[inline]
-    fn add_render_item(&mut self, cell: RenderableCell, glyph: &Glyph) {
+    fn add_render_item(&mut self, cell: &RenderableCell, glyph: &Glyph) {
         // Flush batch if tex changing
         if !self.batch.is_empty() && self.batch.tex != glyph.tex_id {
             self.render_batch();
@@ -1010,55 +1365,194 @@ impl<'a, C> RenderApi<'a, C> {
         }
     }
 
+    /// Render cells, shaping runs of cells with the same style to allow ligatures
+    pub fn render_cells<I>(&mut self, cells: I, glyph_cache: &mut GlyphCache)
+    where
+        I: IntoIterator<Item = RenderableCell>,
+    {
+        if !glyph_cache.ligatures {
+            for cell in cells {
+                self.render_cell(cell, glyph_cache);
+            }
+            return;
+        }
+
+        let mut run: Vec<RenderableCell> = Vec::new();
+        let mut cursor = None;
+
+        for cell in cells {
+            let shapeable = match cell.inner {
+                RenderableCellContent::Cursor(_) => {
+                    cursor = Some((cell.line, cell.column));
+                    false
+                },
+                // Ligatures never continue through the cursor or built-in glyphs
+                RenderableCellContent::Chars(c, _) => {
+                    cursor != Some((cell.line, cell.column))
+                        && !glyph_cache.is_builtin(c)
+                        && !cell.flags.contains(Flags::HIDDEN)
+                        && cell.line_size == LineSize::Normal
+                },
+            };
+
+            if !shapeable {
+                self.render_run(&mut run, glyph_cache);
+                self.render_cell(cell, glyph_cache);
+                continue;
+            }
+
+            if run.last().map_or(false, |last| !Self::continues_run(last, &cell)) {
+                self.render_run(&mut run, glyph_cache);
+            }
+            run.push(cell);
+        }
+
+        self.render_run(&mut run, glyph_cache);
+    }
+
+    /// Check if a cell can be shaped together with the cell before it
+    ///
+    /// Runs are split whenever the style changes, which includes the edges of a selection.
+    fn continues_run(last: &RenderableCell, cell: &RenderableCell) -> bool {
+        let adjacent = cell.column == last.column + 1
+            || (last.flags.contains(Flags::WIDE_CHAR) && cell.column == last.column + 2);
+
+        adjacent
+            && cell.line == last.line
+            && cell.fg == last.fg
+            && cell.selected == last.selected
+            && cell.flags & Flags::BOLD_ITALIC == last.flags & Flags::BOLD_ITALIC
+    }
+
+    /// Render a run of cells as shaped text, which empties the run
+    fn render_run(&mut self, run: &mut Vec<RenderableCell>, glyph_cache: &mut GlyphCache) {
+        // Nothing to combine with a single cell
+        if run.len() < 2 {
+            for cell in run.drain(..) {
+                self.render_cell(cell, glyph_cache);
+            }
+            return;
+        }
+
+        // Byte offset of every cell's text, spacers of wide characters don't add any text
+        let mut text = String::new();
+        let mut offsets = Vec::with_capacity(run.len());
+        for cell in run.iter() {
+            let spacer =
+                cell.flags.intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER);
+            match cell.inner {
+                RenderableCellContent::Chars(c, ref cluster) if !spacer => {
+                    offsets.push(Some(text.len() as u32));
+
+                    // Render tabs as spaces in case the font doesn't support it
+                    text.push(if c == '\t' { ' ' } else { c });
+                    text.push_str(cluster.as_ref().map_or("", |cluster| &**cluster));
+                },
+                _ => offsets.push(None),
+            }
+        }
+
+        let font_key = glyph_cache.key_for_flags(run[0].flags);
+        let mut glyphs = match glyph_cache.shape(text, font_key) {
+            Some(glyphs) => glyphs,
+            None => {
+                for cell in run.drain(..) {
+                    self.render_cell(cell, glyph_cache);
+                }
+                return;
+            },
+        };
+
+        // Glyphs of right-to-left text are in visual order
+        glyphs.sort_by_key(|glyph| glyph.cluster);
+
+        let size = glyph_cache.font_size;
+        let mut glyphs = &glyphs[..];
+        for (cell, offset) in run.drain(..).zip(offsets) {
+            // Glyphs of the cell's cluster, ligatures of the previous cells might already cover it
+            let len = match offset {
+                Some(offset) => {
+                    let skip = glyphs.iter().take_while(|glyph| glyph.cluster < offset).count();
+                    glyphs = &glyphs[skip..];
+                    glyphs.iter().take_while(|glyph| glyph.cluster == offset).count()
+                },
+                None => 0,
+            };
+            let (cell_glyphs, rest) = glyphs.split_at(len);
+            glyphs = rest;
+
+            // Characters missing from the font are rendered with a fallback font instead
+            if cell_glyphs.iter().any(|glyph| glyph.glyph_index == 0) {
+                self.render_cell(cell, glyph_cache);
+                continue;
+            }
+
+            // Render the cell background even if it has no glyph of its own
+            if cell_glyphs.is_empty() {
+                let glyph_key = GlyphKey { font_key, size, id: KeyType::Char(' ') };
+                let glyph = *glyph_cache.get(glyph_key, self);
+                self.add_render_item(&cell, &glyph);
+                continue;
+            }
+
+            let mut pen = 0.;
+            for shaped in cell_glyphs {
+                let glyph_key =
+                    GlyphKey { font_key, size, id: KeyType::GlyphIndex(shaped.glyph_index) };
+                let mut glyph = *glyph_cache.get(glyph_key, self);
+                glyph.left += pen + shaped.x_offset;
+                glyph.top += shaped.y_offset;
+                self.add_render_item(&cell, &glyph);
+
+                pen += shaped.x_advance;
+            }
+        }
+    }
+
     pub fn render_cell(&mut self, cell: RenderableCell, glyph_cache: &mut GlyphCache) {
-        let chars = match cell.inner {
+        let (c, cluster) = match cell.inner {
             RenderableCellContent::Cursor(cursor_key) => {
                 // Raw cell pixel buffers like cursors don't need to go through font lookup
                 let metrics = glyph_cache.metrics;
                 let glyph = glyph_cache.cursor_cache.entry(cursor_key).or_insert_with(|| {
                     self.load_glyph(&get_cursor_glyph(
-                        cursor_key.style,
+                        cursor_key.shape,
                         metrics,
                         self.config.font.offset.x,
                         self.config.font.offset.y,
                         cursor_key.is_wide,
                     ))
                 });
-                self.add_render_item(cell, &glyph);
+                self.add_render_item(&cell, glyph);
                 return;
             },
-            RenderableCellContent::Chars(chars) => chars,
+            RenderableCellContent::Chars(c, ref cluster) => {
+                (c, cluster.as_ref().map(|cluster| &**cluster))
+            },
         };
 
         // Get font key for cell
-        let font_key = match cell.flags & Flags::BOLD_ITALIC {
-            Flags::BOLD_ITALIC => glyph_cache.bold_italic_key,
-            Flags::ITALIC => glyph_cache.italic_key,
-            Flags::BOLD => glyph_cache.bold_key,
-            _ => glyph_cache.font_key,
-        };
+        let font_key = glyph_cache.key_for_flags(cell.flags);
 
         // Don't render text of HIDDEN cells
-        let mut chars = if cell.flags.contains(Flags::HIDDEN) {
-            [' '; cell::MAX_ZEROWIDTH_CHARS + 1]
-        } else {
-            chars
-        };
+        let (c, cluster) =
+            if cell.flags.contains(Flags::HIDDEN) { (' ', None) } else { (c, cluster) };
 
         // Render tabs as spaces in case the font doesn't support it
-        if chars[0] == '\t' {
-            chars[0] = ' ';
-        }
+        let c = if c == '\t' { ' ' } else { c };
 
-        let mut glyph_key = GlyphKey { font_key, size: glyph_cache.font_size, c: chars[0] };
+        let mut glyph_key =
+            GlyphKey { font_key, size: glyph_cache.font_size, id: KeyType::Char(c) };
 
         // Add cell to batch
         let glyph = glyph_cache.get(glyph_key, self);
-        self.add_render_item(cell, glyph);
+        self.add_render_item(&cell, glyph);
 
-        // Render zero-width characters
-        for c in (&chars[1..]).iter().filter(|c| **c != ' ') {
-            glyph_key.c = *c;
+        // Render the zero-width characters of the grapheme cluster on top of the first character,
+        // the glyphs of other characters can only be combined by shaping the whole cluster
+        let zerowidth = cluster.unwrap_or("").chars().filter(|c| c.width() == Some(0));
+        for c in zerowidth {
+            glyph_key.id = KeyType::Char(c);
             let mut glyph = *glyph_cache.get(glyph_key, self);
 
             // The metrics of zero-width characters are based on rendering
@@ -1068,7 +1562,7 @@ impl<'a, C> RenderApi<'a, C> {
             // anchor has been moved to the right by one cell.
             glyph.left += glyph_cache.metrics.average_advance as f32;
 
-            self.add_render_item(cell, &glyph);
+            self.add_render_item(&cell, &glyph);
         }
     }
 }
@@ -1106,6 +1600,7 @@ fn load_glyph(
             uv_left: 0.0,
             uv_width: 0.0,
             uv_height: 0.0,
+            colored: false,
         },
     }
 }
@@ -1266,9 +1761,16 @@ impl RectShaderProgram {
         }
 
         // get uniform locations
-        let u_color = unsafe { gl::GetUniformLocation(program, b"color\0".as_ptr() as *const _) };
+        let (u_color, u_rect_kind, u_rect, u_cell_width) = unsafe {
+            (
+                gl::GetUniformLocation(program, b"color\0".as_ptr() as *const _),
+                gl::GetUniformLocation(program, b"rectKind\0".as_ptr() as *const _),
+                gl::GetUniformLocation(program, b"rect\0".as_ptr() as *const _),
+                gl::GetUniformLocation(program, b"cellWidth\0".as_ptr() as *const _),
+            )
+        };
 
-        let shader = RectShaderProgram { id: program, u_color };
+        let shader = RectShaderProgram { id: program, u_color, u_rect_kind, u_rect, u_cell_width };
 
         unsafe { gl::UseProgram(0) }
 
@@ -1286,6 +1788,21 @@ impl RectShaderProgram {
             );
         }
     }
+
+    fn set_rect(&self, rect: &RenderRect, size: &term::SizeInfo) {
+        unsafe {
+            gl::Uniform1i(self.u_rect_kind, rect.kind as i32);
+            // OpenGL's origin is at the bottom left of the window
+            gl::Uniform4f(
+                self.u_rect,
+                rect.x,
+                size.height - rect.y - rect.height,
+                rect.width,
+                rect.height,
+            );
+            gl::Uniform1f(self.u_cell_width, size.cell_width);
+        }
+    }
 }
 
 impl Drop for RectShaderProgram {
@@ -1296,6 +1813,35 @@ impl Drop for RectShaderProgram {
     }
 }
 
+impl GraphicsShaderProgram {
+    pub fn new() -> Result<Self, ShaderCreationError> {
+        let (vertex_src, fragment_src) = if cfg!(feature = "live-shader-reload") {
+            (None, None)
+        } else {
+            (Some(GRAPHICS_SHADER_V), Some(GRAPHICS_SHADER_F))
+        };
+        let vertex_shader = create_shader(GRAPHICS_SHADER_V_PATH, gl::VERTEX_SHADER, vertex_src)?;
+        let fragment_shader =
+            create_shader(GRAPHICS_SHADER_F_PATH, gl::FRAGMENT_SHADER, fragment_src)?;
+        let program = create_program(vertex_shader, fragment_shader)?;
+
+        unsafe {
+            gl::DeleteShader(fragment_shader);
+            gl::DeleteShader(vertex_shader);
+        }
+
+        Ok(GraphicsShaderProgram { id: program })
+    }
+}
+
+impl Drop for GraphicsShaderProgram {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.id);
+        }
+    }
+}
+
 fn create_program(vertex: GLuint, fragment: GLuint) -> Result<GLuint, ShaderCreationError> {
     unsafe {
         let program = gl::CreateProgram();
@@ -1520,11 +2066,11 @@ impl Atlas {
             gl::TexImage2D(
                 gl::TEXTURE_2D,
                 0,
-                gl::RGB as i32,
+                gl::RGBA as i32,
                 size,
                 size,
                 0,
-                gl::RGB,
+                gl::RGBA,
                 gl::UNSIGNED_BYTE,
                 ptr::null(),
             );
@@ -1581,6 +2127,9 @@ impl Atlas {
         let height = glyph.height as i32;
         let width = glyph.width as i32;
 
+        // Colored glyphs have an alpha channel, coverage of other glyphs is stored as RGB
+        let format = if glyph.colored { gl::RGBA } else { gl::RGB };
+
         unsafe {
             gl::BindTexture(gl::TEXTURE_2D, self.id);
 
@@ -1592,7 +2141,7 @@ impl Atlas {
                 offset_y,
                 width,
                 height,
-                gl::RGB,
+                format,
                 gl::UNSIGNED_BYTE,
                 glyph.buf.as_ptr() as *const _,
             );
@@ -1623,6 +2172,7 @@ impl Atlas {
             uv_left,
             uv_width,
             uv_height,
+            colored: glyph.colored,
         }
     }
 
@@ -20,6 +20,21 @@ use crate::term::cell::Flags;
 use crate::term::color::Rgb;
 use crate::term::{RenderableCell, SizeInfo};
 
+/// Shape drawn by the rectangle shader
+///
+/// The discriminants are used by the shader and must not be changed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RectKind {
+    /// Fill the entire rectangle
+    Filled = 0,
+    /// Wavy line with one wave per cell, a quarter of the rectangle's height thick
+    Undercurl = 1,
+    /// Square dots as tall as the rectangle
+    Dotted = 2,
+    /// Dash in the center half of every cell
+    Dashed = 3,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct RenderRect {
     pub x: f32,
@@ -28,11 +43,12 @@ pub struct RenderRect {
     pub height: f32,
     pub color: Rgb,
     pub alpha: f32,
+    pub kind: RectKind,
 }
 
 impl RenderRect {
     pub fn new(x: f32, y: f32, width: f32, height: f32, color: Rgb, alpha: f32) -> Self {
-        RenderRect { x, y, width, height, color, alpha }
+        RenderRect { x, y, width, height, color, alpha, kind: RectKind::Filled }
     }
 }
 
@@ -51,52 +67,75 @@ impl RenderLine {
         while start.line < self.end.line {
             let mut end = start;
             end.col = size.cols() - 1;
-            rects.push(Self::create_rect(metrics, size, flag, start, end, self.color));
+            Self::push_rects(&mut rects, metrics, size, flag, start, end, self.color);
 
             start.col = Column(0);
             start.line += 1;
         }
 
-        rects.push(Self::create_rect(metrics, size, flag, start, self.end, self.color));
+        Self::push_rects(&mut rects, metrics, size, flag, start, self.end, self.color);
 
         rects
     }
 
-    fn create_rect(
+    fn push_rects(
+        rects: &mut Vec<RenderRect>,
         metrics: &Metrics,
         size: &SizeInfo,
         flag: Flags,
         start: Point,
         end: Point,
         color: Rgb,
-    ) -> RenderRect {
+    ) {
         let start_x = start.col.0 as f32 * size.cell_width;
         let end_x = (end.col.0 + 1) as f32 * size.cell_width;
         let width = end_x - start_x;
 
-        let (position, mut height) = match flag {
-            Flags::UNDERLINE => (metrics.underline_position, metrics.underline_thickness),
+        let (position, mut thickness) = match flag {
             Flags::STRIKEOUT => (metrics.strikeout_position, metrics.strikeout_thickness),
+            _ if Flags::ALL_UNDERLINES.contains(flag) || flag == Flags::OVERLINE => {
+                (metrics.underline_position, metrics.underline_thickness)
+            },
             _ => unimplemented!("Invalid flag for cell line drawing specified"),
         };
 
         // Make sure lines are always visible
-        height = height.max(1.);
+        thickness = thickness.max(1.);
+
+        // Total height covered by the line
+        let (height, kind) = match flag {
+            Flags::DOUBLE_UNDERLINE => (thickness * 3., RectKind::Filled),
+            Flags::UNDERCURL => (thickness * 4., RectKind::Undercurl),
+            Flags::DOTTED_UNDERLINE => (thickness, RectKind::Dotted),
+            Flags::DASHED_UNDERLINE => (thickness, RectKind::Dashed),
+            _ => (thickness, RectKind::Filled),
+        };
 
-        let line_bottom = (start.line.0 as f32 + 1.) * size.cell_height;
+        let line_top = start.line.0 as f32 * size.cell_height;
+        let line_bottom = line_top + size.cell_height;
         let baseline = line_bottom + metrics.descent;
 
-        let mut y = baseline - position - height / 2.;
+        let mut y =
+            if flag == Flags::OVERLINE { line_top } else { baseline - position - height / 2. };
         let max_y = line_bottom - height;
         if y > max_y {
             y = max_y;
         }
 
-        RenderRect::new(start_x + size.padding_x, y + size.padding_y, width, height, color, 1.)
+        let x = start_x + size.padding_x;
+        let y = y + size.padding_y;
+        if flag == Flags::DOUBLE_UNDERLINE {
+            rects.push(RenderRect::new(x, y, width, thickness, color, 1.));
+            rects.push(RenderRect::new(x, y + thickness * 2., width, thickness, color, 1.));
+        } else {
+            let mut rect = RenderRect::new(x, y, width, height, color, 1.);
+            rect.kind = kind;
+            rects.push(rect);
+        }
     }
 }
 
-/// Lines for underline and strikeout.
+/// Lines for underline, strikeout and overline.
 #[derive(Default)]
 pub struct RenderLines {
     inner: HashMap<Flags, Vec<RenderLine>>,
@@ -118,26 +157,43 @@ impl RenderLines {
     }
 
     /// Update the stored lines with the next cell info.
-    pub fn update(&mut self, cell: RenderableCell) {
-        for flag in &[Flags::UNDERLINE, Flags::STRIKEOUT] {
+    pub fn update(&mut self, cell: &RenderableCell) {
+        for flag in &[
+            Flags::UNDERLINE,
+            Flags::DOUBLE_UNDERLINE,
+            Flags::UNDERCURL,
+            Flags::DOTTED_UNDERLINE,
+            Flags::DASHED_UNDERLINE,
+            Flags::STRIKEOUT,
+            Flags::OVERLINE,
+        ] {
             if !cell.flags.contains(*flag) {
                 continue;
             }
 
+            let color =
+                if Flags::ALL_UNDERLINES.contains(*flag) { cell.underline } else { cell.fg };
+
+            // Cells of double-width lines cover two columns
+            let mut end: Point = cell.into();
+            if cell.line_size.is_double_width() {
+                end.col += 1;
+            }
+
             // Check if there's an active line
             if let Some(line) = self.inner.get_mut(flag).and_then(|lines| lines.last_mut()) {
-                if cell.fg == line.color
+                if color == line.color
                     && cell.column == line.end.col + 1
                     && cell.line == line.end.line
                 {
                     // Update the length of the line
-                    line.end = cell.into();
+                    line.end = end;
                     continue;
                 }
             }
 
             // Start new line if there currently is none
-            let line = RenderLine { start: cell.into(), end: cell.into(), color: cell.fg };
+            let line = RenderLine { start: cell.into(), end, color };
             match self.inner.get_mut(flag) {
                 Some(lines) => lines.push(line),
                 None => {
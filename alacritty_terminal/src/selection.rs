@@ -131,6 +131,27 @@ impl Selection {
         }
     }
 
+    /// Expand the selection to fully include the cells at both of its anchors
+    pub fn include_all(&mut self) {
+        match *self {
+            Selection::Simple { ref mut region } => {
+                let swap = Selection::points_need_swap(region.start.point, region.end.point);
+                let (start_side, end_side) =
+                    if swap { (Side::Right, Side::Left) } else { (Side::Left, Side::Right) };
+                region.start.side = start_side;
+                region.end.side = end_side;
+            },
+            Selection::Block { ref mut region } => {
+                let swap = region.start.point.col > region.end.point.col;
+                let (start_side, end_side) =
+                    if swap { (Side::Right, Side::Left) } else { (Side::Left, Side::Right) };
+                region.start.side = start_side;
+                region.end.side = end_side;
+            },
+            Selection::Semantic { .. } | Selection::Lines { .. } => (),
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         match *self {
             Selection::Simple { ref region } => {
@@ -610,6 +631,32 @@ mod test {
         });
     }
 
+    #[test]
+    fn simple_include_all() {
+        let mut selection = Selection::simple(Point::new(0, Column(3)), Side::Left);
+        selection.update(Point::new(1, Column(1)), Side::Left);
+        selection.include_all();
+
+        assert_eq!(selection.to_span(&term(5, 2)).unwrap(), Span {
+            start: Point::new(1, Column(1)),
+            end: Point::new(0, Column(3)),
+            is_block: false,
+        });
+    }
+
+    #[test]
+    fn block_include_all() {
+        let mut selection = Selection::block(Point::new(0, Column(3)), Side::Left);
+        selection.update(Point::new(1, Column(1)), Side::Left);
+        selection.include_all();
+
+        assert_eq!(selection.to_span(&term(5, 2)).unwrap(), Span {
+            start: Point::new(1, Column(1)),
+            end: Point::new(0, Column(3)),
+            is_block: true,
+        });
+    }
+
     #[test]
     fn simple_is_empty() {
         let mut selection = Selection::simple(Point::new(0, Column(0)), Side::Right);
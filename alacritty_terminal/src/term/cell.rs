@@ -11,6 +11,9 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use std::convert::TryFrom;
+use std::num::NonZeroU32;
+
 use bitflags::bitflags;
 
 use serde::{Deserialize, Serialize};
@@ -19,29 +22,85 @@ use crate::ansi::{Color, NamedColor};
 use crate::grid::{self, GridCell};
 use crate::index::Column;
 
-// Maximum number of zerowidth characters which will be stored per cell.
-pub const MAX_ZEROWIDTH_CHARS: usize = 5;
-
 bitflags! {
     #[derive(Serialize, Deserialize)]
-    pub struct Flags: u16 {
-        const INVERSE           = 0b00_0000_0001;
-        const BOLD              = 0b00_0000_0010;
-        const ITALIC            = 0b00_0000_0100;
-        const BOLD_ITALIC       = 0b00_0000_0110;
-        const UNDERLINE         = 0b00_0000_1000;
-        const WRAPLINE          = 0b00_0001_0000;
-        const WIDE_CHAR         = 0b00_0010_0000;
-        const WIDE_CHAR_SPACER  = 0b00_0100_0000;
-        const DIM               = 0b00_1000_0000;
-        const DIM_BOLD          = 0b00_1000_0010;
-        const HIDDEN            = 0b01_0000_0000;
-        const STRIKEOUT         = 0b10_0000_0000;
+    pub struct Flags: u32 {
+        const INVERSE                  = 0b0000_0000_0000_0000_0000_0001;
+        const BOLD                     = 0b0000_0000_0000_0000_0000_0010;
+        const ITALIC                   = 0b0000_0000_0000_0000_0000_0100;
+        const BOLD_ITALIC              = 0b0000_0000_0000_0000_0000_0110;
+        const UNDERLINE                = 0b0000_0000_0000_0000_0000_1000;
+        const WRAPLINE                 = 0b0000_0000_0000_0000_0001_0000;
+        const WIDE_CHAR                = 0b0000_0000_0000_0000_0010_0000;
+        const WIDE_CHAR_SPACER         = 0b0000_0000_0000_0000_0100_0000;
+        const DIM                      = 0b0000_0000_0000_0000_1000_0000;
+        const DIM_BOLD                 = 0b0000_0000_0000_0000_1000_0010;
+        const HIDDEN                   = 0b0000_0000_0000_0001_0000_0000;
+        const STRIKEOUT                = 0b0000_0000_0000_0010_0000_0000;
+        const DOUBLE_UNDERLINE         = 0b0000_0000_0000_0100_0000_0000;
+        const UNDERCURL                = 0b0000_0000_0000_1000_0000_0000;
+        const DOTTED_UNDERLINE         = 0b0000_0000_0001_0000_0000_0000;
+        const DASHED_UNDERLINE         = 0b0000_0000_0010_0000_0000_0000;
+        const ALL_UNDERLINES           = 0b0000_0000_0011_1100_0000_1000;
+        const OVERLINE                 = 0b0000_0000_0100_0000_0000_0000;
+        const BLINK                    = 0b0000_0000_1000_0000_0000_0000;
+        const PROTECTED                = 0b0000_0001_0000_0000_0000_0000;
+        const LEADING_WIDE_CHAR_SPACER = 0b0000_0010_0000_0000_0000_0000;
+    }
+}
+
+/// Reference to a hyperlink stored in the terminal's hyperlink table
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct HyperlinkId(NonZeroU32);
+
+impl HyperlinkId {
+    /// Create the ID of the hyperlink at `index` in the hyperlink table
+    ///
+    /// Returns `None` if the index does not fit into an ID.
+    pub fn new(index: usize) -> Option<HyperlinkId> {
+        u32::try_from(index + 1).ok().and_then(NonZeroU32::new).map(HyperlinkId)
+    }
+
+    /// Index of the hyperlink in the hyperlink table
+    pub fn index(self) -> usize {
+        self.0.get() as usize - 1
     }
 }
 
-const fn default_extra() -> [char; MAX_ZEROWIDTH_CHARS] {
-    [' '; MAX_ZEROWIDTH_CHARS]
+/// Reference to the rest of a grapheme cluster stored in the terminal's cluster table
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct ClusterId(NonZeroU32);
+
+impl ClusterId {
+    /// Create the ID of the cluster at `index` in the cluster table
+    ///
+    /// Returns `None` if the index does not fit into an ID.
+    pub fn new(index: usize) -> Option<ClusterId> {
+        u32::try_from(index + 1).ok().and_then(NonZeroU32::new).map(ClusterId)
+    }
+
+    /// Index of the cluster in the cluster table
+    pub fn index(self) -> usize {
+        self.0.get() as usize - 1
+    }
+}
+
+/// Reference to a part of a graphic stored in the terminal's graphic cell table
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct GraphicCellId(NonZeroU32);
+
+impl GraphicCellId {
+    /// Create the ID of the graphic cell at `index` in the graphic cell table
+    ///
+    /// Returns `None` if the index does not fit into an ID.
+    pub fn new(index: usize) -> Option<GraphicCellId> {
+        u32::try_from(index + 1).ok().and_then(NonZeroU32::new).map(GraphicCellId)
+    }
+
+    /// Index of the graphic cell in the graphic cell table
+    pub fn index(self) -> usize {
+        self.0.get() as usize - 1
+    }
 }
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
@@ -50,8 +109,15 @@ pub struct Cell {
     pub fg: Color,
     pub bg: Color,
     pub flags: Flags,
-    #[serde(default = "default_extra")]
-    pub extra: [char; MAX_ZEROWIDTH_CHARS],
+    /// Characters of the grapheme cluster following `c`
+    #[serde(default)]
+    pub cluster: Option<ClusterId>,
+    #[serde(default)]
+    pub hyperlink: Option<HyperlinkId>,
+    #[serde(default)]
+    pub graphic: Option<GraphicCellId>,
+    #[serde(default)]
+    pub underline_color: Option<Color>,
 }
 
 impl Default for Cell {
@@ -64,12 +130,18 @@ impl GridCell for Cell {
     #[inline]
     fn is_empty(&self) -> bool {
         (self.c == ' ' || self.c == '\t')
-            && self.extra[0] == ' '
+            && self.cluster.is_none()
             && self.bg == Color::Named(NamedColor::Background)
             && self.fg == Color::Named(NamedColor::Foreground)
-            && !self
-                .flags
-                .intersects(Flags::INVERSE | Flags::UNDERLINE | Flags::STRIKEOUT | Flags::WRAPLINE)
+            && self.hyperlink.is_none()
+            && self.graphic.is_none()
+            && !self.flags.intersects(
+                Flags::INVERSE
+                    | Flags::ALL_UNDERLINES
+                    | Flags::STRIKEOUT
+                    | Flags::OVERLINE
+                    | Flags::WRAPLINE,
+            )
     }
 
     #[inline]
@@ -85,6 +157,25 @@ impl GridCell for Cell {
             self.flags.remove(Flags::WRAPLINE);
         }
     }
+
+    #[inline]
+    fn is_wide(&self) -> bool {
+        self.flags.contains(Flags::WIDE_CHAR)
+    }
+
+    #[inline]
+    fn is_leading_spacer(&self) -> bool {
+        self.flags.contains(Flags::LEADING_WIDE_CHAR_SPACER)
+    }
+
+    #[inline]
+    fn set_leading_spacer(&mut self, spacer: bool) {
+        if spacer {
+            self.flags.insert(Flags::LEADING_WIDE_CHAR_SPACER);
+        } else {
+            self.flags.remove(Flags::LEADING_WIDE_CHAR_SPACER);
+        }
+    }
 }
 
 /// Get the length of occupied cells in a line
@@ -102,7 +193,7 @@ impl LineLength for grid::Row<Cell> {
         }
 
         for (index, cell) in self[..].iter().rev().enumerate() {
-            if cell.c != ' ' || cell.extra[0] != ' ' {
+            if cell.c != ' ' || cell.cluster.is_some() {
                 length = Column(self.len() - index);
                 break;
             }
@@ -129,7 +220,16 @@ impl Cell {
     }
 
     pub fn new(c: char, fg: Color, bg: Color) -> Cell {
-        Cell { extra: [' '; MAX_ZEROWIDTH_CHARS], c, bg, fg, flags: Flags::empty() }
+        Cell {
+            c,
+            cluster: None,
+            bg,
+            fg,
+            flags: Flags::empty(),
+            hyperlink: None,
+            graphic: None,
+            underline_color: None,
+        }
     }
 
     #[inline]
@@ -137,39 +237,24 @@ impl Cell {
         // memcpy template to self
         *self = Cell { c: template.c, bg: template.bg, ..Cell::default() };
     }
-
-    #[inline]
-    pub fn chars(&self) -> [char; MAX_ZEROWIDTH_CHARS + 1] {
-        unsafe {
-            let mut chars = [std::mem::MaybeUninit::uninit(); MAX_ZEROWIDTH_CHARS + 1];
-            std::ptr::write(chars[0].as_mut_ptr(), self.c);
-            std::ptr::copy_nonoverlapping(
-                self.extra.as_ptr() as *mut std::mem::MaybeUninit<char>,
-                chars.as_mut_ptr().offset(1),
-                self.extra.len(),
-            );
-            std::mem::transmute(chars)
-        }
-    }
-
-    #[inline]
-    pub fn push_extra(&mut self, c: char) {
-        for elem in self.extra.iter_mut() {
-            if elem == &' ' {
-                *elem = c;
-                break;
-            }
-        }
-    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Cell, LineLength};
+    use std::mem;
+
+    use super::{Cell, ClusterId, LineLength};
 
     use crate::grid::Row;
     use crate::index::Column;
 
+    #[test]
+    fn cell_size_is_bounded() {
+        // Every column of the scrollback history stores a cell, so larger attributes have to be
+        // stored in tables instead
+        assert!(mem::size_of::<Cell>() <= 32);
+    }
+
     #[test]
     fn line_length_works() {
         let template = Cell::default();
@@ -179,6 +264,30 @@ mod tests {
         assert_eq!(row.line_length(), Column(6));
     }
 
+    #[test]
+    fn line_length_works_with_cluster() {
+        let template = Cell::default();
+        let mut row = Row::new(Column(10), &template);
+        row[Column(3)].cluster = ClusterId::new(0);
+
+        assert_eq!(row.line_length(), Column(4));
+    }
+
+    #[test]
+    fn deserialize_inline_zerowidth_chars() {
+        // Grids serialized before grapheme clusters stored zero-width characters inline
+        let cell: Cell = serde_json::from_value(serde_json::json!({
+            "c": "e",
+            "fg": { "Named": "Foreground" },
+            "bg": { "Named": "Background" },
+            "flags": { "bits": 0 },
+            "extra": [" ", " ", " ", " ", " "],
+        }))
+        .unwrap();
+
+        assert_eq!(cell, Cell { c: 'e', ..Cell::default() });
+    }
+
     #[test]
     fn line_length_works_with_wrapline() {
         let template = Cell::default();
@@ -14,33 +14,47 @@
 //
 //! Exports the `Term` type which is a high-level API for the Grid
 use std::cmp::{max, min};
-use std::ops::{Index, IndexMut, Range};
+use std::collections::HashMap;
+use std::ops::{Index, IndexMut, Range, RangeInclusive};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-use std::{io, mem, ptr, str};
+use std::{io, iter, mem, ptr, str};
 
 use log::{debug, trace};
 use serde::{Deserialize, Serialize};
-use unicode_width::UnicodeWidthChar;
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::ansi::{
-    self, Attr, CharsetIndex, Color, CursorStyle, Handler, NamedColor, StandardCharset, TermInfo,
+    self, Attr, AttributeChangeExtent, CharsetIndex, Color, CursorShape, CursorStyle, Handler,
+    Hyperlink, KeyboardModes, KeyboardModesApplyBehavior, LineSize, ModeState, ModifyOtherKeys,
+    NamedColor, Notification, PromptMark, StandardCharset, StatusRequest, TermInfo,
+    WindowOperation, WindowReport, WorkingDirectory,
 };
 use crate::clipboard::{Clipboard, ClipboardType};
-use crate::config::{Config, VisualBellAnimation, DEFAULT_NAME};
+use crate::config::{Config, CursorBlinking, VisualBellAnimation, DEFAULT_NAME};
 use crate::cursor::CursorKey;
 use crate::event::{Event, EventListener};
+use crate::graphics::{Anchor, GraphicCell, Graphics, Image};
 use crate::grid::{
-    BidirectionalIterator, DisplayIter, Grid, GridCell, IndexRegion, Indexed, Scroll,
+    BidirectionalIterator, DisplayIter, Grid, GridCell, IndexRegion, Indexed, PromptMarks, Scroll,
 };
-use crate::index::{self, Column, IndexRange, Line, Point};
+use crate::index::{self, Column, IndexRange, Line, Point, Side};
 use crate::selection::{self, Selection, SelectionRange, Span};
-use crate::term::cell::{Cell, Flags, LineLength};
+use crate::term::cell::{Cell, ClusterId, Flags, GraphicCellId, HyperlinkId, LineLength};
 use crate::term::color::Rgb;
-#[cfg(windows)]
+use crate::term::search::{self as regex_search, Direction, Match, RegexSearch};
+use crate::term::table::Table;
+use crate::term::width::WidthPolicy;
 use crate::tty;
+use crate::vi_mode::{self, ViModeCursor, ViMotion};
 
 pub mod cell;
 pub mod color;
+pub mod search;
+mod table;
+#[cfg(test)]
+pub mod test;
+pub mod width;
 
 /// Used to match equal brackets, when performing a bracket-pair selection.
 const BRACKET_PAIRS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
@@ -48,6 +62,9 @@ const BRACKET_PAIRS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('
 /// Max size of the window title stack
 const TITLE_STACK_MAX_DEPTH: usize = 4096;
 
+/// Max size of the keyboard mode stacks
+const KEYBOARD_MODE_STACK_MAX_DEPTH: usize = TITLE_STACK_MAX_DEPTH;
+
 /// A type that can expand a given point to a region
 ///
 /// Usually this is implemented for some 2-D array type since
@@ -70,6 +87,11 @@ impl<T> Search for Term<T> {
         let last_col = self.grid.num_cols() - Column(1);
 
         while let Some(cell) = iter.prev() {
+            // Wide characters wrapped to the next line don't separate words
+            if cell.flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) {
+                continue;
+            }
+
             if self.semantic_escape_chars.contains(cell.c) {
                 break;
             }
@@ -92,6 +114,11 @@ impl<T> Search for Term<T> {
         let last_col = self.grid.num_cols() - 1;
 
         while let Some(cell) = iter.next() {
+            // Wide characters wrapped to the next line don't separate words
+            if cell.flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) {
+                continue;
+            }
+
             if self.semantic_escape_chars.contains(cell.c) {
                 break;
             }
@@ -172,13 +199,18 @@ impl<T> selection::Dimensions for Term<T> {
 pub struct RenderableCellsIter<'a, C> {
     inner: DisplayIter<'a, Cell>,
     grid: &'a Grid<Cell>,
-    cursor: &'a Point,
-    cursor_offset: usize,
+    cursor: Point<usize>,
     cursor_key: Option<CursorKey>,
-    cursor_style: CursorStyle,
+    cursor_shape: CursorShape,
+    cursor_color: Option<Color>,
+    cursor_text_color: Option<Rgb>,
     config: &'a Config<C>,
     colors: &'a color::List,
+    clusters: &'a Clusters,
+    graphic_cells: &'a Table<GraphicCell>,
     selection: Option<SelectionRange>,
+    search_matches: &'a [Match],
+    blink_hidden: bool,
 }
 
 impl<'a, C> RenderableCellsIter<'a, C> {
@@ -190,11 +222,9 @@ impl<'a, C> RenderableCellsIter<'a, C> {
         term: &'b Term<T>,
         config: &'b Config<C>,
         selection: Option<Span>,
-        mut cursor_style: CursorStyle,
+        mut cursor_shape: CursorShape,
     ) -> RenderableCellsIter<'b, C> {
         let grid = &term.grid;
-
-        let cursor_offset = grid.line_to_offset(term.cursor.point.line);
         let inner = grid.display_iter();
 
         let selection_range = selection.map(|span| {
@@ -215,40 +245,109 @@ impl<'a, C> RenderableCellsIter<'a, C> {
             SelectionRange::new(start.into(), end.into(), span.is_block)
         });
 
+        // Vi mode cursor replaces the terminal cursor while vi mode is active
+        let (cursor, cursor_visible, cursor_color, cursor_text_color) =
+            if term.mode.contains(TermMode::VI) {
+                let cursor = term.vi_mode_cursor.point;
+                let display_offset = grid.display_offset();
+                let visible = cursor.line >= display_offset
+                    && cursor.line < display_offset + grid.num_lines().0;
+                cursor_shape = config.cursor.vi_mode_style;
+
+                let color = config.vi_mode_cursor_cursor_color();
+                (cursor, visible, color, config.vi_mode_cursor_text_color())
+            } else {
+                let cursor = &term.cursor.point;
+                let visible = term.mode.contains(TermMode::SHOW_CURSOR) && grid.contains(cursor);
+                let cursor = Point::new(grid.line_to_offset(cursor.line), cursor.col);
+                (cursor, visible, config.cursor_cursor_color(), config.cursor_text_color())
+            };
+
+        // Keep the cursor inside the usable half of double-width lines
+        let mut cursor = cursor;
+        if cursor_visible && grid[cursor.line].line_size().is_double_width() {
+            cursor.col = min(cursor.col, Column(grid.num_cols().0 / 2) - 1);
+        }
+
         // Load cursor glyph
-        let cursor = &term.cursor.point;
-        let cursor_visible = term.mode.contains(TermMode::SHOW_CURSOR) && grid.contains(cursor);
         let cursor_key = if cursor_visible {
-            let is_wide =
-                grid[cursor].flags.contains(Flags::WIDE_CHAR) && (cursor.col + 1) < grid.num_cols();
-            Some(CursorKey { style: cursor_style, is_wide })
+            let is_wide = grid[cursor.line][cursor.col].flags.contains(Flags::WIDE_CHAR)
+                && (cursor.col + 1) < grid.num_cols();
+            Some(CursorKey { shape: cursor_shape, is_wide })
         } else {
             // Use hidden cursor so text will not get inverted
-            cursor_style = CursorStyle::Hidden;
+            cursor_shape = CursorShape::Hidden;
             None
         };
 
+        let search_matches = term.search_matches.as_ref().map_or(&[][..], |(_, m)| &m[..]);
+
         RenderableCellsIter {
             cursor,
-            cursor_offset,
             grid,
             inner,
             selection: selection_range,
+            search_matches,
             config,
             colors: &term.colors,
+            clusters: &term.clusters,
+            graphic_cells: &term.graphic_cells,
             cursor_key,
-            cursor_style,
+            cursor_shape,
+            cursor_color,
+            cursor_text_color,
+            blink_hidden: term.blink_hidden,
+        }
+    }
+
+    /// Create a renderable cell, hiding blinking text during the hidden phase of the blink
+    fn renderable_cell(
+        &self,
+        cell: Indexed<Cell>,
+        selected: bool,
+        search_match: bool,
+    ) -> RenderableCell {
+        let point = self.grid.visible_to_buffer(Point::new(cell.line, cell.column));
+        let line_size = self.grid[point.line].line_size();
+        let cluster = cell.cluster.and_then(|id| self.clusters.get(id).cloned());
+        let graphic = cell.graphic.and_then(|id| self.graphic_cells.get(id.index()).copied());
+
+        let mut cell = RenderableCell::new(self.config, self.colors, cell, selected, search_match);
+        if self.blink_hidden && cell.flags.contains(Flags::BLINK) {
+            cell.flags.insert(Flags::HIDDEN);
+        }
+        if let RenderableCellContent::Chars(_, ref mut rest) = cell.inner {
+            *rest = cluster;
+        }
+        cell.line_size = line_size;
+        cell.graphic = graphic;
+        cell
+    }
+
+    /// Check if a cell is part of a visible search match
+    ///
+    /// Cells have to be checked in reading order, since the matches ending before a cell are
+    /// dropped.
+    fn is_search_match(&mut self, line: usize, col: Column) -> bool {
+        let point = Point::new(line, col);
+        while let Some(regex_match) = self.search_matches.first() {
+            if !regex_search::is_before(*regex_match.end(), point) {
+                return regex_search::match_contains(regex_match, point);
+            }
+            self.search_matches = &self.search_matches[1..];
         }
+        false
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub enum RenderableCellContent {
-    Chars([char; cell::MAX_ZEROWIDTH_CHARS + 1]),
+    /// First character of a grapheme cluster and the characters following it
+    Chars(char, Option<Arc<str>>),
     Cursor(CursorKey),
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct RenderableCell {
     /// A _Display_ line (not necessarily an _Active_ line)
     pub line: Line,
@@ -258,6 +357,11 @@ pub struct RenderableCell {
     pub bg: Rgb,
     pub bg_alpha: f32,
     pub flags: Flags,
+    pub hyperlink: Option<HyperlinkId>,
+    pub graphic: Option<GraphicCell>,
+    pub underline: Rgb,
+    pub line_size: LineSize,
+    pub selected: bool,
 }
 
 impl RenderableCell {
@@ -266,6 +370,7 @@ impl RenderableCell {
         colors: &color::List,
         cell: Indexed<Cell>,
         selected: bool,
+        search_match: bool,
     ) -> Self {
         // Lookup RGB values
         let mut fg_rgb = Self::compute_fg_rgb(config, colors, cell.fg, cell.flags);
@@ -295,14 +400,31 @@ impl RenderableCell {
             fg_rgb = col;
         }
 
+        // Highlight search matches outside of the selection
+        if search_match && !selected {
+            fg_rgb = config.colors.search.matches.text;
+            bg_rgb = config.colors.search.matches.background;
+            bg_alpha = 1.0;
+        }
+
+        let underline = match cell.underline_color {
+            Some(color) => Self::compute_bg_rgb(colors, color),
+            None => fg_rgb,
+        };
+
         RenderableCell {
             line: cell.line,
             column: cell.column,
-            inner: RenderableCellContent::Chars(cell.chars()),
+            inner: RenderableCellContent::Chars(cell.c, None),
             fg: fg_rgb,
             bg: bg_rgb,
             bg_alpha,
             flags: cell.flags,
+            hyperlink: cell.hyperlink,
+            graphic: None,
+            underline,
+            line_size: LineSize::Normal,
+            selected,
         }
     }
 
@@ -372,17 +494,38 @@ impl<'a, C> Iterator for RenderableCellsIter<'a, C> {
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            if self.cursor_offset == self.inner.offset() && self.inner.column() == self.cursor.col {
+            let mut cell = self.next_cell()?;
+
+            // Every cell of a double-width line covers two columns of the screen
+            if cell.line_size.is_double_width() {
+                if cell.column.0 >= self.grid.num_cols().0 / 2 {
+                    continue;
+                }
+                cell.column = Column(cell.column.0 * 2);
+            }
+
+            return Some(cell);
+        }
+    }
+}
+
+impl<'a, C> RenderableCellsIter<'a, C> {
+    /// Next renderable cell at its column in the grid
+    #[inline]
+    fn next_cell(&mut self) -> Option<RenderableCell> {
+        loop {
+            if self.cursor.line == self.inner.offset() && self.inner.column() == self.cursor.col {
                 let selected = self
                     .selection
                     .as_ref()
-                    .map(|range| range.contains(self.cursor.col, self.cursor.line))
+                    .map(|range| range.contains(self.cursor.col, self.inner.line()))
                     .unwrap_or(false);
+                let search_match = self.is_search_match(self.cursor.line, self.cursor.col);
 
                 // Handle cursor
                 if let Some(cursor_key) = self.cursor_key.take() {
                     let cell = Indexed {
-                        inner: self.grid[self.cursor],
+                        inner: self.grid[self.cursor.line][self.cursor.col],
                         column: self.cursor.col,
                         // Using `self.cursor.line` leads to inconsitent cursor position when
                         // scrolling. See https://github.com/jwilm/alacritty/issues/2570 for more
@@ -390,24 +533,23 @@ impl<'a, C> Iterator for RenderableCellsIter<'a, C> {
                         line: self.inner.line(),
                     };
 
-                    let mut renderable_cell =
-                        RenderableCell::new(self.config, self.colors, cell, selected);
+                    let mut renderable_cell = self.renderable_cell(cell, selected, search_match);
 
                     renderable_cell.inner = RenderableCellContent::Cursor(cursor_key);
 
-                    if let Some(color) = self.config.cursor_cursor_color() {
+                    if let Some(color) = self.cursor_color {
                         renderable_cell.fg = RenderableCell::compute_bg_rgb(self.colors, color);
                     }
 
                     return Some(renderable_cell);
                 } else {
-                    let mut cell =
-                        RenderableCell::new(self.config, self.colors, self.inner.next()?, selected);
+                    let cell = self.inner.next()?;
+                    let mut cell = self.renderable_cell(cell, selected, search_match);
 
-                    if self.cursor_style == CursorStyle::Block {
+                    if self.cursor_shape == CursorShape::Block {
                         std::mem::swap(&mut cell.bg, &mut cell.fg);
 
-                        if let Some(color) = self.config.cursor_text_color() {
+                        if let Some(color) = self.cursor_text_color {
                             cell.fg = color;
                         }
                     }
@@ -415,6 +557,7 @@ impl<'a, C> Iterator for RenderableCellsIter<'a, C> {
                     return Some(cell);
                 }
             } else {
+                let line = self.inner.offset();
                 let cell = self.inner.next()?;
 
                 let selected = self
@@ -422,9 +565,10 @@ impl<'a, C> Iterator for RenderableCellsIter<'a, C> {
                     .as_ref()
                     .map(|range| range.contains(cell.column, cell.line))
                     .unwrap_or(false);
+                let search_match = self.is_search_match(line, cell.column);
 
-                if !cell.is_empty() || selected {
-                    return Some(RenderableCell::new(self.config, self.colors, cell, selected));
+                if !cell.is_empty() || selected || search_match {
+                    return Some(self.renderable_cell(cell, selected, search_match));
                 }
             }
         }
@@ -434,27 +578,42 @@ impl<'a, C> Iterator for RenderableCellsIter<'a, C> {
 pub mod mode {
     use bitflags::bitflags;
 
+    use crate::ansi::KeyboardModes;
+
     bitflags! {
-        pub struct TermMode: u16 {
-            const SHOW_CURSOR         = 0b0000_0000_0000_0001;
-            const APP_CURSOR          = 0b0000_0000_0000_0010;
-            const APP_KEYPAD          = 0b0000_0000_0000_0100;
-            const MOUSE_REPORT_CLICK  = 0b0000_0000_0000_1000;
-            const BRACKETED_PASTE     = 0b0000_0000_0001_0000;
-            const SGR_MOUSE           = 0b0000_0000_0010_0000;
-            const MOUSE_MOTION        = 0b0000_0000_0100_0000;
-            const LINE_WRAP           = 0b0000_0000_1000_0000;
-            const LINE_FEED_NEW_LINE  = 0b0000_0001_0000_0000;
-            const ORIGIN              = 0b0000_0010_0000_0000;
-            const INSERT              = 0b0000_0100_0000_0000;
-            const FOCUS_IN_OUT        = 0b0000_1000_0000_0000;
-            const ALT_SCREEN          = 0b0001_0000_0000_0000;
-            const MOUSE_DRAG          = 0b0010_0000_0000_0000;
-            const MOUSE_MODE          = 0b0010_0000_0100_1000;
-            const UTF8_MOUSE          = 0b0100_0000_0000_0000;
-            const ALTERNATE_SCROLL    = 0b1000_0000_0000_0000;
-            const ANY                 = 0b1111_1111_1111_1111;
-            const NONE                = 0;
+        pub struct TermMode: u32 {
+            const SHOW_CURSOR             = 0b0000_0000_0000_0000_0000_0000_0001;
+            const APP_CURSOR              = 0b0000_0000_0000_0000_0000_0000_0010;
+            const APP_KEYPAD              = 0b0000_0000_0000_0000_0000_0000_0100;
+            const MOUSE_REPORT_CLICK      = 0b0000_0000_0000_0000_0000_0000_1000;
+            const BRACKETED_PASTE         = 0b0000_0000_0000_0000_0000_0001_0000;
+            const SGR_MOUSE               = 0b0000_0000_0000_0000_0000_0010_0000;
+            const MOUSE_MOTION            = 0b0000_0000_0000_0000_0000_0100_0000;
+            const LINE_WRAP               = 0b0000_0000_0000_0000_0000_1000_0000;
+            const LINE_FEED_NEW_LINE      = 0b0000_0000_0000_0000_0001_0000_0000;
+            const ORIGIN                  = 0b0000_0000_0000_0000_0010_0000_0000;
+            const INSERT                  = 0b0000_0000_0000_0000_0100_0000_0000;
+            const FOCUS_IN_OUT            = 0b0000_0000_0000_0000_1000_0000_0000;
+            const ALT_SCREEN              = 0b0000_0000_0000_0001_0000_0000_0000;
+            const MOUSE_DRAG              = 0b0000_0000_0000_0010_0000_0000_0000;
+            const MOUSE_MODE              = 0b0000_0000_0000_0010_0000_0100_1000;
+            const UTF8_MOUSE              = 0b0000_0000_0000_0100_0000_0000_0000;
+            const ALTERNATE_SCROLL        = 0b0000_0000_0000_1000_0000_0000_0000;
+            const VI                      = 0b0000_0000_0001_0000_0000_0000_0000;
+            const SYNC_UPDATE             = 0b0000_0000_0010_0000_0000_0000_0000;
+            const DISAMBIGUATE_ESC_CODES  = 0b0000_0000_0100_0000_0000_0000_0000;
+            const REPORT_EVENT_TYPES      = 0b0000_0000_1000_0000_0000_0000_0000;
+            const REPORT_ALTERNATE_KEYS   = 0b0000_0001_0000_0000_0000_0000_0000;
+            const REPORT_ALL_KEYS_AS_ESC  = 0b0000_0010_0000_0000_0000_0000_0000;
+            const KITTY_KEYBOARD_PROTOCOL = 0b0000_0011_1100_0000_0000_0000_0000;
+            const MODIFY_OTHER_KEYS       = 0b0000_1000_0000_0000_0000_0000_0000;
+            const MODIFY_ALL_KEYS         = 0b0001_0000_0000_0000_0000_0000_0000;
+            const LEFT_RIGHT_MARGIN       = 0b0010_0000_0000_0000_0000_0000_0000;
+            const URXVT_MOUSE             = 0b0100_0000_0000_0000_0000_0000_0000;
+            const SGR_PIXEL_MOUSE         = 0b1000_0000_0000_0000_0000_0000_0000;
+            const MOUSE_ENCODING          = 0b1100_0000_0000_0100_0000_0010_0000;
+            const ANY                     = std::u32::MAX;
+            const NONE                    = 0;
         }
     }
 
@@ -463,6 +622,29 @@ pub mod mode {
             TermMode::SHOW_CURSOR | TermMode::LINE_WRAP | TermMode::ALTERNATE_SCROLL
         }
     }
+
+    impl From<KeyboardModes> for TermMode {
+        fn from(mode: KeyboardModes) -> TermMode {
+            let mut term_mode = TermMode::empty();
+            term_mode.set(
+                TermMode::DISAMBIGUATE_ESC_CODES,
+                mode.contains(KeyboardModes::DISAMBIGUATE_ESC_CODES),
+            );
+            term_mode.set(
+                TermMode::REPORT_EVENT_TYPES,
+                mode.contains(KeyboardModes::REPORT_EVENT_TYPES),
+            );
+            term_mode.set(
+                TermMode::REPORT_ALTERNATE_KEYS,
+                mode.contains(KeyboardModes::REPORT_ALTERNATE_KEYS),
+            );
+            term_mode.set(
+                TermMode::REPORT_ALL_KEYS_AS_ESC,
+                mode.contains(KeyboardModes::REPORT_ALL_KEYS_AS_ESC),
+            );
+            term_mode
+        }
+    }
 }
 
 pub use crate::term::mode::TermMode;
@@ -514,6 +696,237 @@ impl CharsetMapping for StandardCharset {
                 '~' => '·',
                 _ => c,
             },
+            StandardCharset::DecSupplementalGraphics => match c {
+                '(' => '¤',
+                'W' => 'Œ',
+                ']' => 'Ÿ',
+                'w' => 'œ',
+                '}' => 'ÿ',
+                // Reserved positions
+                '$' | '&' | ',' | '-' | '.' | '/' | '4' | '8' | '>' | 'P' | '^' | 'p' | '~' => c,
+                // Everything else matches the upper half of ISO 8859-1
+                '!'..='}' => char::from(c as u8 + 0x80),
+                _ => c,
+            },
+            StandardCharset::DecTechnical => match c {
+                '!' => '⎷',
+                '"' => '┌',
+                '#' => '─',
+                '$' => '⌠',
+                '%' => '⌡',
+                '&' => '│',
+                '\'' => '⎡',
+                '(' => '⎣',
+                ')' => '⎤',
+                '*' => '⎦',
+                '+' => '⎛',
+                ',' => '⎝',
+                '-' => '⎞',
+                '.' => '⎠',
+                '/' => '⎨',
+                '0' => '⎬',
+                '<' => '≤',
+                '=' => '≠',
+                '>' => '≥',
+                '?' => '∫',
+                '@' => '∴',
+                'A' => '∝',
+                'B' => '∞',
+                'C' => '÷',
+                'D' => 'Δ',
+                'E' => '∇',
+                'F' => 'Φ',
+                'G' => 'Γ',
+                'H' => '∼',
+                'I' => '≃',
+                'J' => 'Θ',
+                'K' => '×',
+                'L' => 'Λ',
+                'M' => '⇔',
+                'N' => '⇒',
+                'O' => '≡',
+                'P' => 'Π',
+                'Q' => 'Ψ',
+                'S' => 'Σ',
+                'V' => '√',
+                'W' => 'Ω',
+                'X' => 'Ξ',
+                'Y' => 'Υ',
+                'Z' => '⊂',
+                '[' => '⊃',
+                '\\' => '∩',
+                ']' => '∪',
+                '^' => '∧',
+                '_' => '∨',
+                '`' => '¬',
+                'a' => 'α',
+                'b' => 'β',
+                'c' => 'χ',
+                'd' => 'δ',
+                'e' => 'ε',
+                'f' => 'φ',
+                'g' => 'γ',
+                'h' => 'η',
+                'i' => 'ι',
+                'j' => 'θ',
+                'k' => 'κ',
+                'l' => 'λ',
+                'n' => 'ν',
+                'o' => '∂',
+                'p' => 'π',
+                'q' => 'ψ',
+                'r' => 'ρ',
+                's' => 'σ',
+                't' => 'τ',
+                'v' => 'ƒ',
+                'w' => 'ω',
+                'x' => 'ξ',
+                'y' => 'υ',
+                'z' => 'ζ',
+                '{' => '←',
+                '|' => '↑',
+                '}' => '→',
+                '~' => '↓',
+                _ => c,
+            },
+            StandardCharset::British => match c {
+                '#' => '£',
+                _ => c,
+            },
+            StandardCharset::Dutch => match c {
+                '#' => '£',
+                '@' => '¾',
+                '[' => 'ĳ',
+                '\\' => '½',
+                ']' => '|',
+                '{' => '¨',
+                '|' => 'ƒ',
+                '}' => '¼',
+                '~' => '´',
+                _ => c,
+            },
+            StandardCharset::Finnish => match c {
+                '[' => 'Ä',
+                '\\' => 'Ö',
+                ']' => 'Å',
+                '^' => 'Ü',
+                '`' => 'é',
+                '{' => 'ä',
+                '|' => 'ö',
+                '}' => 'å',
+                '~' => 'ü',
+                _ => c,
+            },
+            StandardCharset::French => match c {
+                '#' => '£',
+                '@' => 'à',
+                '[' => '°',
+                '\\' => 'ç',
+                ']' => '§',
+                '{' => 'é',
+                '|' => 'ù',
+                '}' => 'è',
+                '~' => '¨',
+                _ => c,
+            },
+            StandardCharset::FrenchCanadian => match c {
+                '@' => 'à',
+                '[' => 'â',
+                '\\' => 'ç',
+                ']' => 'ê',
+                '^' => 'î',
+                '`' => 'ô',
+                '{' => 'é',
+                '|' => 'ù',
+                '}' => 'è',
+                '~' => 'û',
+                _ => c,
+            },
+            StandardCharset::German => match c {
+                '@' => '§',
+                '[' => 'Ä',
+                '\\' => 'Ö',
+                ']' => 'Ü',
+                '{' => 'ä',
+                '|' => 'ö',
+                '}' => 'ü',
+                '~' => 'ß',
+                _ => c,
+            },
+            StandardCharset::Italian => match c {
+                '#' => '£',
+                '@' => '§',
+                '[' => '°',
+                '\\' => 'ç',
+                ']' => 'é',
+                '`' => 'ù',
+                '{' => 'à',
+                '|' => 'ò',
+                '}' => 'è',
+                '~' => 'ì',
+                _ => c,
+            },
+            StandardCharset::NorwegianDanish => match c {
+                '@' => 'Ä',
+                '[' => 'Æ',
+                '\\' => 'Ø',
+                ']' => 'Å',
+                '^' => 'Ü',
+                '`' => 'ä',
+                '{' => 'æ',
+                '|' => 'ø',
+                '}' => 'å',
+                '~' => 'ü',
+                _ => c,
+            },
+            StandardCharset::Portuguese => match c {
+                '[' => 'Ã',
+                '\\' => 'Ç',
+                ']' => 'Õ',
+                '{' => 'ã',
+                '|' => 'ç',
+                '}' => 'õ',
+                _ => c,
+            },
+            StandardCharset::Spanish => match c {
+                '#' => '£',
+                '@' => '§',
+                '[' => '¡',
+                '\\' => 'Ñ',
+                ']' => '¿',
+                '{' => '°',
+                '|' => 'ñ',
+                '}' => 'ç',
+                _ => c,
+            },
+            StandardCharset::Swedish => match c {
+                '@' => 'É',
+                '[' => 'Ä',
+                '\\' => 'Ö',
+                ']' => 'Å',
+                '^' => 'Ü',
+                '`' => 'é',
+                '{' => 'ä',
+                '|' => 'ö',
+                '}' => 'å',
+                '~' => 'ü',
+                _ => c,
+            },
+            StandardCharset::Swiss => match c {
+                '#' => 'ù',
+                '@' => 'à',
+                '[' => 'é',
+                '\\' => 'ç',
+                ']' => 'ê',
+                '^' => 'î',
+                '_' => 'è',
+                '`' => 'ô',
+                '{' => 'ä',
+                '|' => 'ö',
+                '}' => 'ü',
+                '~' => 'û',
+                _ => c,
+            },
         }
     }
 }
@@ -695,6 +1108,9 @@ pub struct Term<T> {
     /// being mapped to
     active_charset: CharsetIndex,
 
+    /// The graphic character set used for the next character only
+    single_shift: Option<CharsetIndex>,
+
     /// Tabstops
     tabs: TabStops,
 
@@ -704,10 +1120,28 @@ pub struct Term<T> {
     /// Scroll region
     scroll_region: Range<Line>,
 
+    /// Left and right margins of the scroll region
+    horizontal_margins: Range<Column>,
+
+    /// Cells changed by the rectangular attribute operations
+    attribute_change_extent: AttributeChangeExtent,
+
     pub dirty: bool,
 
     pub visual_bell: VisualBell,
 
+    /// Blinking text and the blinking cursor are currently hidden
+    pub blink_hidden: bool,
+
+    /// Cursor for keyboard selection
+    pub vi_mode_cursor: ViModeCursor,
+
+    /// Regex used for searching the grid
+    regex_search: Option<RegexSearch>,
+
+    /// Visible matches of the regex search and the display offset they were found at
+    search_matches: Option<(usize, Vec<Match>)>,
+
     /// Saved cursor from main grid
     cursor_save: Cursor,
 
@@ -731,6 +1165,9 @@ pub struct Term<T> {
     /// Default style for resetting the cursor
     default_cursor_style: CursorStyle,
 
+    /// Blinking behavior of the cursor forced by the config
+    cursor_blinking: CursorBlinking,
+
     /// Whether to permit updating the terminal title
     dynamic_title: bool,
 
@@ -740,6 +1177,9 @@ pub struct Term<T> {
     /// Automatically scroll to bottom when new lines are added
     auto_scroll: bool,
 
+    /// Number of columns occupied by characters
+    width_policy: WidthPolicy,
+
     /// Clipboard access coupled to the active window
     clipboard: Clipboard,
 
@@ -749,9 +1189,40 @@ pub struct Term<T> {
     /// Current title of the window
     title: String,
 
-    /// Stack of saved window titles. When a title is popped from this stack, the `title` for the
-    /// term is set, and the Glutin window's title attribute is changed through the event listener.
-    title_stack: Vec<String>,
+    /// Current icon name of the window
+    icon_name: String,
+
+    /// Stack of saved window titles and icon names. When a title is popped from this stack, the
+    /// `title` and `icon_name` for the term are set, and the Glutin window's title attribute is
+    /// changed through the event listener.
+    title_stack: Vec<(String, String)>,
+
+    /// Allow applications to read the window title
+    allow_title_reports: bool,
+
+    /// Stack of kitty keyboard protocol modes of the active screen
+    keyboard_mode_stack: Vec<KeyboardModes>,
+
+    /// Stack of kitty keyboard protocol modes of the inactive screen
+    inactive_keyboard_mode_stack: Vec<KeyboardModes>,
+
+    /// Working directory reported by the shell
+    working_directory: Option<WorkingDirectory>,
+
+    /// Hyperlinks referenced by the cells of both grids
+    hyperlinks: Hyperlinks,
+
+    /// Grapheme clusters referenced by the cells of both grids
+    clusters: Clusters,
+
+    /// Graphics referenced by the cells of both grids
+    graphics: Graphics,
+
+    /// Parts of graphics referenced by the cells of both grids
+    graphic_cells: Table<GraphicCell>,
+
+    /// Size of the terminal and its cells
+    size_info: SizeInfo,
 }
 
 /// Terminal size info
@@ -828,6 +1299,19 @@ impl<T> Term<T> {
     {
         self.event_proxy.send_event(Event::MouseCursorDirty);
         self.grid.scroll_display(scroll);
+
+        // Keep the vi mode cursor inside the viewport
+        if self.mode.contains(TermMode::VI) {
+            let display_offset = self.grid.display_offset();
+            let max_line = display_offset + self.grid.num_lines().0 - 1;
+            let line = max(min(self.vi_mode_cursor.point.line, max_line), display_offset);
+
+            if line != self.vi_mode_cursor.point.line {
+                self.vi_mode_cursor.point.line = line;
+                self.update_vi_selection();
+            }
+        }
+
         self.dirty = true;
     }
 
@@ -848,6 +1332,7 @@ impl<T> Term<T> {
         let tabs = TabStops::new(grid.num_cols(), tabspaces);
 
         let scroll_region = Line(0)..grid.num_lines();
+        let horizontal_margins = Column(0)..grid.num_cols();
 
         let colors = color::List::from(&config.colors);
 
@@ -859,26 +1344,45 @@ impl<T> Term<T> {
             alt_grid: alt,
             alt: false,
             active_charset: Default::default(),
+            single_shift: None,
             cursor: Default::default(),
+            vi_mode_cursor: Default::default(),
+            regex_search: None,
+            search_matches: None,
             cursor_save: Default::default(),
             cursor_save_alt: Default::default(),
             tabs,
             mode: Default::default(),
             scroll_region,
+            horizontal_margins,
+            attribute_change_extent: Default::default(),
             colors,
             color_modified: [false; color::COUNT],
             original_colors: colors,
             semantic_escape_chars: config.selection.semantic_escape_chars().to_owned(),
             cursor_style: None,
-            default_cursor_style: config.cursor.style,
+            default_cursor_style: config.cursor.default_style(),
+            cursor_blinking: config.cursor.blinking,
             dynamic_title: config.dynamic_title(),
             tabspaces,
             auto_scroll: config.scrolling.auto_scroll,
+            width_policy: WidthPolicy::new(&config.unicode),
             clipboard,
             event_proxy,
             is_focused: true,
+            blink_hidden: false,
             title: config.window.title.clone(),
+            icon_name: String::new(),
             title_stack: Vec::new(),
+            allow_title_reports: config.window.allow_title_reports,
+            keyboard_mode_stack: Vec::new(),
+            inactive_keyboard_mode_stack: Vec::new(),
+            working_directory: None,
+            hyperlinks: Default::default(),
+            clusters: Default::default(),
+            graphics: Default::default(),
+            graphic_cells: Default::default(),
+            size_info: *size,
         }
     }
 
@@ -896,9 +1400,12 @@ impl<T> Term<T> {
         if let Some(0) = config.scrolling.faux_multiplier() {
             self.mode.remove(TermMode::ALTERNATE_SCROLL);
         }
-        self.default_cursor_style = config.cursor.style;
+        self.default_cursor_style = config.cursor.default_style();
+        self.cursor_blinking = config.cursor.blinking;
         self.dynamic_title = config.dynamic_title();
+        self.allow_title_reports = config.window.allow_title_reports;
         self.auto_scroll = config.scrolling.auto_scroll;
+        self.width_policy = WidthPolicy::new(&config.unicode);
         self.grid.update_history(config.scrolling.history() as usize, &self.cursor.template);
     }
 
@@ -941,7 +1448,14 @@ impl<T> Term<T> {
 
         let grid_line = &self.grid[line];
         let line_length = grid_line.line_length();
-        let line_end = min(line_length, cols.end + 1);
+
+        // Only the first half of double-width lines holds characters
+        let last_col = if grid_line.line_size().is_double_width() {
+            Column(self.cols().0 / 2) - 1
+        } else {
+            self.cols() - 1
+        };
+        let line_end = min(line_length, min(cols.end, last_col) + 1);
 
         let mut tab_mode = false;
 
@@ -961,18 +1475,14 @@ impl<T> Term<T> {
                 tab_mode = true;
             }
 
-            if !cell.flags.contains(cell::Flags::WIDE_CHAR_SPACER) {
-                // Push cells primary character
+            if !cell.flags.intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER) {
+                // Push the cell's whole grapheme cluster
                 text.push(cell.c);
-
-                // Push zero-width characters
-                for c in (&cell.chars()[1..]).iter().take_while(|c| **c != ' ') {
-                    text.push(*c);
-                }
+                text.push_str(self.cluster(&cell));
             }
         }
 
-        if cols.end >= self.cols() - 1
+        if cols.end >= last_col
             && (line_end == Column(0)
                 || !self.grid[line][line_end - 1].flags.contains(cell::Flags::WRAPLINE))
         {
@@ -990,6 +1500,90 @@ impl<T> Term<T> {
         self.grid.buffer_to_visible(point)
     }
 
+    /// Size of the characters on a visible line
+    pub fn line_size(&self, line: Line) -> LineSize {
+        if line >= self.grid.num_lines() {
+            return LineSize::Normal;
+        }
+
+        self.grid[self.grid.visible_to_buffer(Point::new(line, Column(0))).line].line_size()
+    }
+
+    /// Convert a point on the screen to the cell it is displaying
+    ///
+    /// Cells of double-width lines cover two columns of the screen.
+    pub fn screen_to_cell(&self, mut point: Point) -> Point {
+        if self.line_size(point.line).is_double_width() {
+            point.col = Column(point.col.0 / 2);
+        }
+        point
+    }
+
+    /// Hyperlink referenced by a cell
+    pub fn hyperlink(&self, id: HyperlinkId) -> Option<&Hyperlink> {
+        self.hyperlinks.get(id)
+    }
+
+    /// Number of cells in the history and screen of both grids
+    fn total_cells(&self) -> usize {
+        let lines = self.grid.len() + self.alt_grid.len();
+        lines * self.grid.num_cols().0
+    }
+
+    /// Remove table entries which are not referenced by any cell anymore
+    fn sweep_tables(&mut self) {
+        let mut hyperlinks = vec![false; self.hyperlinks.links.capacity()];
+        let mut clusters = vec![false; self.clusters.clusters.capacity()];
+        let mut graphic_cells = vec![false; self.graphic_cells.capacity()];
+        let mut anchors = HashMap::new();
+
+        let grids = [(&self.grid, self.alt), (&self.alt_grid, !self.alt)];
+        for &(grid, alt_screen) in &grids {
+            for line in 0..grid.num_lines().0 + grid.scroll_limit() {
+                for cell in &grid[line][..] {
+                    if let Some(id) = cell.hyperlink {
+                        hyperlinks[id.index()] = true;
+                    }
+
+                    if let Some(id) = cell.cluster {
+                        clusters[id.index()] = true;
+                    }
+
+                    if let Some(id) = cell.graphic {
+                        graphic_cells[id.index()] = true;
+
+                        // Lines are visited from the bottom, so the first one is the lowest
+                        if let Some(graphic) = self.graphic_cells.get(id.index()) {
+                            anchors.entry(graphic.id).or_insert(Anchor { alt_screen, line });
+                        }
+                    }
+                }
+            }
+        }
+
+        // Cells printed later are created from the cursor templates
+        for cursor in &[&self.cursor, &self.cursor_save, &self.cursor_save_alt] {
+            if let Some(id) = cursor.template.hyperlink {
+                hyperlinks[id.index()] = true;
+            }
+        }
+
+        self.hyperlinks.sweep(&hyperlinks);
+        self.clusters.sweep(&clusters);
+        self.graphic_cells.sweep(&graphic_cells);
+        self.graphics.retain(anchors);
+    }
+
+    /// Characters following the first one in the grapheme cluster of a cell
+    pub fn cluster(&self, cell: &Cell) -> &str {
+        cell.cluster.and_then(|id| self.clusters.get(id)).map_or("", |cluster| cluster)
+    }
+
+    /// Graphics referenced by the cells of the grid
+    pub fn graphics_mut(&mut self) -> &mut Graphics {
+        &mut self.graphics
+    }
+
     /// Access to the raw grid data structure
     ///
     /// This is a bit of a hack; when the window is closed, the event processor
@@ -1012,17 +1606,93 @@ impl<T> Term<T> {
     pub fn renderable_cells<'b, C>(&'b self, config: &'b Config<C>) -> RenderableCellsIter<'_, C> {
         let selection = self.grid.selection.as_ref().and_then(|s| s.to_span(self));
 
-        let cursor = if self.is_focused || !config.cursor.unfocused_hollow() {
-            self.cursor_style.unwrap_or(self.default_cursor_style)
+        let cursor_style = self.cursor_style();
+        let cursor = if !self.is_focused && config.cursor.unfocused_hollow() {
+            CursorShape::HollowBlock
+        } else if self.blink_hidden && cursor_style.blinking {
+            CursorShape::Hidden
         } else {
-            CursorStyle::HollowBlock
+            cursor_style.shape
         };
 
         RenderableCellsIter::new(&self, config, selection, cursor)
     }
 
+    /// Window title set by the application
+    #[inline]
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Icon name set by the application
+    #[inline]
+    pub fn icon_name(&self) -> &str {
+        &self.icon_name
+    }
+
+    /// Working directory reported by the shell with OSC 7
+    #[inline]
+    pub fn working_directory(&self) -> Option<&WorkingDirectory> {
+        self.working_directory.as_ref()
+    }
+
+    /// Buffer line of the closest prompt above or below the top of the viewport
+    pub fn prompt_line(&self, direction: Direction) -> Option<usize> {
+        let top = self.grid.display_offset() + self.grid.num_lines().0 - 1;
+        let total_lines = self.grid.num_lines().0 + self.grid.scroll_limit();
+        let is_prompt = |line: &usize| self.grid[*line].marks().contains(PromptMarks::PROMPT);
+
+        match direction {
+            Direction::Backward => (top + 1..total_lines).find(is_prompt),
+            Direction::Forward => (0..top).rev().find(is_prompt),
+        }
+    }
+
+    /// Buffer lines of the output of the last finished command
+    pub fn last_command_output(&self) -> Option<RangeInclusive<usize>> {
+        let total_lines = self.grid.num_lines().0 + self.grid.scroll_limit();
+        let end = (0..total_lines)
+            .find(|&line| self.grid[line].marks().contains(PromptMarks::OUTPUT_END))?;
+
+        for line in end..total_lines {
+            let marks = self.grid[line].marks();
+            if marks.contains(PromptMarks::OUTPUT) {
+                return Some(end..=line);
+            } else if line != end && marks.intersects(PromptMarks::PROMPT | PromptMarks::COMMAND) {
+                break;
+            }
+        }
+
+        None
+    }
+
+    /// Active style of the terminal cursor
+    pub fn cursor_style(&self) -> CursorStyle {
+        let mut style = self.cursor_style.unwrap_or(self.default_cursor_style);
+        match self.cursor_blinking {
+            CursorBlinking::Never => style.blinking = false,
+            CursorBlinking::Always => style.blinking = true,
+            CursorBlinking::Off | CursorBlinking::On => (),
+        }
+        style
+    }
+
+    /// Check if a blinking cursor is visible
+    ///
+    /// Blinking text is only known to be visible once it has been rendered.
+    pub fn has_blinking_cursor(&self) -> bool {
+        let cursor_visible = self.mode.contains(TermMode::SHOW_CURSOR)
+            && !self.mode.contains(TermMode::VI)
+            && self.grid.contains(&self.cursor.point);
+
+        cursor_visible && self.cursor_style().blinking
+    }
+
     /// Resize terminal to new dimensions
     pub fn resize(&mut self, size: &SizeInfo) {
+        self.size_info = *size;
+        self.search_matches = None;
+
         let old_cols = self.grid.num_cols();
         let old_lines = self.grid.num_lines();
         let mut num_cols = size.cols();
@@ -1082,6 +1752,7 @@ impl<T> Term<T> {
 
         // Reset scrolling region to new size
         self.scroll_region = Line(0)..self.grid.num_lines();
+        self.horizontal_margins = Column(0)..self.grid.num_cols();
 
         // Ensure cursors are in-bounds.
         self.cursor.point.col = min(self.cursor.point.col, num_cols - 1);
@@ -1093,6 +1764,8 @@ impl<T> Term<T> {
 
         // Recreate tabs list
         self.tabs = TabStops::new(self.grid.num_cols(), self.tabspaces);
+
+        self.clamp_vi_mode_cursor();
     }
 
     #[inline]
@@ -1109,10 +1782,17 @@ impl<T> Term<T> {
         if self.alt {
             let template = self.cursor.template;
             self.grid.region_mut(..).each(|c| c.reset(&template));
+            self.reset_line_sizes(Line(0)..self.grid.num_lines());
         }
 
         self.alt = !self.alt;
         std::mem::swap(&mut self.grid, &mut self.alt_grid);
+
+        // Each screen has its own keyboard modes
+        std::mem::swap(&mut self.keyboard_mode_stack, &mut self.inactive_keyboard_mode_stack);
+        self.update_keyboard_mode();
+
+        self.clamp_vi_mode_cursor();
     }
 
     /// Scroll screen down
@@ -1127,7 +1807,17 @@ impl<T> Term<T> {
 
         // Scroll between origin and bottom
         let template = Cell { bg: self.cursor.template.bg, ..Cell::default() };
-        self.grid.scroll_down(&(origin..self.scroll_region.end), lines, &template);
+        let region = origin..self.scroll_region.end;
+        if self.has_horizontal_margins() {
+            self.grid.scroll_down_columns(&region, &self.horizontal_margins, lines, &template);
+            return;
+        }
+        self.grid.scroll_down(&region, lines, &template);
+
+        // Keep the vi mode cursor on the same content when the history moves
+        if origin == Line(0) {
+            self.vi_mode_cursor.point.line = self.vi_mode_cursor.point.line.saturating_sub(lines.0);
+        }
     }
 
     /// Scroll screen up
@@ -1141,7 +1831,24 @@ impl<T> Term<T> {
 
         // Scroll from origin to bottom less number of lines
         let template = Cell { bg: self.cursor.template.bg, ..Cell::default() };
-        self.grid.scroll_up(&(origin..self.scroll_region.end), lines, &template);
+        let region = origin..self.scroll_region.end;
+        if self.has_horizontal_margins() {
+            self.grid.scroll_up_columns(&region, &self.horizontal_margins, lines, &template);
+            return;
+        }
+        self.grid.scroll_up(&region, lines, &template);
+
+        // Keep the vi mode cursor on the same content when the history moves
+        if origin == Line(0) {
+            self.vi_mode_cursor.point.line += lines.0;
+            self.clamp_vi_mode_cursor();
+
+            // Release graphics which have left the history
+            let total_lines = self.grid.num_lines().0 + self.grid.scroll_limit();
+            if self.graphics.scroll_up(self.alt, lines.0, total_lines) {
+                self.sweep_tables();
+            }
+        }
     }
 
     fn deccolm(&mut self)
@@ -1152,88 +1859,472 @@ impl<T> Term<T> {
         // Clear scrolling region
         self.set_scrolling_region(1, self.grid.num_lines().0);
 
+        // Disable left and right margins
+        self.mode.remove(TermMode::LEFT_RIGHT_MARGIN);
+        self.horizontal_margins = Column(0)..self.grid.num_cols();
+
         // Clear grid
         let template = self.cursor.template;
         self.grid.region_mut(..).each(|c| c.reset(&template));
+        self.reset_line_sizes(Line(0)..self.grid.num_lines());
     }
 
+    /// Check if the left and right margins are narrower than the screen
     #[inline]
-    pub fn background_color(&self) -> Rgb {
-        self.colors[NamedColor::Background]
+    fn has_horizontal_margins(&self) -> bool {
+        self.horizontal_margins.start > Column(0)
+            || self.horizontal_margins.end < self.grid.num_cols()
     }
 
-    #[inline]
-    pub fn exit(&mut self)
-    where
-        T: EventListener,
-    {
-        self.event_proxy.send_event(Event::Exit);
+    /// Top left and bottom right corner of the area addressed relative to the origin
+    fn origin_bounds(&self) -> (Point, Point) {
+        if self.mode.contains(TermMode::ORIGIN) {
+            let margins = &self.horizontal_margins;
+            let start = Point::new(self.scroll_region.start, margins.start);
+            (start, Point::new(self.scroll_region.end - 1, margins.end - 1))
+        } else {
+            let end = Point::new(self.grid.num_lines() - 1, self.grid.num_cols() - 1);
+            (Point::new(Line(0), Column(0)), end)
+        }
     }
 
-    pub fn clipboard(&mut self) -> &mut Clipboard {
-        &mut self.clipboard
+    /// Inclusive corners of a rectangular area, clamped to the origin bounds
+    ///
+    /// The area is empty when the start is below or right of the end.
+    fn rectangle_bounds(&self, area: ansi::Rectangle) -> (Point, Point) {
+        let (origin, max) = self.origin_bounds();
+        let start = Point::new(area.top + origin.line, area.left + origin.col);
+        let end = Point::new(
+            area.bottom.map_or(max.line, |bottom| min(bottom + origin.line, max.line)),
+            area.right.map_or(max.col, |right| min(right + origin.col, max.col)),
+        );
+        (start, end)
     }
-}
 
-impl<T> TermInfo for Term<T> {
-    #[inline]
-    fn lines(&self) -> Line {
-        self.grid.num_lines()
+    /// Call `func` for every cell of a rectangular area
+    ///
+    /// With the stream extent all lines but the first start at the left edge and all lines but
+    /// the last end at the right edge.
+    fn for_each_rectangle_cell<F>(
+        &mut self,
+        area: ansi::Rectangle,
+        extent: AttributeChangeExtent,
+        mut func: F,
+    ) where
+        F: FnMut(&mut Cell),
+    {
+        let (start, end) = self.rectangle_bounds(area);
+        if start.line > end.line {
+            return;
+        }
+
+        let (origin, max) = self.origin_bounds();
+        let last = end.line.0 - start.line.0;
+        for (i, row) in self.grid.region_mut(start.line..end.line + 1).into_iter().enumerate() {
+            let (left, right) = match extent {
+                AttributeChangeExtent::Rectangle => (start.col, end.col),
+                AttributeChangeExtent::Stream => (
+                    if i == 0 { start.col } else { origin.col },
+                    if i == last { end.col } else { max.col },
+                ),
+            };
+
+            if left <= right {
+                row[left..right + 1].iter_mut().for_each(&mut func);
+            }
+        }
     }
 
+    /// Column after the right margin if the cursor is left of it, otherwise the end of the line
     #[inline]
-    fn cols(&self) -> Column {
-        self.grid.num_cols()
+    fn cursor_line_end(&self) -> Column {
+        let end = if self.cursor.point.col < self.horizontal_margins.end {
+            self.horizontal_margins.end
+        } else {
+            self.grid.num_cols()
+        };
+        min(end, self.cursor_line_cols())
     }
-}
 
-impl<T: EventListener> ansi::Handler for Term<T> {
+    /// Number of usable columns on the cursor's line
     #[inline]
-    #[cfg(not(windows))]
-    fn set_title(&mut self, title: &str) {
-        if self.dynamic_title {
-            trace!("Setting window title to '{}'", title);
-
-            self.title = title.into();
-            self.event_proxy.send_event(Event::Title(title.to_owned()));
+    fn cursor_line_cols(&self) -> Column {
+        if self.grid[self.cursor.point.line].line_size().is_double_width() {
+            Column(self.grid.num_cols().0 / 2)
+        } else {
+            self.grid.num_cols()
         }
     }
 
-    #[inline]
-    #[cfg(windows)]
-    fn set_title(&mut self, title: &str) {
-        if self.dynamic_title {
-            // cmd.exe in winpty: winpty incorrectly sets the title to ' ' instead of
-            // 'Alacritty' - thus we have to substitute this back to get equivalent
-            // behaviour as conpty.
-            //
-            // The starts_with check is necessary because other shells e.g. bash set a
-            // different title and don't need Alacritty prepended.
-            trace!("Setting window title to '{}'", title);
-
-            let title = if !tty::is_conpty() && title.starts_with(' ') {
-                format!("Alacritty {}", title.trim())
-            } else {
-                title.to_owned()
-            };
+    /// Cell containing the last character written before the cursor
+    fn previous_cell(&self) -> Option<Point> {
+        let mut point = self.cursor.point;
+        if !self.input_needs_wrap {
+            if point.col == Column(0) {
+                return None;
+            }
+            point.col -= 1;
+        }
 
-            self.title = title.clone();
-            self.event_proxy.send_event(Event::Title(title.to_owned()));
+        if self.grid[&point].flags.contains(Flags::WIDE_CHAR_SPACER) && point.col > Column(0) {
+            point.col -= 1;
         }
+
+        Some(point)
     }
 
-    /// A character to be displayed
-    #[inline]
-    fn input(&mut self, c: char) {
-        // If enabled, scroll to bottom when character is received
-        if self.auto_scroll {
-            self.scroll_display(Scroll::Bottom);
+    /// Add a character to the grapheme cluster of the cell before the cursor
+    ///
+    /// Returns `false` if the character starts a new grapheme cluster instead. Zero-width
+    /// characters are always added to the previous cell.
+    fn extend_cluster(&mut self, c: char) -> bool {
+        // ASCII characters always start a new cluster
+        if c.is_ascii() {
+            return false;
         }
 
-        if self.input_needs_wrap {
-            if !self.mode.contains(TermMode::LINE_WRAP) {
-                return;
-            }
+        let point = match self.previous_cell() {
+            Some(point) => point,
+            None => return false,
+        };
+
+        let cell = self.grid[&point];
+        let mut cluster = String::new();
+        cluster.push(cell.c);
+        cluster.push_str(self.cluster(&cell));
+        cluster.push(c);
+
+        if self.width_policy.char_width(c) != Some(0) && cluster.graphemes(true).nth(1).is_some() {
+            return false;
+        }
+
+        if self.clusters.clusters.needs_sweep(self.total_cells()) {
+            self.sweep_tables();
+        }
+
+        // Characters which can't be stored anymore are dropped
+        let id = self.clusters.insert(&cluster[cell.c.len_utf8()..]);
+        if id.is_none() {
+            return true;
+        }
+        self.grid[&point].cluster = id;
+
+        // Clusters like flags can be wider than their first character
+        let line_end = self.cursor_line_end();
+        if !cell.flags.contains(Flags::WIDE_CHAR)
+            && !self.input_needs_wrap
+            && self.width_policy.cluster_width(&cluster) == 2
+        {
+            self.grid[&point].flags.insert(Flags::WIDE_CHAR);
+
+            let spacer = &mut self.grid[&self.cursor.point];
+            *spacer = self.cursor.template;
+            spacer.flags.insert(Flags::WIDE_CHAR_SPACER);
+
+            if self.cursor.point.col + 1 < line_end {
+                self.cursor.point.col += 1;
+            } else {
+                self.input_needs_wrap = true;
+            }
+        }
+
+        true
+    }
+
+    /// Reset the size of all lines in `lines` to normal
+    fn reset_line_sizes(&mut self, lines: Range<Line>) {
+        if lines.start >= lines.end {
+            return;
+        }
+
+        for row in self.grid.region_mut(lines) {
+            row.line_size = LineSize::Normal;
+        }
+    }
+
+    /// Left margin if the cursor is right of it, otherwise the start of the line
+    #[inline]
+    fn cursor_line_start(&self) -> Column {
+        if self.cursor.point.col >= self.horizontal_margins.start {
+            self.horizontal_margins.start
+        } else {
+            Column(0)
+        }
+    }
+
+    /// Apply the keyboard modes at the top of the stack to the terminal mode
+    fn update_keyboard_mode(&mut self) {
+        let keyboard_mode =
+            self.keyboard_mode_stack.last().copied().unwrap_or(KeyboardModes::NO_MODE);
+        self.mode.remove(TermMode::KITTY_KEYBOARD_PROTOCOL);
+        self.mode.insert(TermMode::from(keyboard_mode));
+    }
+
+    /// Replace the underline style of new cells, since they are mutually exclusive
+    #[inline]
+    fn set_underline(&mut self, style: Flags) {
+        self.cursor.template.flags.remove(Flags::ALL_UNDERLINES);
+        self.cursor.template.flags.insert(style);
+    }
+
+    #[inline]
+    pub fn background_color(&self) -> Rgb {
+        self.colors[NamedColor::Background]
+    }
+
+    #[inline]
+    pub fn exit(&mut self)
+    where
+        T: EventListener,
+    {
+        self.event_proxy.send_event(Event::Exit);
+    }
+
+    pub fn clipboard(&mut self) -> &mut Clipboard {
+        &mut self.clipboard
+    }
+
+    /// Characters separating words for semantic selection and motions
+    #[inline]
+    pub fn semantic_escape_chars(&self) -> &str {
+        &self.semantic_escape_chars
+    }
+
+    /// Toggle the vi mode
+    pub fn toggle_vi_mode(&mut self) {
+        self.mode ^= TermMode::VI;
+        self.grid.selection = None;
+
+        // Start at the position of the terminal cursor within the viewport
+        if self.mode.contains(TermMode::VI) {
+            let point = self.grid.visible_to_buffer(self.cursor.point);
+            self.vi_mode_cursor = ViModeCursor::new(point);
+        } else {
+            self.regex_search = None;
+            self.search_matches = None;
+        }
+
+        self.dirty = true;
+    }
+
+    /// Move the vi mode cursor
+    pub fn vi_motion(&mut self, motion: ViMotion)
+    where
+        T: EventListener,
+    {
+        // Require vi mode to be active
+        if !self.mode.contains(TermMode::VI) {
+            return;
+        }
+
+        self.vi_mode_cursor = self.vi_mode_cursor.motion(self, motion);
+        self.update_vi_selection();
+
+        // Scroll the display to keep the cursor visible
+        self.scroll_to_point(self.vi_mode_cursor.point);
+
+        self.dirty = true;
+    }
+
+    /// Scroll the display just enough to make a buffer point visible
+    pub fn scroll_to_point(&mut self, point: Point<usize>)
+    where
+        T: EventListener,
+    {
+        let line = point.line as isize;
+        let display_offset = self.grid.display_offset() as isize;
+        let num_lines = self.grid.num_lines().0 as isize;
+        if line < display_offset {
+            self.scroll_display(Scroll::Lines(line - display_offset));
+        } else if line >= display_offset + num_lines {
+            self.scroll_display(Scroll::Lines(line - display_offset - num_lines + 1));
+        }
+    }
+
+    /// Extend the active selection to the vi mode cursor
+    fn update_vi_selection(&mut self) {
+        let point = self.vi_mode_cursor.point;
+        if let Some(selection) = &mut self.grid.selection {
+            selection.update(point, Side::Left);
+            selection.include_all();
+        }
+    }
+
+    /// Clamp the vi mode cursor to the available grid area
+    fn clamp_vi_mode_cursor(&mut self) {
+        let topmost_line = vi_mode::topmost_line(self);
+        let last_col = self.grid.num_cols() - 1;
+
+        let point = &mut self.vi_mode_cursor.point;
+        point.line = min(point.line, topmost_line);
+        point.col = min(point.col, last_col);
+    }
+}
+
+impl<T> TermInfo for Term<T> {
+    #[inline]
+    fn lines(&self) -> Line {
+        self.grid.num_lines()
+    }
+
+    #[inline]
+    fn cols(&self) -> Column {
+        self.grid.num_cols()
+    }
+}
+
+/// Value of a cell in the checksum of a rectangular area
+fn cell_checksum(cell: &Cell, cluster: &str) -> u16 {
+    // Wide characters only count once
+    if cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+        return 0;
+    }
+
+    let mut checksum = iter::once(cell.c)
+        .chain(cluster.chars())
+        .fold(0u16, |checksum, c| checksum.wrapping_add(c as u32 as u16));
+
+    let flags = [
+        (Flags::UNDERLINE, 0x10),
+        (Flags::INVERSE, 0x20),
+        (Flags::BLINK, 0x40),
+        (Flags::BOLD, 0x80),
+    ];
+    for &(flag, value) in flags.iter() {
+        if cell.flags.contains(flag) {
+            checksum = checksum.wrapping_add(value);
+        }
+    }
+
+    checksum
+}
+
+/// SGR parameters which recreate the attributes of a cell
+fn sgr_parameters(cell: &Cell) -> String {
+    let mut parameters = vec![String::from("0")];
+
+    let flags = [
+        (Flags::BOLD, "1"),
+        (Flags::DIM, "2"),
+        (Flags::ITALIC, "3"),
+        (Flags::BLINK, "5"),
+        (Flags::UNDERLINE, "4"),
+        (Flags::DOUBLE_UNDERLINE, "4:2"),
+        (Flags::UNDERCURL, "4:3"),
+        (Flags::DOTTED_UNDERLINE, "4:4"),
+        (Flags::DASHED_UNDERLINE, "4:5"),
+        (Flags::INVERSE, "7"),
+        (Flags::HIDDEN, "8"),
+        (Flags::STRIKEOUT, "9"),
+        (Flags::OVERLINE, "53"),
+    ];
+    for &(flag, parameter) in flags.iter() {
+        if cell.flags.contains(flag) {
+            parameters.push(String::from(parameter));
+        }
+    }
+
+    parameters.extend(sgr_color(cell.fg, 3));
+    parameters.extend(sgr_color(cell.bg, 4));
+    parameters.extend(cell.underline_color.and_then(|color| sgr_color(color, 5)));
+
+    parameters.join(";")
+}
+
+/// SGR parameters selecting a foreground (3), background (4) or underline (5) color
+///
+/// Returns `None` for the default colors.
+fn sgr_color(color: Color, kind: usize) -> Option<String> {
+    match color {
+        Color::Named(named) if (named as usize) < 8 && kind != 5 => {
+            Some(format!("{}{}", kind, named as usize))
+        },
+        Color::Named(named) if (named as usize) < 16 && kind != 5 => {
+            Some(format!("{}", (kind + 6) * 10 + named as usize - 8))
+        },
+        Color::Named(named) if (named as usize) < 16 => Some(format!("58;5;{}", named as usize)),
+        Color::Named(_) => None,
+        Color::Indexed(index) => Some(format!("{}8;5;{}", kind, index)),
+        Color::Spec(rgb) => Some(format!("{}8;2;{};{};{}", kind, rgb.r, rgb.g, rgb.b)),
+    }
+}
+
+impl<T: EventListener> ansi::Handler for Term<T> {
+    #[inline]
+    #[cfg(not(windows))]
+    fn set_title(&mut self, title: &str) {
+        if self.dynamic_title {
+            trace!("Setting window title to '{}'", title);
+
+            self.title = title.into();
+            self.event_proxy.send_event(Event::Title(title.to_owned()));
+        }
+    }
+
+    #[inline]
+    #[cfg(windows)]
+    fn set_title(&mut self, title: &str) {
+        if self.dynamic_title {
+            // cmd.exe in winpty: winpty incorrectly sets the title to ' ' instead of
+            // 'Alacritty' - thus we have to substitute this back to get equivalent
+            // behaviour as conpty.
+            //
+            // The starts_with check is necessary because other shells e.g. bash set a
+            // different title and don't need Alacritty prepended.
+            trace!("Setting window title to '{}'", title);
+
+            let title = if !tty::is_conpty() && title.starts_with(' ') {
+                format!("Alacritty {}", title.trim())
+            } else {
+                title.to_owned()
+            };
+
+            self.title = title.clone();
+            self.event_proxy.send_event(Event::Title(title.to_owned()));
+        }
+    }
+
+    #[inline]
+    fn set_icon_name(&mut self, icon_name: &str) {
+        if self.dynamic_title {
+            trace!("Setting icon name to '{}'", icon_name);
+
+            self.icon_name = icon_name.into();
+            self.event_proxy.send_event(Event::IconName(icon_name.to_owned()));
+        }
+    }
+
+    /// A character to be displayed
+    #[inline]
+    fn input(&mut self, c: char) {
+        // If enabled, scroll to bottom when character is received
+        if self.auto_scroll {
+            self.scroll_display(Scroll::Bottom);
+        }
+
+        if self.extend_cluster(c) {
+            return;
+        }
+
+        let width = self.width_policy.char_width(c);
+
+        // Wrap wide characters which don't fit into the last column of the line
+        if width == Some(2)
+            && !self.input_needs_wrap
+            && self.mode.contains(TermMode::LINE_WRAP)
+            && self.cursor.point.col + 1 >= self.cursor_line_end()
+            && self.cursor.point.col > self.cursor_line_start()
+        {
+            let spacer = &mut self.grid[&self.cursor.point];
+            *spacer = self.cursor.template;
+            spacer.flags.insert(Flags::LEADING_WIDE_CHAR_SPACER);
+            self.input_needs_wrap = true;
+        }
+
+        if self.input_needs_wrap {
+            if !self.mode.contains(TermMode::LINE_WRAP) {
+                return;
+            }
 
             trace!("Wrapping input");
 
@@ -1250,13 +2341,21 @@ impl<T: EventListener> ansi::Handler for Term<T> {
                 self.cursor.point.line += 1;
             }
 
-            self.cursor.point.col = Column(0);
+            self.cursor.point.col = self.cursor_line_start();
             self.input_needs_wrap = false;
         }
 
+        // The cursor might have moved onto a double-width line without being clamped
+        self.cursor.point.col = min(self.cursor.point.col, self.cursor_line_cols() - 1);
+
         // Number of cells the char will occupy
-        if let Some(width) = c.width() {
-            let num_cols = self.grid.num_cols();
+        if let Some(width) = width {
+            // Zero-width characters without a preceding character are dropped
+            if width == 0 {
+                return;
+            }
+
+            let num_cols = self.cursor_line_end();
 
             // If in insert mode, first shift cells to the right.
             if self.mode.contains(TermMode::INSERT) && self.cursor.point.col + width < num_cols {
@@ -1272,20 +2371,10 @@ impl<T: EventListener> ansi::Handler for Term<T> {
                 }
             }
 
-            // Handle zero-width characters
-            if width == 0 {
-                let mut col = self.cursor.point.col.0.saturating_sub(1);
-                let line = self.cursor.point.line;
-                if self.grid[line][Column(col)].flags.contains(Flags::WIDE_CHAR_SPACER) {
-                    col = col.saturating_sub(1);
-                }
-                self.grid[line][Column(col)].push_extra(c);
-                return;
-            }
-
             let cell = &mut self.grid[&self.cursor.point];
             *cell = self.cursor.template;
-            cell.c = self.cursor.charsets[self.active_charset].map(c);
+            let charset = self.single_shift.take().unwrap_or(self.active_charset);
+            cell.c = self.cursor.charsets[charset].map(c);
 
             // Handle wide chars
             if width == 2 {
@@ -1300,7 +2389,7 @@ impl<T: EventListener> ansi::Handler for Term<T> {
             }
         }
 
-        if (self.cursor.point.col + 1) < self.grid.num_cols() {
+        if (self.cursor.point.col + 1) < self.cursor_line_end() {
             self.cursor.point.col += 1;
         } else {
             self.input_needs_wrap = true;
@@ -1313,19 +2402,34 @@ impl<T: EventListener> ansi::Handler for Term<T> {
 
         let template = Cell { c: 'E', ..Cell::default() };
         self.grid.region_mut(..).each(|c| c.reset(&template));
+        self.reset_line_sizes(Line(0)..self.grid.num_lines());
+    }
+
+    #[inline]
+    fn set_line_size(&mut self, line_size: LineSize) {
+        trace!("Setting line size: {:?}", line_size);
+
+        let line = self.cursor.point.line;
+        self.grid[line].line_size = line_size;
+
+        // Characters in the second half of the line are no longer visible
+        if line_size.is_double_width() {
+            let half = Column(self.grid.num_cols().0 / 2);
+            let template = self.cursor.template;
+            for cell in &mut self.grid[line][half..] {
+                cell.reset(&template);
+            }
+
+            self.cursor.point.col = min(self.cursor.point.col, half - 1);
+        }
     }
 
     #[inline]
     fn goto(&mut self, line: Line, col: Column) {
         trace!("Going to: line={}, col={}", line, col);
-        let (y_offset, max_y) = if self.mode.contains(TermMode::ORIGIN) {
-            (self.scroll_region.start, self.scroll_region.end - 1)
-        } else {
-            (Line(0), self.grid.num_lines() - 1)
-        };
-
-        self.cursor.point.line = min(line + y_offset, max_y);
-        self.cursor.point.col = min(col, self.grid.num_cols() - 1);
+        let (origin, max) = self.origin_bounds();
+        self.cursor.point.line = min(line + origin.line, max.line);
+        self.cursor.point.col = min(min(col + origin.col, max.col), self.cursor_line_cols() - 1);
         self.input_needs_wrap = false;
     }
 
@@ -1343,13 +2447,18 @@ impl<T: EventListener> ansi::Handler for Term<T> {
 
     #[inline]
     fn insert_blank(&mut self, count: Column) {
-        // Ensure inserting within terminal bounds
+        // Characters are only inserted between the left and right margins
+        if !self.horizontal_margins.contains(&self.cursor.point.col) {
+            return;
+        }
 
-        let count = min(count, self.grid.num_cols() - self.cursor.point.col);
+        // Ensure inserting within terminal bounds
+        let end = self.horizontal_margins.end;
+        let count = min(count, end - self.cursor.point.col);
 
         let source = self.cursor.point.col;
         let destination = self.cursor.point.col + count;
-        let num_cells = (self.grid.num_cols() - destination).0;
+        let num_cells = (end - destination).0;
 
         let line = &mut self.grid[self.cursor.point.line];
 
@@ -1384,21 +2493,22 @@ impl<T: EventListener> ansi::Handler for Term<T> {
     #[inline]
     fn move_forward(&mut self, cols: Column) {
         trace!("Moving forward: {}", cols);
-        self.cursor.point.col = min(self.cursor.point.col + cols, self.grid.num_cols() - 1);
+        self.cursor.point.col = min(self.cursor.point.col + cols, self.cursor_line_end() - 1);
         self.input_needs_wrap = false;
     }
 
     #[inline]
     fn move_backward(&mut self, cols: Column) {
         trace!("Moving backward: {}", cols);
-        self.cursor.point.col -= min(self.cursor.point.col, cols);
+        let start = self.cursor_line_start();
+        self.cursor.point.col -= min(self.cursor.point.col - start, cols);
         self.input_needs_wrap = false;
     }
 
     #[inline]
     fn identify_terminal<W: io::Write>(&mut self, writer: &mut W) {
         trace!("Reporting terminal identity");
-        let _ = writer.write_all(b"\x1b[?6c");
+        let _ = writer.write_all(b"\x1b[?62;4c");
     }
 
     #[inline]
@@ -1435,7 +2545,8 @@ impl<T: EventListener> ansi::Handler for Term<T> {
     fn put_tab(&mut self, mut count: i64) {
         trace!("Putting tab: {}", count);
 
-        while self.cursor.point.col < self.grid.num_cols() && count != 0 {
+        let num_cols = self.cursor_line_cols();
+        while self.cursor.point.col < num_cols && count != 0 {
             count -= 1;
 
             let cell = &mut self.grid[&self.cursor.point];
@@ -1444,7 +2555,7 @@ impl<T: EventListener> ansi::Handler for Term<T> {
             }
 
             loop {
-                if (self.cursor.point.col + 1) == self.grid.num_cols() {
+                if (self.cursor.point.col + 1) >= num_cols {
                     break;
                 }
 
@@ -1463,7 +2574,7 @@ impl<T: EventListener> ansi::Handler for Term<T> {
     #[inline]
     fn backspace(&mut self) {
         trace!("Backspace");
-        if self.cursor.point.col > Column(0) {
+        if self.cursor.point.col > self.cursor_line_start() {
             self.cursor.point.col -= 1;
             self.input_needs_wrap = false;
         }
@@ -1473,7 +2584,7 @@ impl<T: EventListener> ansi::Handler for Term<T> {
     #[inline]
     fn carriage_return(&mut self) {
         trace!("Carriage return");
-        self.cursor.point.col = Column(0);
+        self.cursor.point.col = self.cursor_line_start();
         self.input_needs_wrap = false;
     }
 
@@ -1483,7 +2594,10 @@ impl<T: EventListener> ansi::Handler for Term<T> {
         trace!("Linefeed");
         let next = self.cursor.point.line + 1;
         if next == self.scroll_region.end {
-            self.scroll_up(Line(1));
+            // Outside of the left and right margins the cursor stays at the bottom margin
+            if self.horizontal_margins.contains(&self.cursor.point.col) {
+                self.scroll_up(Line(1));
+            }
         } else if next < self.grid.num_lines() {
             self.cursor.point.line += 1;
         }
@@ -1555,7 +2669,9 @@ impl<T: EventListener> ansi::Handler for Term<T> {
     #[inline]
     fn insert_blank_lines(&mut self, lines: Line) {
         trace!("Inserting blank {} lines", lines);
-        if self.scroll_region.contains(&self.cursor.point.line) {
+        if self.scroll_region.contains(&self.cursor.point.line)
+            && self.horizontal_margins.contains(&self.cursor.point.col)
+        {
             let origin = self.cursor.point.line;
             self.scroll_down_relative(origin, lines);
         }
@@ -1568,7 +2684,10 @@ impl<T: EventListener> ansi::Handler for Term<T> {
 
         trace!("Deleting {} lines", lines);
 
-        if lines.0 > 0 && self.scroll_region.contains(&self.cursor.point.line) {
+        if lines.0 > 0
+            && self.scroll_region.contains(&self.cursor.point.line)
+            && self.horizontal_margins.contains(&self.cursor.point.col)
+        {
             self.scroll_up_relative(origin, lines);
         }
     }
@@ -1588,13 +2707,18 @@ impl<T: EventListener> ansi::Handler for Term<T> {
 
     #[inline]
     fn delete_chars(&mut self, count: Column) {
-        let cols = self.grid.num_cols();
+        // Characters are only deleted between the left and right margins
+        if !self.horizontal_margins.contains(&self.cursor.point.col) {
+            return;
+        }
 
-        // Ensure deleting within terminal bounds
-        let count = min(count, cols);
+        let cols = self.horizontal_margins.end;
 
+        // Ensure deleting within terminal bounds
         let start = self.cursor.point.col;
-        let end = min(start + count, cols - 1);
+        let count = min(count, cols - start);
+
+        let end = start + count;
         let n = (cols - end).0;
 
         let line = &mut self.grid[self.cursor.point.line];
@@ -1609,7 +2733,7 @@ impl<T: EventListener> ansi::Handler for Term<T> {
         // Clear last `count` cells in line. If deleting 1 char, need to delete
         // 1 cell.
         let end = cols - count;
-        for c in &mut line[end..] {
+        for c in &mut line[end..cols] {
             c.reset(&self.cursor.template);
         }
     }
@@ -1744,6 +2868,7 @@ impl<T: EventListener> ansi::Handler for Term<T> {
     fn clear_screen(&mut self, mode: ansi::ClearMode) {
         trace!("Clearing screen: {:?}", mode);
         let template = self.cursor.template;
+        self.search_matches = None;
 
         // Remove active selections
         self.grid.selection = None;
@@ -1758,8 +2883,12 @@ impl<T: EventListener> ansi::Handler for Term<T> {
                         .region_mut((self.cursor.point.line + 1)..)
                         .each(|cell| cell.reset(&template));
                 }
+                self.reset_line_sizes(self.cursor.point.line + 1..self.grid.num_lines());
+            },
+            ansi::ClearMode::All => {
+                self.grid.region_mut(..).each(|c| c.reset(&template));
+                self.reset_line_sizes(Line(0)..self.grid.num_lines());
             },
-            ansi::ClearMode::All => self.grid.region_mut(..).each(|c| c.reset(&template)),
             ansi::ClearMode::Above => {
                 // If clearing more than one line
                 if self.cursor.point.line > Line(1) {
@@ -1768,59 +2897,273 @@ impl<T: EventListener> ansi::Handler for Term<T> {
                         .region_mut(..self.cursor.point.line)
                         .each(|cell| cell.reset(&template));
                 }
+                self.reset_line_sizes(Line(0)..self.cursor.point.line);
                 // Clear up to the current column in the current line
                 let end = min(self.cursor.point.col + 1, self.grid.num_cols());
                 for cell in &mut self.grid[self.cursor.point.line][..end] {
                     cell.reset(&template);
                 }
             },
-            ansi::ClearMode::Saved => self.grid.clear_history(),
-        }
-    }
-
-    #[inline]
-    fn clear_tabs(&mut self, mode: ansi::TabulationClearMode) {
-        trace!("Clearing tabs: {:?}", mode);
-        match mode {
-            ansi::TabulationClearMode::Current => {
-                let column = self.cursor.point.col;
-                self.tabs[column] = false;
-            },
-            ansi::TabulationClearMode::All => {
-                self.tabs.clear_all();
+            ansi::ClearMode::Saved => {
+                self.grid.clear_history();
+                self.sweep_tables();
             },
         }
     }
 
-    // Reset all important fields in the term struct
     #[inline]
-    fn reset_state(&mut self) {
-        if self.alt {
-            self.swap_alt();
+    fn selective_clear_line(&mut self, mode: ansi::LineClearMode) {
+        trace!("Selectively clearing line: {:?}", mode);
+
+        let col = self.cursor.point.col;
+        let (start, end) = match mode {
+            ansi::LineClearMode::Right => (col, self.grid.num_cols()),
+            ansi::LineClearMode::Left => (Column(0), col + 1),
+            ansi::LineClearMode::All => (Column(0), self.grid.num_cols()),
+        };
+
+        let template = self.cursor.template;
+        for cell in &mut self.grid[self.cursor.point.line][start..end] {
+            if !cell.flags.contains(Flags::PROTECTED) {
+                cell.reset(&template);
+            }
         }
-        self.input_needs_wrap = false;
-        self.cursor = Default::default();
-        self.active_charset = Default::default();
-        self.mode = Default::default();
-        self.cursor_save = Default::default();
-        self.cursor_save_alt = Default::default();
-        self.colors = self.original_colors;
-        self.color_modified = [false; color::COUNT];
-        self.cursor_style = None;
-        self.grid.reset(&Cell::default());
-        self.alt_grid.reset(&Cell::default());
-        self.scroll_region = Line(0)..self.grid.num_lines();
-        self.title = DEFAULT_NAME.to_string();
-        self.title_stack.clear();
     }
 
     #[inline]
-    fn reverse_index(&mut self) {
-        trace!("Reversing index");
-        // if cursor is at the top
-        if self.cursor.point.line == self.scroll_region.start {
-            self.scroll_down(Line(1));
-        } else {
+    fn selective_clear_screen(&mut self, mode: ansi::ClearMode) {
+        trace!("Selectively clearing screen: {:?}", mode);
+
+        let cursor = self.cursor.point;
+        let last = Point::new(self.grid.num_lines() - 1, self.grid.num_cols() - 1);
+        let (start, end) = match mode {
+            ansi::ClearMode::Below => (cursor, last),
+            ansi::ClearMode::Above => (Point::new(Line(0), Column(0)), cursor),
+            ansi::ClearMode::All => (Point::new(Line(0), Column(0)), last),
+            ansi::ClearMode::Saved => return,
+        };
+
+        // Remove active selections
+        self.grid.selection = None;
+
+        let template = self.cursor.template;
+        for line in start.line.0..=end.line.0 {
+            let first = if line == start.line.0 { start.col } else { Column(0) };
+            let last = if line == end.line.0 { end.col } else { last.col };
+
+            for cell in &mut self.grid[Line(line)][first..last + 1] {
+                if !cell.flags.contains(Flags::PROTECTED) {
+                    cell.reset(&template);
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn set_character_protection(&mut self, protected: bool) {
+        trace!("Setting character protection: {}", protected);
+        self.cursor.template.flags.set(Flags::PROTECTED, protected);
+    }
+
+    #[inline]
+    fn copy_rectangle(&mut self, area: ansi::Rectangle, line: Line, col: Column) {
+        trace!("Copying rectangle {:?} to line={}, col={}", area, line, col);
+
+        let (start, end) = self.rectangle_bounds(area);
+        let (origin, max) = self.origin_bounds();
+        let dest = Point::new(line + origin.line, col + origin.col);
+        if start.line > end.line
+            || start.col > end.col
+            || dest.line > max.line
+            || dest.col > max.col
+        {
+            return;
+        }
+
+        // Cells copied past the edge of the origin bounds are discarded
+        let lines = min(end.line - start.line, max.line - dest.line) + 1;
+        let cols = min(end.col - start.col, max.col - dest.col) + 1;
+
+        // Read the whole source first, since it can overlap with the destination
+        let cells: Vec<Vec<Cell>> = self
+            .grid
+            .region(start.line..start.line + lines)
+            .into_iter()
+            .map(|row| row[start.col..start.col + cols].to_vec())
+            .collect();
+
+        let rows = self.grid.region_mut(dest.line..dest.line + lines);
+        for (row, cells) in rows.into_iter().zip(&cells) {
+            row[dest.col..dest.col + cols].copy_from_slice(cells);
+        }
+    }
+
+    #[inline]
+    fn fill_rectangle(&mut self, c: char, area: ansi::Rectangle) {
+        trace!("Filling rectangle {:?} with {:?}", area, c);
+
+        let fill = Cell { c, ..self.cursor.template };
+        self.for_each_rectangle_cell(area, AttributeChangeExtent::Rectangle, |cell| *cell = fill);
+    }
+
+    #[inline]
+    fn erase_rectangle(&mut self, area: ansi::Rectangle) {
+        trace!("Erasing rectangle {:?}", area);
+
+        let template = self.cursor.template;
+        self.for_each_rectangle_cell(area, AttributeChangeExtent::Rectangle, |cell| {
+            cell.reset(&template)
+        });
+    }
+
+    #[inline]
+    fn change_rectangle_attributes(&mut self, area: ansi::Rectangle, attrs: &[Attr]) {
+        trace!("Changing attributes of rectangle {:?}: {:?}", area, attrs);
+
+        let extent = self.attribute_change_extent;
+        self.for_each_rectangle_cell(area, extent, |cell| {
+            for attr in attrs {
+                match attr {
+                    Attr::Reset => cell.flags.remove(
+                        Flags::BOLD
+                            | Flags::ALL_UNDERLINES
+                            | Flags::BLINK
+                            | Flags::INVERSE
+                            | Flags::HIDDEN,
+                    ),
+                    Attr::Bold => cell.flags.insert(Flags::BOLD),
+                    Attr::CancelBoldDim => cell.flags.remove(Flags::BOLD | Flags::DIM),
+                    Attr::Underline => {
+                        cell.flags.remove(Flags::ALL_UNDERLINES);
+                        cell.flags.insert(Flags::UNDERLINE);
+                    },
+                    Attr::CancelUnderline => cell.flags.remove(Flags::ALL_UNDERLINES),
+                    Attr::BlinkSlow => cell.flags.insert(Flags::BLINK),
+                    Attr::CancelBlink => cell.flags.remove(Flags::BLINK),
+                    Attr::Reverse => cell.flags.insert(Flags::INVERSE),
+                    Attr::CancelReverse => cell.flags.remove(Flags::INVERSE),
+                    Attr::Hidden => cell.flags.insert(Flags::HIDDEN),
+                    Attr::CancelHidden => cell.flags.remove(Flags::HIDDEN),
+                    _ => (),
+                }
+            }
+        });
+    }
+
+    #[inline]
+    fn reverse_rectangle_attributes(&mut self, area: ansi::Rectangle, attrs: &[Attr]) {
+        trace!("Reversing attributes of rectangle {:?}: {:?}", area, attrs);
+
+        let mut flags = Flags::empty();
+        for attr in attrs {
+            match attr {
+                Attr::Bold => flags.insert(Flags::BOLD),
+                Attr::Underline => flags.insert(Flags::UNDERLINE),
+                Attr::BlinkSlow => flags.insert(Flags::BLINK),
+                Attr::Reverse => flags.insert(Flags::INVERSE),
+                Attr::Hidden => flags.insert(Flags::HIDDEN),
+                _ => (),
+            }
+        }
+
+        let extent = self.attribute_change_extent;
+        self.for_each_rectangle_cell(area, extent, |cell| {
+            // Any underline style counts as underlined
+            if flags.contains(Flags::UNDERLINE) && cell.flags.intersects(Flags::ALL_UNDERLINES) {
+                cell.flags.remove(Flags::ALL_UNDERLINES);
+                cell.flags.toggle(flags - Flags::UNDERLINE);
+            } else {
+                cell.flags.toggle(flags);
+            }
+        });
+    }
+
+    #[inline]
+    fn set_attribute_change_extent(&mut self, extent: AttributeChangeExtent) {
+        trace!("Setting attribute change extent: {:?}", extent);
+        self.attribute_change_extent = extent;
+    }
+
+    #[inline]
+    fn report_rectangle_checksum<W: io::Write>(
+        &mut self,
+        writer: &mut W,
+        id: i64,
+        area: ansi::Rectangle,
+    ) {
+        trace!("Reporting checksum of rectangle {:?}", area);
+
+        let (start, end) = self.rectangle_bounds(area);
+        let mut checksum = 0u16;
+        if start.line <= end.line && start.col <= end.col {
+            for row in self.grid.region(start.line..end.line + 1) {
+                for cell in &row[start.col..end.col + 1] {
+                    checksum = checksum.wrapping_add(cell_checksum(cell, self.cluster(cell)));
+                }
+            }
+        }
+
+        // Like the VT420, report the negated sum
+        let response = format!("\x1bP{}!~{:04X}\x1b\\", id, checksum.wrapping_neg());
+        let _ = writer.write_all(response.as_bytes());
+    }
+
+    #[inline]
+    fn clear_tabs(&mut self, mode: ansi::TabulationClearMode) {
+        trace!("Clearing tabs: {:?}", mode);
+        match mode {
+            ansi::TabulationClearMode::Current => {
+                let column = self.cursor.point.col;
+                self.tabs[column] = false;
+            },
+            ansi::TabulationClearMode::All => {
+                self.tabs.clear_all();
+            },
+        }
+    }
+
+    // Reset all important fields in the term struct
+    #[inline]
+    fn reset_state(&mut self) {
+        if self.alt {
+            self.swap_alt();
+        }
+        self.input_needs_wrap = false;
+        self.cursor = Default::default();
+        self.active_charset = Default::default();
+        self.single_shift = None;
+        self.mode = Default::default();
+        self.cursor_save = Default::default();
+        self.cursor_save_alt = Default::default();
+        self.colors = self.original_colors;
+        self.color_modified = [false; color::COUNT];
+        self.cursor_style = None;
+        self.grid.reset(&Cell::default());
+        self.alt_grid.reset(&Cell::default());
+        self.scroll_region = Line(0)..self.grid.num_lines();
+        self.horizontal_margins = Column(0)..self.grid.num_cols();
+        self.attribute_change_extent = Default::default();
+        self.title = DEFAULT_NAME.to_string();
+        self.icon_name.clear();
+        self.title_stack.clear();
+        self.keyboard_mode_stack.clear();
+        self.inactive_keyboard_mode_stack.clear();
+        self.hyperlinks.clear();
+        self.clusters.clear();
+        self.graphic_cells.clear();
+        self.graphics.retain(HashMap::new());
+    }
+
+    #[inline]
+    fn reverse_index(&mut self) {
+        trace!("Reversing index");
+        // if cursor is at the top
+        if self.cursor.point.line == self.scroll_region.start {
+            // Outside of the left and right margins the cursor stays at the top margin
+            if self.horizontal_margins.contains(&self.cursor.point.col) {
+                self.scroll_down(Line(1));
+            }
+        } else {
             self.cursor.point.line -= min(self.cursor.point.line, Line(1));
         }
     }
@@ -1835,7 +3178,9 @@ impl<T: EventListener> ansi::Handler for Term<T> {
             Attr::Reset => {
                 self.cursor.template.fg = Color::Named(NamedColor::Foreground);
                 self.cursor.template.bg = Color::Named(NamedColor::Background);
-                self.cursor.template.flags = Flags::empty();
+                // Character protection is not a graphic rendition
+                self.cursor.template.flags &= Flags::PROTECTED;
+                self.cursor.template.underline_color = None;
             },
             Attr::Reverse => self.cursor.template.flags.insert(Flags::INVERSE),
             Attr::CancelReverse => self.cursor.template.flags.remove(Flags::INVERSE),
@@ -1845,15 +3190,21 @@ impl<T: EventListener> ansi::Handler for Term<T> {
             Attr::CancelBoldDim => self.cursor.template.flags.remove(Flags::BOLD | Flags::DIM),
             Attr::Italic => self.cursor.template.flags.insert(Flags::ITALIC),
             Attr::CancelItalic => self.cursor.template.flags.remove(Flags::ITALIC),
-            Attr::Underline => self.cursor.template.flags.insert(Flags::UNDERLINE),
-            Attr::CancelUnderline => self.cursor.template.flags.remove(Flags::UNDERLINE),
+            Attr::Underline => self.set_underline(Flags::UNDERLINE),
+            Attr::DoubleUnderline => self.set_underline(Flags::DOUBLE_UNDERLINE),
+            Attr::Undercurl => self.set_underline(Flags::UNDERCURL),
+            Attr::DottedUnderline => self.set_underline(Flags::DOTTED_UNDERLINE),
+            Attr::DashedUnderline => self.set_underline(Flags::DASHED_UNDERLINE),
+            Attr::CancelUnderline => self.cursor.template.flags.remove(Flags::ALL_UNDERLINES),
+            Attr::UnderlineColor(color) => self.cursor.template.underline_color = color,
+            Attr::Overline => self.cursor.template.flags.insert(Flags::OVERLINE),
+            Attr::CancelOverline => self.cursor.template.flags.remove(Flags::OVERLINE),
             Attr::Hidden => self.cursor.template.flags.insert(Flags::HIDDEN),
             Attr::CancelHidden => self.cursor.template.flags.remove(Flags::HIDDEN),
+            Attr::BlinkSlow | Attr::BlinkFast => self.cursor.template.flags.insert(Flags::BLINK),
+            Attr::CancelBlink => self.cursor.template.flags.remove(Flags::BLINK),
             Attr::Strike => self.cursor.template.flags.insert(Flags::STRIKEOUT),
             Attr::CancelStrike => self.cursor.template.flags.remove(Flags::STRIKEOUT),
-            _ => {
-                debug!("Term got unhandled attr: {:?}", attr);
-            },
         }
     }
 
@@ -1891,21 +3242,31 @@ impl<T: EventListener> ansi::Handler for Term<T> {
             ansi::Mode::BracketedPaste => self.mode.insert(TermMode::BRACKETED_PASTE),
             // Mouse encodings are mutually exlusive
             ansi::Mode::SgrMouse => {
-                self.mode.remove(TermMode::UTF8_MOUSE);
+                self.mode.remove(TermMode::MOUSE_ENCODING);
                 self.mode.insert(TermMode::SGR_MOUSE);
             },
             ansi::Mode::Utf8Mouse => {
-                self.mode.remove(TermMode::SGR_MOUSE);
+                self.mode.remove(TermMode::MOUSE_ENCODING);
                 self.mode.insert(TermMode::UTF8_MOUSE);
             },
+            ansi::Mode::UrxvtMouse => {
+                self.mode.remove(TermMode::MOUSE_ENCODING);
+                self.mode.insert(TermMode::URXVT_MOUSE);
+            },
+            ansi::Mode::SgrPixelMouse => {
+                self.mode.remove(TermMode::MOUSE_ENCODING);
+                self.mode.insert(TermMode::SGR_PIXEL_MOUSE);
+            },
             ansi::Mode::AlternateScroll => self.mode.insert(TermMode::ALTERNATE_SCROLL),
             ansi::Mode::LineWrap => self.mode.insert(TermMode::LINE_WRAP),
             ansi::Mode::LineFeedNewLine => self.mode.insert(TermMode::LINE_FEED_NEW_LINE),
             ansi::Mode::Origin => self.mode.insert(TermMode::ORIGIN),
+            ansi::Mode::LeftRightMargin => self.mode.insert(TermMode::LEFT_RIGHT_MARGIN),
             ansi::Mode::DECCOLM => self.deccolm(),
             ansi::Mode::Insert => self.mode.insert(TermMode::INSERT), // heh
+            ansi::Mode::SyncUpdate => self.mode.insert(TermMode::SYNC_UPDATE),
             ansi::Mode::BlinkingCursor => {
-                trace!("... unimplemented mode");
+                self.cursor_style.get_or_insert(self.default_cursor_style).blinking = true;
             },
         }
     }
@@ -1940,18 +3301,138 @@ impl<T: EventListener> ansi::Handler for Term<T> {
             ansi::Mode::BracketedPaste => self.mode.remove(TermMode::BRACKETED_PASTE),
             ansi::Mode::SgrMouse => self.mode.remove(TermMode::SGR_MOUSE),
             ansi::Mode::Utf8Mouse => self.mode.remove(TermMode::UTF8_MOUSE),
+            ansi::Mode::UrxvtMouse => self.mode.remove(TermMode::URXVT_MOUSE),
+            ansi::Mode::SgrPixelMouse => self.mode.remove(TermMode::SGR_PIXEL_MOUSE),
             ansi::Mode::AlternateScroll => self.mode.remove(TermMode::ALTERNATE_SCROLL),
             ansi::Mode::LineWrap => self.mode.remove(TermMode::LINE_WRAP),
             ansi::Mode::LineFeedNewLine => self.mode.remove(TermMode::LINE_FEED_NEW_LINE),
             ansi::Mode::Origin => self.mode.remove(TermMode::ORIGIN),
+            ansi::Mode::LeftRightMargin => {
+                self.mode.remove(TermMode::LEFT_RIGHT_MARGIN);
+                self.horizontal_margins = Column(0)..self.grid.num_cols();
+            },
             ansi::Mode::DECCOLM => self.deccolm(),
             ansi::Mode::Insert => self.mode.remove(TermMode::INSERT),
+            ansi::Mode::SyncUpdate => self.mode.remove(TermMode::SYNC_UPDATE),
             ansi::Mode::BlinkingCursor => {
-                trace!("... unimplemented mode");
+                self.cursor_style.get_or_insert(self.default_cursor_style).blinking = false;
+            },
+        }
+    }
+
+    #[inline]
+    fn push_keyboard_mode(&mut self, mode: KeyboardModes) {
+        trace!("Pushing keyboard mode: {:?}", mode);
+        if self.keyboard_mode_stack.len() >= KEYBOARD_MODE_STACK_MAX_DEPTH {
+            self.keyboard_mode_stack.remove(0);
+        }
+
+        self.keyboard_mode_stack.push(mode);
+        self.update_keyboard_mode();
+    }
+
+    #[inline]
+    fn pop_keyboard_modes(&mut self, to_pop: u16) {
+        trace!("Popping {} keyboard modes", to_pop);
+        let new_len = self.keyboard_mode_stack.len().saturating_sub(to_pop as usize);
+        self.keyboard_mode_stack.truncate(new_len);
+        self.update_keyboard_mode();
+    }
+
+    #[inline]
+    fn set_keyboard_mode(&mut self, mode: KeyboardModes, behavior: KeyboardModesApplyBehavior) {
+        trace!("Setting keyboard mode {:?} with behavior {:?}", mode, behavior);
+        let active_mode = self.keyboard_mode_stack.pop().unwrap_or(KeyboardModes::NO_MODE);
+        let new_mode = match behavior {
+            KeyboardModesApplyBehavior::Replace => mode,
+            KeyboardModesApplyBehavior::Union => active_mode | mode,
+            KeyboardModesApplyBehavior::Difference => active_mode - mode,
+        };
+
+        self.keyboard_mode_stack.push(new_mode);
+        self.update_keyboard_mode();
+    }
+
+    #[inline]
+    fn report_keyboard_mode<W: io::Write>(&mut self, writer: &mut W) {
+        trace!("Reporting keyboard mode");
+        let mode = self.keyboard_mode_stack.last().copied().unwrap_or(KeyboardModes::NO_MODE);
+        let _ = write!(writer, "\x1b[?{}u", mode.bits());
+    }
+
+    #[inline]
+    fn set_modify_other_keys(&mut self, modify_other_keys: ModifyOtherKeys) {
+        trace!("Setting modifyOtherKeys: {:?}", modify_other_keys);
+        self.mode.remove(TermMode::MODIFY_OTHER_KEYS | TermMode::MODIFY_ALL_KEYS);
+        match modify_other_keys {
+            ModifyOtherKeys::Reset => (),
+            ModifyOtherKeys::EnableExceptWellDefined => {
+                self.mode.insert(TermMode::MODIFY_OTHER_KEYS)
             },
+            ModifyOtherKeys::EnableAll => self.mode.insert(TermMode::MODIFY_ALL_KEYS),
         }
     }
 
+    #[inline]
+    fn mode_state(&self, mode: &ansi::Mode) -> ModeState {
+        let flag = match *mode {
+            ansi::Mode::CursorKeys => TermMode::APP_CURSOR,
+            ansi::Mode::Insert => TermMode::INSERT,
+            ansi::Mode::Origin => TermMode::ORIGIN,
+            ansi::Mode::LineWrap => TermMode::LINE_WRAP,
+            ansi::Mode::LeftRightMargin => TermMode::LEFT_RIGHT_MARGIN,
+            ansi::Mode::LineFeedNewLine => TermMode::LINE_FEED_NEW_LINE,
+            ansi::Mode::ShowCursor => TermMode::SHOW_CURSOR,
+            ansi::Mode::ReportMouseClicks => TermMode::MOUSE_REPORT_CLICK,
+            ansi::Mode::ReportCellMouseMotion => TermMode::MOUSE_DRAG,
+            ansi::Mode::ReportAllMouseMotion => TermMode::MOUSE_MOTION,
+            ansi::Mode::ReportFocusInOut => TermMode::FOCUS_IN_OUT,
+            ansi::Mode::Utf8Mouse => TermMode::UTF8_MOUSE,
+            ansi::Mode::SgrMouse => TermMode::SGR_MOUSE,
+            ansi::Mode::UrxvtMouse => TermMode::URXVT_MOUSE,
+            ansi::Mode::SgrPixelMouse => TermMode::SGR_PIXEL_MOUSE,
+            ansi::Mode::AlternateScroll => TermMode::ALTERNATE_SCROLL,
+            ansi::Mode::SwapScreenAndSetRestoreCursor => TermMode::ALT_SCREEN,
+            ansi::Mode::BracketedPaste => TermMode::BRACKETED_PASTE,
+            ansi::Mode::SyncUpdate => TermMode::SYNC_UPDATE,
+            ansi::Mode::BlinkingCursor => return ModeState::from(self.cursor_style().blinking),
+            // Switching to 132 columns is not supported
+            ansi::Mode::DECCOLM => return ModeState::PermanentlyReset,
+        };
+
+        ModeState::from(self.mode.contains(flag))
+    }
+
+    #[inline]
+    fn status_string(&self, request: StatusRequest) -> Option<String> {
+        trace!("Reporting status string: {:?}", request);
+        let status = match request {
+            StatusRequest::Sgr => format!("{}m", sgr_parameters(&self.cursor.template)),
+            StatusRequest::ScrollingRegion => {
+                format!("{};{}r", self.scroll_region.start.0 + 1, self.scroll_region.end.0)
+            },
+            StatusRequest::CursorStyle => {
+                let style = self.cursor_style();
+                let shape = match style.shape {
+                    CursorShape::Underline => 4,
+                    CursorShape::Beam => 6,
+                    CursorShape::Block | CursorShape::HollowBlock | CursorShape::Hidden => 2,
+                };
+                format!("{} q", shape - style.blinking as u8)
+            },
+            StatusRequest::CharacterProtection => {
+                let protected = self.cursor.template.flags.contains(Flags::PROTECTED);
+                format!("{}\"q", protected as u8)
+            },
+            StatusRequest::AttributeChangeExtent => match self.attribute_change_extent {
+                AttributeChangeExtent::Stream => String::from("1*x"),
+                AttributeChangeExtent::Rectangle => String::from("2*x"),
+            },
+        };
+
+        Some(status)
+    }
+
     #[inline]
     fn set_scrolling_region(&mut self, top: usize, bottom: usize) {
         if top >= bottom {
@@ -1973,6 +3454,27 @@ impl<T: EventListener> ansi::Handler for Term<T> {
         self.goto(Line(0), Column(0));
     }
 
+    #[inline]
+    fn set_left_right_margins(&mut self, left: usize, right: usize) {
+        // Without left and right margin mode this is SCOSC
+        if !self.mode.contains(TermMode::LEFT_RIGHT_MARGIN) {
+            self.save_cursor_position();
+            return;
+        }
+
+        let start = Column(left - 1);
+        let end = min(Column(right), self.grid.num_cols());
+        if start >= end {
+            debug!("Invalid left and right margins: ({};{})", left, right);
+            return;
+        }
+
+        trace!("Setting left and right margins: ({};{})", start, end);
+
+        self.horizontal_margins = start..end;
+        self.goto(Line(0), Column(0));
+    }
+
     #[inline]
     fn set_keypad_application_mode(&mut self) {
         trace!("Setting keypad application mode");
@@ -1997,35 +3499,285 @@ impl<T: EventListener> ansi::Handler for Term<T> {
         self.active_charset = index;
     }
 
+    #[inline]
+    fn single_shift(&mut self, index: CharsetIndex) {
+        trace!("Single shift to charset {:?}", index);
+        self.single_shift = Some(index);
+    }
+
     #[inline]
     fn set_cursor_style(&mut self, style: Option<CursorStyle>) {
         trace!("Setting cursor style {:?}", style);
         self.cursor_style = style;
     }
 
+    #[inline]
+    fn set_cursor_shape(&mut self, shape: CursorShape) {
+        trace!("Setting cursor shape {:?}", shape);
+        self.cursor_style.get_or_insert(self.default_cursor_style).shape = shape;
+    }
+
     #[inline]
     fn push_title(&mut self) {
         trace!("Pushing '{}' onto title stack", self.title);
 
         if self.title_stack.len() >= TITLE_STACK_MAX_DEPTH {
-            let removed = self.title_stack.remove(0);
+            let (removed, _) = self.title_stack.remove(0);
             trace!(
                 "Removing '{}' from bottom of title stack that exceeds its maximum depth",
                 removed
             );
         }
 
-        self.title_stack.push(self.title.clone());
+        self.title_stack.push((self.title.clone(), self.icon_name.clone()));
     }
 
     #[inline]
     fn pop_title(&mut self) {
         trace!("Attempting to pop title from stack...");
 
-        if let Some(popped) = self.title_stack.pop() {
-            trace!("Title '{}' popped from stack", popped);
-            self.set_title(&popped);
+        if let Some((title, icon_name)) = self.title_stack.pop() {
+            trace!("Title '{}' popped from stack", title);
+            self.set_icon_name(&icon_name);
+            self.set_title(&title);
+        }
+    }
+
+    #[inline]
+    fn report_window<W: io::Write>(&mut self, writer: &mut W, report: WindowReport) {
+        trace!("Reporting window property: {:?}", report);
+        let (lines, cols) = (self.grid.num_lines().0, self.grid.num_cols().0);
+        let cell_width = self.size_info.cell_width as usize;
+        let cell_height = self.size_info.cell_height as usize;
+
+        let response = match report {
+            WindowReport::TextAreaSizePixels => {
+                format!("\x1b[4;{};{}t", lines * cell_height, cols * cell_width)
+            },
+            WindowReport::CellSizePixels => format!("\x1b[6;{};{}t", cell_height, cell_width),
+            WindowReport::TextAreaSizeChars => format!("\x1b[8;{};{}t", lines, cols),
+            // The size of the screen is not known, so the text area is reported instead
+            WindowReport::ScreenSizeChars => format!("\x1b[9;{};{}t", lines, cols),
+            WindowReport::IconLabel | WindowReport::Title if !self.allow_title_reports => {
+                debug!("Ignoring window title report request");
+                return;
+            },
+            // Control characters are removed to avoid injecting escapes into the response
+            WindowReport::IconLabel | WindowReport::Title => {
+                let (kind, title) = match report {
+                    WindowReport::IconLabel => ('L', &self.icon_name),
+                    _ => ('l', &self.title),
+                };
+                let title: String = title.chars().filter(|c| !c.is_control()).collect();
+                format!("\x1b]{}{}\x1b\\", kind, title)
+            },
+        };
+
+        let _ = writer.write_all(response.as_bytes());
+    }
+
+    #[inline]
+    fn window_operation(&mut self, operation: WindowOperation) {
+        trace!("Requesting window operation: {:?}", operation);
+        self.event_proxy.send_event(Event::WindowOperation(operation));
+    }
+
+    #[inline]
+    fn insert_graphic(&mut self, image: Image) {
+        let cell_width = max(self.size_info.cell_width as usize, 1);
+        let cell_height = max(self.size_info.cell_height as usize, 1);
+        let cols = (image.width + cell_width - 1) / cell_width;
+        let lines = (image.height + cell_height - 1) / cell_height;
+        trace!("Inserting {}x{} graphic", image.width, image.height);
+
+        if self.graphic_cells.needs_sweep(self.total_cells()) {
+            self.sweep_tables();
+        }
+
+        let id = self.graphics.insert(image, cell_width, cell_height);
+        let start = self.cursor.point.col;
+        let end = min(start + cols, self.grid.num_cols());
+        let template = Cell { bg: self.cursor.template.bg, ..Cell::default() };
+
+        for line in 0..lines {
+            if line > 0 {
+                self.linefeed();
+            }
+
+            let row = &mut self.grid[self.cursor.point.line];
+            for (col, cell) in row[start..end].iter_mut().enumerate() {
+                let graphic = GraphicCell { id, col: col as u16, line: line as u16 };
+                *cell = template;
+                cell.graphic = GraphicCellId::new(self.graphic_cells.insert(graphic));
+            }
+        }
+
+        // Track the last line, to release the graphic once it has left the history
+        let line = self.grid.num_lines().0 - 1 - self.cursor.point.line.0;
+        self.graphics.set_anchor(id, Anchor { alt_screen: self.alt, line });
+
+        // Continue below the graphic
+        self.linefeed();
+        self.input_needs_wrap = false;
+    }
+
+    #[inline]
+    fn set_working_directory(&mut self, directory: Option<WorkingDirectory>) {
+        trace!("Setting working directory: {:?}", directory);
+
+        // Directories on this machine are always considered local, even with a hostname
+        let directory = directory.map(|mut directory| {
+            let local = tty::hostname();
+            if directory.host.as_ref().map_or(false, |host| local.as_ref() == Some(host)) {
+                directory.host = None;
+            }
+            directory
+        });
+
+        if directory != self.working_directory {
+            self.working_directory = directory.clone();
+            self.event_proxy.send_event(Event::WorkingDirectory(directory));
+        }
+    }
+
+    #[inline]
+    fn set_prompt_mark(&mut self, mark: PromptMark) {
+        trace!("Setting prompt mark: {:?}", mark);
+        let point = self.grid.visible_to_buffer(self.cursor.point);
+
+        match mark {
+            PromptMark::PromptStart => {
+                let row = &mut self.grid[point.line];
+                row.marks.insert(PromptMarks::PROMPT);
+                row.exit_status = None;
+            },
+            PromptMark::CommandStart => self.grid[point.line].marks.insert(PromptMarks::COMMAND),
+            PromptMark::OutputStart => self.grid[point.line].marks.insert(PromptMarks::OUTPUT),
+            PromptMark::CommandEnd(status) => {
+                let total_lines = self.grid.num_lines().0 + self.grid.scroll_limit();
+                let mut output = None;
+                let mut prompt = None;
+                for line in point.line..total_lines {
+                    let marks = self.grid[line].marks();
+                    if output.is_none() && marks.contains(PromptMarks::OUTPUT) {
+                        output = Some(line);
+                    }
+                    if marks.contains(PromptMarks::PROMPT) {
+                        prompt = Some(line);
+                        break;
+                    }
+                }
+
+                // Output terminated by a newline ends on the previous line
+                let end = if point.col == Column(0) { point.line + 1 } else { point.line };
+                if output.map_or(false, |output| output >= end) {
+                    self.grid[end].marks.insert(PromptMarks::OUTPUT_END);
+                }
+
+                if let Some(prompt) = prompt {
+                    self.grid[prompt].exit_status = status;
+                }
+            },
+        }
+    }
+
+    #[inline]
+    fn desktop_notification(&mut self, notification: Notification) {
+        trace!("Requesting desktop notification: {:?}", notification);
+        self.event_proxy.send_event(Event::Notification(notification));
+    }
+
+    #[inline]
+    fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
+        trace!("Setting hyperlink: {:?}", hyperlink);
+        if hyperlink.is_some() && self.hyperlinks.links.needs_sweep(self.total_cells()) {
+            self.sweep_tables();
+        }
+
+        self.cursor.template.hyperlink = hyperlink.and_then(|link| self.hyperlinks.insert(link));
+    }
+}
+
+/// Hyperlinks referenced by cells
+///
+/// Cells are `Copy`, so they only store the `HyperlinkId` of their hyperlink. Hyperlinks with an
+/// explicit `id` share the same ID, while every anonymous hyperlink gets its own, so only the
+/// cells printed while it was active are grouped together.
+#[derive(Default)]
+struct Hyperlinks {
+    links: Table<Hyperlink>,
+    ids: HashMap<Hyperlink, HyperlinkId>,
+}
+
+impl Hyperlinks {
+    fn insert(&mut self, hyperlink: Hyperlink) -> Option<HyperlinkId> {
+        if let Some(id) = self.ids.get(&hyperlink) {
+            return Some(*id);
+        }
+
+        let named = hyperlink.id.is_some();
+        let id = HyperlinkId::new(self.links.insert(hyperlink.clone()))?;
+        if named {
+            self.ids.insert(hyperlink, id);
+        }
+        Some(id)
+    }
+
+    fn get(&self, id: HyperlinkId) -> Option<&Hyperlink> {
+        self.links.get(id.index())
+    }
+
+    /// Remove all hyperlinks which are not marked as referenced
+    fn sweep(&mut self, referenced: &[bool]) {
+        self.links.sweep(referenced);
+
+        let links = &self.links;
+        self.ids.retain(|_, id| links.get(id.index()).is_some());
+    }
+
+    fn clear(&mut self) {
+        self.links.clear();
+        self.ids.clear();
+    }
+}
+
+/// Interned grapheme clusters
+///
+/// Cells only store the `ClusterId` of the characters following their first character, so
+/// clusters of any length fit into a cell. Identical clusters share the same ID.
+#[derive(Default)]
+struct Clusters {
+    clusters: Table<Arc<str>>,
+    ids: HashMap<Arc<str>, ClusterId>,
+}
+
+impl Clusters {
+    fn insert(&mut self, cluster: &str) -> Option<ClusterId> {
+        if let Some(id) = self.ids.get(cluster) {
+            return Some(*id);
         }
+
+        let cluster: Arc<str> = Arc::from(cluster);
+        let id = ClusterId::new(self.clusters.insert(cluster.clone()))?;
+        self.ids.insert(cluster, id);
+        Some(id)
+    }
+
+    fn get(&self, id: ClusterId) -> Option<&Arc<str>> {
+        self.clusters.get(id.index())
+    }
+
+    /// Remove all clusters which are not marked as referenced
+    fn sweep(&mut self, referenced: &[bool]) {
+        self.clusters.sweep(referenced);
+
+        let clusters = &self.clusters;
+        self.ids.retain(|_, id| clusters.get(id.index()).is_some());
+    }
+
+    fn clear(&mut self) {
+        self.clusters.clear();
+        self.ids.clear();
     }
 }
 
@@ -2065,19 +3817,29 @@ impl IndexMut<Column> for TabStops {
 
 #[cfg(test)]
 mod tests {
+    use std::io;
     use std::mem;
+    use std::path::PathBuf;
 
     use serde_json;
 
-    use crate::ansi::{self, CharsetIndex, Handler, StandardCharset};
+    use crate::ansi::{
+        self, Attr, AttributeChangeExtent, CharsetIndex, Color, CursorShape, CursorStyle, Handler,
+        Hyperlink, KeyboardModes, KeyboardModesApplyBehavior, LineSize, ModeState, NamedColor,
+        PromptMark, StandardCharset, StatusRequest, WindowReport, WorkingDirectory,
+    };
     use crate::clipboard::Clipboard;
-    use crate::config::MockConfig;
+    use crate::config::{AmbiguousWidth, MockConfig, WidthOverride};
     use crate::event::{Event, EventListener};
+    use crate::graphics::Image;
     use crate::grid::{Grid, Scroll};
     use crate::index::{Column, Line, Point, Side};
     use crate::selection::Selection;
     use crate::term::cell::{Cell, Flags};
-    use crate::term::{SizeInfo, Term};
+    use crate::term::color::Rgb;
+    use crate::term::search::Direction;
+    use crate::term::{SizeInfo, Term, TermMode};
+    use crate::tty;
 
     struct Mock;
     impl EventListener for Mock {
@@ -2217,7 +3979,7 @@ mod tests {
     }
 
     #[test]
-    fn clear_saved_lines() {
+    fn working_directory_on_local_host() {
         let size = SizeInfo {
             width: 21.0,
             height: 51.0,
@@ -2229,8 +3991,189 @@ mod tests {
         };
         let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
 
-        // Add one line of scrollback
-        term.grid.scroll_up(&(Line(0)..Line(1)), Line(1), &Cell::default());
+        let remote = WorkingDirectory {
+            host: Some(String::from("remote.invalid")),
+            path: PathBuf::from("/tmp"),
+        };
+        term.set_working_directory(Some(remote.clone()));
+        assert_eq!(term.working_directory(), Some(&remote));
+
+        let local = WorkingDirectory { host: tty::hostname(), path: PathBuf::from("/tmp") };
+        term.set_working_directory(Some(local));
+        assert_eq!(term.working_directory().map(|directory| &directory.host), Some(&None));
+    }
+
+    #[test]
+    fn prompt_marks() {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+        let buffer_line = |term: &Term<Mock>, line| {
+            term.grid().visible_to_buffer(Point::new(Line(line), Column(0))).line
+        };
+
+        // Command with two lines of output
+        term.set_prompt_mark(PromptMark::PromptStart);
+        term.input('$');
+        term.set_prompt_mark(PromptMark::CommandStart);
+        term.input('a');
+        term.carriage_return();
+        term.linefeed();
+        term.set_prompt_mark(PromptMark::OutputStart);
+        term.input('1');
+        term.carriage_return();
+        term.linefeed();
+        term.input('2');
+        term.carriage_return();
+        term.linefeed();
+        term.set_prompt_mark(PromptMark::CommandEnd(Some(2)));
+
+        // Command without output
+        term.set_prompt_mark(PromptMark::PromptStart);
+        term.input('$');
+        term.set_prompt_mark(PromptMark::CommandStart);
+        term.input('b');
+        term.carriage_return();
+        term.linefeed();
+        term.set_prompt_mark(PromptMark::OutputStart);
+        term.set_prompt_mark(PromptMark::CommandEnd(Some(0)));
+
+        let first_prompt = buffer_line(&term, 0);
+        let second_prompt = buffer_line(&term, 3);
+        assert_eq!(term.grid()[first_prompt].exit_status(), Some(2));
+        assert_eq!(term.grid()[second_prompt].exit_status(), Some(0));
+        assert_eq!(term.last_command_output(), Some(buffer_line(&term, 2)..=buffer_line(&term, 1)));
+
+        assert_eq!(term.prompt_line(Direction::Forward), Some(second_prompt));
+        assert_eq!(term.prompt_line(Direction::Backward), None);
+    }
+
+    #[test]
+    fn shrink_reflow_keeps_command_output() {
+        let mut size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+
+        term.set_prompt_mark(PromptMark::PromptStart);
+        term.input('$');
+        term.set_prompt_mark(PromptMark::CommandStart);
+        term.input('a');
+        term.carriage_return();
+        term.linefeed();
+        term.set_prompt_mark(PromptMark::OutputStart);
+        for c in "123456".chars() {
+            term.input(c);
+        }
+        term.carriage_return();
+        term.linefeed();
+        term.set_prompt_mark(PromptMark::CommandEnd(Some(0)));
+        term.set_prompt_mark(PromptMark::PromptStart);
+        term.input('$');
+
+        // The output line is split in two, both halves are part of the output
+        size.width = 9.0;
+        term.resize(&size);
+
+        let output = term.last_command_output().unwrap();
+        assert_eq!(output.end() - output.start(), 1);
+        assert_eq!(term.grid()[*output.end()][Column(0)].c, '1');
+        assert_eq!(term.grid()[*output.start()][Column(0)].c, '4');
+    }
+
+    #[test]
+    fn input_single_shift() {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+        term.configure_charset(CharsetIndex::G2, StandardCharset::SpecialCharacterAndLineDrawing);
+        term.single_shift(CharsetIndex::G2);
+        term.input('q');
+        term.input('q');
+
+        assert_eq!(term.grid()[Line(0)][Column(0)].c, '─');
+        assert_eq!(term.grid()[Line(0)][Column(1)].c, 'q');
+    }
+
+    #[test]
+    fn input_national_and_dec_charsets() {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+
+        let expected = [
+            (StandardCharset::DecSupplementalGraphics, "(W+}~", "¤Œ«ÿ~"),
+            (StandardCharset::DecTechnical, "!<a{R", "⎷≤α←R"),
+            (StandardCharset::British, "#a", "£a"),
+            (StandardCharset::Dutch, "@[\\", "¾ĳ½"),
+            (StandardCharset::Finnish, "[`}", "Äéå"),
+            (StandardCharset::French, "@]{", "à§é"),
+            (StandardCharset::FrenchCanadian, "[^~", "âîû"),
+            (StandardCharset::German, "@{~", "§äß"),
+            (StandardCharset::Italian, "]`~", "éùì"),
+            (StandardCharset::NorwegianDanish, "[\\|", "ÆØø"),
+            (StandardCharset::Portuguese, "[\\}", "ÃÇõ"),
+            (StandardCharset::Spanish, "[]|", "¡¿ñ"),
+            (StandardCharset::Swedish, "@[~", "ÉÄü"),
+            (StandardCharset::Swiss, "#_{", "ùèä"),
+        ];
+
+        for (charset, input, output) in expected.iter() {
+            term.configure_charset(CharsetIndex::G0, *charset);
+            term.goto(Line(0), Column(0));
+            for c in input.chars() {
+                term.input(c);
+            }
+
+            let line: String = (0..output.chars().count())
+                .map(|col| term.grid()[Line(0)][Column(col)].c)
+                .collect();
+            assert_eq!(&line, output);
+        }
+    }
+
+    #[test]
+    fn clear_saved_lines() {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+
+        // Add one line of scrollback
+        term.grid.scroll_up(&(Line(0)..Line(1)), Line(1), &Cell::default());
 
         // Clear the history
         term.clear_screen(ansi::ClearMode::Saved);
@@ -2260,18 +4203,27 @@ mod tests {
             assert_eq!(term.title, "Test");
         }
 
-        // Title can be pushed onto stack
+        // Icon name is separate from the title
+        {
+            term.set_icon_name("Icon");
+            assert_eq!(term.icon_name, "Icon");
+            assert_eq!(term.title, "Test");
+        }
+
+        // Title and icon name can be pushed onto stack
         {
             term.push_title();
             term.title = "Next".to_string();
+            term.icon_name = "Next icon".to_string();
             assert_eq!(term.title, "Next");
-            assert_eq!(term.title_stack.get(0).unwrap(), "Test");
+            assert_eq!(term.title_stack[0], ("Test".to_string(), "Icon".to_string()));
         }
 
-        // Title can be popped from stack and set as the window title
+        // Title and icon name can be popped from stack and set as the window title
         {
             term.pop_title();
             assert_eq!(term.title, "Test");
+            assert_eq!(term.icon_name, "Icon");
             assert!(term.title_stack.is_empty());
         }
 
@@ -2288,9 +4240,657 @@ mod tests {
             term.push_title();
             term.reset_state();
             assert_eq!(term.title, "Alacritty");
+            assert_eq!(term.icon_name, "");
             assert!(term.title_stack.is_empty());
         }
     }
+
+    #[test]
+    fn hyperlinks_are_interned() {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+
+        let link = Hyperlink { id: Some(String::from("a")), uri: String::from("file:///a") };
+        term.set_hyperlink(Some(link.clone()));
+        term.input('a');
+        term.set_hyperlink(None);
+        term.input(' ');
+        term.set_hyperlink(Some(link.clone()));
+        term.input('b');
+
+        let first = term.grid[Line(0)][Column(0)].hyperlink;
+        assert!(first.is_some());
+        assert_eq!(term.grid[Line(0)][Column(1)].hyperlink, None);
+        assert_eq!(term.grid[Line(0)][Column(2)].hyperlink, first);
+        assert_eq!(term.hyperlink(first.unwrap()), Some(&link));
+    }
+
+    #[test]
+    fn anonymous_hyperlinks_are_distinct() {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+
+        let link = Hyperlink { id: None, uri: String::from("file:///a") };
+        term.set_hyperlink(Some(link.clone()));
+        term.input('a');
+        term.input('b');
+        term.set_hyperlink(None);
+        term.input(' ');
+        term.set_hyperlink(Some(link.clone()));
+        term.input('c');
+
+        let first = term.grid[Line(0)][Column(0)].hyperlink;
+        let second = term.grid[Line(0)][Column(3)].hyperlink;
+        assert_eq!(term.grid[Line(0)][Column(1)].hyperlink, first);
+        assert_ne!(first, second);
+        assert_eq!(term.hyperlink(second.unwrap()), Some(&link));
+    }
+
+    #[test]
+    fn unreferenced_hyperlinks_are_pruned() {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+
+        let link = Hyperlink { id: Some(String::from("a")), uri: String::from("file:///a") };
+        term.set_hyperlink(Some(link));
+        term.input('a');
+        term.set_hyperlink(None);
+        let first = term.grid[Line(0)][Column(0)].hyperlink.unwrap();
+
+        // Overwritten hyperlinks are dropped once the tables are swept
+        term.carriage_return();
+        term.input('b');
+        term.sweep_tables();
+        assert_eq!(term.hyperlink(first), None);
+        assert_eq!(term.hyperlinks.links.len(), 0);
+
+        // Hyperlinks of the cursor are kept alive
+        let link = Hyperlink { id: None, uri: String::from("file:///b") };
+        term.set_hyperlink(Some(link.clone()));
+        term.sweep_tables();
+        let id = term.cursor.template.hyperlink.unwrap();
+        assert_eq!(term.hyperlink(id), Some(&link));
+
+        term.reset_state();
+        assert_eq!(term.hyperlinks.links.len(), 0);
+    }
+
+    #[test]
+    fn linefeed_outside_horizontal_margins() {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+        term.set_mode(ansi::Mode::LeftRightMargin);
+        term.set_left_right_margins(3, 5);
+
+        term.goto(Line(16), Column(2));
+        term.input('a');
+
+        // The bottom margin does not scroll while the cursor is left of the margins
+        term.goto(Line(16), Column(0));
+        term.linefeed();
+        assert_eq!(term.cursor.point.line, Line(16));
+        assert_eq!(term.grid[Line(16)][Column(2)].c, 'a');
+
+        // Right of the margins the top margin does not scroll either
+        term.goto(Line(0), Column(6));
+        term.reverse_index();
+        assert_eq!(term.cursor.point.line, Line(0));
+        assert_eq!(term.grid[Line(16)][Column(2)].c, 'a');
+
+        term.goto(Line(16), Column(2));
+        term.linefeed();
+        assert_eq!(term.cursor.point.line, Line(16));
+        assert_eq!(term.grid[Line(15)][Column(2)].c, 'a');
+    }
+
+    #[test]
+    fn graphics_are_anchored_to_cells() {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+
+        let image = Image { width: 4, height: 6, pixels: vec![255; 4 * 6 * 4] };
+        term.goto(Line(0), Column(1));
+        term.insert_graphic(image);
+
+        // Graphic covers 2x2 cells and the cursor is moved below it
+        assert_eq!(term.cursor.point, Point::new(Line(2), Column(1)));
+        let graphic = term.grid[Line(1)][Column(2)].graphic.unwrap();
+        let graphic_cell = term.graphic_cells.get(graphic.index()).unwrap();
+        assert_eq!((graphic_cell.col, graphic_cell.line), (1, 1));
+        assert_eq!(term.grid[Line(0)][Column(0)].graphic, None);
+        assert_eq!(term.grid[Line(0)][Column(3)].graphic, None);
+        assert_eq!(term.graphics_mut().take_updates().added.len(), 1);
+
+        // Graphic scrolls with the text
+        term.grid.scroll_up(&(Line(0)..Line(17)), Line(1), &Cell::default());
+        assert_eq!(term.grid[Line(0)][Column(2)].graphic, Some(graphic));
+
+        // Graphic is dropped once all of its cells have been overwritten or left the history
+        term.goto(Line(0), Column(1));
+        term.input('a');
+        term.input('b');
+        term.clear_screen(ansi::ClearMode::Below);
+        assert!(!term.graphics_mut().is_empty());
+        term.clear_screen(ansi::ClearMode::Saved);
+        assert!(term.graphics_mut().is_empty());
+        assert_eq!(term.graphics_mut().take_updates().removed, vec![graphic_cell.id]);
+        assert!(term.graphic_cells.is_empty());
+    }
+
+    #[test]
+    fn graphics_are_released_after_leaving_history() {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+        term.grid.update_history(3, &Cell::default());
+
+        let image = Image { width: 3, height: 6, pixels: vec![255; 3 * 6 * 4] };
+        term.insert_graphic(image);
+        let id = term.graphics_mut().take_updates().added[0].id;
+
+        // Last line of the graphic is still part of the history
+        term.goto(Line(16), Column(0));
+        for _ in 0..4 {
+            term.linefeed();
+        }
+        assert!(!term.graphics_mut().is_empty());
+
+        term.linefeed();
+        assert!(term.graphics_mut().is_empty());
+        assert_eq!(term.graphics_mut().take_updates().removed, vec![id]);
+        assert!(term.graphic_cells.is_empty());
+
+        // Resetting the terminal releases all graphics right away
+        let image = Image { width: 3, height: 6, pixels: vec![255; 3 * 6 * 4] };
+        term.insert_graphic(image);
+        let id = term.graphics_mut().take_updates().added[0].id;
+
+        term.reset_state();
+        assert!(term.graphics_mut().is_empty());
+        assert_eq!(term.graphics_mut().take_updates().removed, vec![id]);
+        assert!(term.graphic_cells.is_empty());
+    }
+
+    #[test]
+    fn keyboard_modes_per_screen() {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+
+        term.push_keyboard_mode(KeyboardModes::DISAMBIGUATE_ESC_CODES);
+        assert!(term.mode().contains(TermMode::DISAMBIGUATE_ESC_CODES));
+
+        // The alternate screen starts without any keyboard modes
+        term.set_mode(ansi::Mode::SwapScreenAndSetRestoreCursor);
+        assert!(!term.mode().intersects(TermMode::KITTY_KEYBOARD_PROTOCOL));
+
+        term.push_keyboard_mode(KeyboardModes::REPORT_ALL_KEYS_AS_ESC);
+        term.set_keyboard_mode(
+            KeyboardModes::REPORT_EVENT_TYPES,
+            KeyboardModesApplyBehavior::Union,
+        );
+        let mut report = Vec::new();
+        term.report_keyboard_mode(&mut report);
+        assert_eq!(report, b"\x1b[?10u");
+
+        term.unset_mode(ansi::Mode::SwapScreenAndSetRestoreCursor);
+        assert!(term.mode().contains(TermMode::DISAMBIGUATE_ESC_CODES));
+        assert!(!term.mode().contains(TermMode::REPORT_ALL_KEYS_AS_ESC));
+
+        term.pop_keyboard_modes(1);
+        assert!(!term.mode().intersects(TermMode::KITTY_KEYBOARD_PROTOCOL));
+    }
+
+    #[test]
+    fn report_mode_state() {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+
+        assert_eq!(term.mode_state(&ansi::Mode::SyncUpdate), ModeState::Reset);
+        assert_eq!(term.mode_state(&ansi::Mode::LineWrap), ModeState::Set);
+        assert_eq!(term.mode_state(&ansi::Mode::DECCOLM), ModeState::PermanentlyReset);
+
+        term.set_mode(ansi::Mode::SyncUpdate);
+        assert_eq!(term.mode_state(&ansi::Mode::SyncUpdate), ModeState::Set);
+
+        term.unset_mode(ansi::Mode::LineWrap);
+        assert_eq!(term.mode_state(&ansi::Mode::LineWrap), ModeState::Reset);
+    }
+
+    #[test]
+    fn mouse_encodings_are_exclusive() {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+
+        term.set_mode(ansi::Mode::SgrMouse);
+        term.set_mode(ansi::Mode::SgrPixelMouse);
+        assert_eq!(*term.mode() & TermMode::MOUSE_ENCODING, TermMode::SGR_PIXEL_MOUSE);
+        assert_eq!(term.mode_state(&ansi::Mode::SgrMouse), ModeState::Reset);
+
+        term.set_mode(ansi::Mode::UrxvtMouse);
+        assert_eq!(*term.mode() & TermMode::MOUSE_ENCODING, TermMode::URXVT_MOUSE);
+        assert_eq!(term.mode_state(&ansi::Mode::UrxvtMouse), ModeState::Set);
+
+        term.unset_mode(ansi::Mode::UrxvtMouse);
+        assert!(!term.mode().intersects(TermMode::MOUSE_ENCODING));
+    }
+
+    #[test]
+    fn report_status_strings() {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+
+        assert_eq!(term.status_string(StatusRequest::Sgr), Some(String::from("0m")));
+        assert_eq!(term.status_string(StatusRequest::ScrollingRegion), Some(String::from("1;17r")));
+        assert_eq!(term.status_string(StatusRequest::CursorStyle), Some(String::from("2 q")));
+
+        term.terminal_attribute(Attr::Bold);
+        term.terminal_attribute(Attr::Undercurl);
+        term.terminal_attribute(Attr::Foreground(Color::Named(NamedColor::BrightRed)));
+        term.terminal_attribute(Attr::Background(Color::Indexed(100)));
+        term.terminal_attribute(Attr::UnderlineColor(Some(Color::Spec(Rgb { r: 1, g: 2, b: 3 }))));
+        term.set_scrolling_region(2, 10);
+        term.set_cursor_style(Some(CursorStyle { shape: CursorShape::Beam, blinking: true }));
+
+        assert_eq!(
+            term.status_string(StatusRequest::Sgr),
+            Some(String::from("0;1;4:3;91;48;5;100;58;2;1;2;3m"))
+        );
+        assert_eq!(term.status_string(StatusRequest::ScrollingRegion), Some(String::from("2;10r")));
+        assert_eq!(term.status_string(StatusRequest::CursorStyle), Some(String::from("5 q")));
+    }
+
+    #[test]
+    fn sgr_status_round_trip() {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+
+        term.terminal_attribute(Attr::Bold);
+        term.terminal_attribute(Attr::Italic);
+        term.terminal_attribute(Attr::DoubleUnderline);
+        term.terminal_attribute(Attr::Foreground(Color::Named(NamedColor::Green)));
+        term.terminal_attribute(Attr::Background(Color::Spec(Rgb { r: 4, g: 5, b: 6 })));
+        term.terminal_attribute(Attr::UnderlineColor(Some(Color::Indexed(200))));
+        let template = term.cursor.template;
+
+        // Restoring the reported state must reproduce the same attributes
+        let sgr = format!("\x1b[{}", term.status_string(StatusRequest::Sgr).unwrap());
+        term.terminal_attribute(Attr::Reset);
+        let mut parser = ansi::Processor::new();
+        for byte in sgr.bytes() {
+            parser.advance(&mut term, byte, &mut io::sink());
+        }
+
+        assert_eq!(term.cursor.template, template);
+        assert!(term.cursor.template.flags.contains(Flags::BOLD | Flags::DOUBLE_UNDERLINE));
+    }
+
+    #[test]
+    fn report_window_properties() {
+        let size = SizeInfo {
+            width: 25.0,
+            height: 55.0,
+            cell_width: 3.0,
+            cell_height: 5.0,
+            padding_x: 2.0,
+            padding_y: 2.0,
+            dpr: 1.0,
+        };
+        let mut config = MockConfig::default();
+        let mut term = Term::new(&config, &size, Clipboard::new_nop(), Mock);
+        term.set_title("vim\x1b[31m");
+        term.set_icon_name("vi\x1b[31m");
+
+        let report = |term: &mut Term<Mock>, report| {
+            let mut response = Vec::new();
+            term.report_window(&mut response, report);
+            String::from_utf8(response).unwrap()
+        };
+
+        assert_eq!(report(&mut term, WindowReport::TextAreaSizePixels), "\x1b[4;50;21t");
+        assert_eq!(report(&mut term, WindowReport::CellSizePixels), "\x1b[6;5;3t");
+        assert_eq!(report(&mut term, WindowReport::TextAreaSizeChars), "\x1b[8;10;7t");
+        assert_eq!(report(&mut term, WindowReport::ScreenSizeChars), "\x1b[9;10;7t");
+        assert_eq!(report(&mut term, WindowReport::Title), "");
+
+        config.window.allow_title_reports = true;
+        term.update_config(&config);
+        assert_eq!(report(&mut term, WindowReport::Title), "\x1b]lvim[31m\x1b\\");
+        assert_eq!(report(&mut term, WindowReport::IconLabel), "\x1b]Lvi[31m\x1b\\");
+    }
+
+    #[test]
+    fn rectangle_operations() {
+        let size = SizeInfo {
+            width: 15.0,
+            height: 9.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+        for (line, text) in ["abcde", "fghij", "klmno"].iter().enumerate() {
+            term.goto(Line(line), Column(0));
+            text.chars().for_each(|c| term.input(c));
+        }
+
+        let area = |top, left, bottom, right| ansi::Rectangle {
+            top: Line(top),
+            left: Column(left),
+            bottom: Some(Line(bottom)),
+            right: Some(Column(right)),
+        };
+        let text = |term: &Term<Mock>, line| -> String {
+            term.grid[Line(line)][..].iter().map(|cell| cell.c).collect()
+        };
+
+        // Overlapping source and destination
+        term.copy_rectangle(area(0, 0, 1, 1), Line(1), Column(1));
+        assert_eq!(text(&term, 1), "fabij");
+        assert_eq!(text(&term, 2), "kfgno");
+
+        term.fill_rectangle('x', ansi::Rectangle { bottom: None, right: None, ..area(2, 3, 0, 0) });
+        assert_eq!(text(&term, 2), "kfgxx");
+
+        term.erase_rectangle(area(0, 0, 0, 0));
+        assert_eq!(text(&term, 0), " bcde");
+
+        let mut checksum = Vec::new();
+        term.report_rectangle_checksum(&mut checksum, 7, area(0, 1, 0, 2));
+        assert_eq!(checksum, b"\x1bP7!~FF3B\x1b\\");
+
+        let bold = |term: &Term<Mock>, line| -> Vec<bool> {
+            term.grid[Line(line)][..].iter().map(|cell| cell.flags.contains(Flags::BOLD)).collect()
+        };
+
+        term.change_rectangle_attributes(area(0, 3, 1, 1), &[Attr::Bold]);
+        assert_eq!(bold(&term, 0), vec![false, false, false, true, true]);
+        assert_eq!(bold(&term, 1), vec![true, true, false, false, false]);
+
+        term.set_attribute_change_extent(AttributeChangeExtent::Rectangle);
+        term.reverse_rectangle_attributes(area(0, 1, 1, 3), &[Attr::Bold]);
+        assert_eq!(bold(&term, 0), vec![false, true, true, false, true]);
+        assert_eq!(bold(&term, 1), vec![true, false, true, true, false]);
+
+        // Rectangles are relative to the origin
+        term.set_scrolling_region(2, 3);
+        term.set_mode(ansi::Mode::Origin);
+        term.fill_rectangle('y', area(0, 0, 5, 0));
+        assert_eq!(text(&term, 0), " bcde");
+        assert_eq!(text(&term, 1), "yabij");
+        assert_eq!(text(&term, 2), "yfgxx");
+    }
+
+    #[test]
+    fn grapheme_clusters() {
+        let size = SizeInfo {
+            width: 30.0,
+            height: 9.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+
+        // Combining characters are not limited to a fixed number per cell
+        let accents = "a\u{301}\u{302}\u{303}\u{304}\u{305}\u{306}\u{307}";
+        accents.chars().for_each(|c| term.input(c));
+        assert_eq!(term.cursor.point.col, Column(1));
+        assert_eq!(term.cluster(&term.grid[Line(0)][Column(0)]), &accents[1..]);
+
+        // Flags are wider than their regional indicators
+        "\u{1F1FA}\u{1F1F8}".chars().for_each(|c| term.input(c));
+        assert_eq!(term.cursor.point.col, Column(3));
+        assert!(term.grid[Line(0)][Column(1)].flags.contains(Flags::WIDE_CHAR));
+        assert!(term.grid[Line(0)][Column(2)].flags.contains(Flags::WIDE_CHAR_SPACER));
+
+        // Emoji joined into a single glyph only advance the cursor once
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        family.chars().for_each(|c| term.input(c));
+        assert_eq!(term.cursor.point.col, Column(5));
+
+        term.input('b');
+        let text = format!("{}\u{1F1FA}\u{1F1F8}{}b\n", accents, family);
+        assert_eq!(term.line_to_string(2, Column(0)..Column(9)), text);
+    }
+
+    #[test]
+    fn unreferenced_clusters_are_pruned() {
+        let size = SizeInfo {
+            width: 30.0,
+            height: 9.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+
+        // Every extension of a cluster is interned, but only the last one is referenced
+        let accents = "a\u{301}\u{302}";
+        accents.chars().for_each(|c| term.input(c));
+        assert_eq!(term.clusters.clusters.len(), 2);
+
+        term.sweep_tables();
+        assert_eq!(term.clusters.clusters.len(), 1);
+        assert_eq!(term.cluster(&term.grid[Line(0)][Column(0)]), &accents[1..]);
+
+        // Overwritten clusters are dropped once the tables are swept
+        term.carriage_return();
+        term.input('b');
+        term.sweep_tables();
+        assert_eq!(term.clusters.clusters.len(), 0);
+        assert!(term.clusters.ids.is_empty());
+
+        accents.chars().for_each(|c| term.input(c));
+        term.reset_state();
+        assert_eq!(term.clusters.clusters.len(), 0);
+    }
+
+    #[test]
+    fn width_policy() {
+        let size = SizeInfo {
+            width: 12.0,
+            height: 9.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut config = MockConfig::default();
+        config.unicode.ambiguous_width = AmbiguousWidth::Wide;
+        let mut term = Term::new(&config, &size, Clipboard::new_nop(), Mock);
+
+        // Ambiguous-width characters occupy two cells
+        term.input('\u{2160}');
+        assert_eq!(term.cursor.point.col, Column(2));
+        assert!(term.grid[Line(0)][Column(0)].flags.contains(Flags::WIDE_CHAR));
+
+        // Wide characters which don't fit are wrapped to the next line
+        term.input('a');
+        term.input('\u{4E00}');
+        assert!(term.grid[Line(0)][Column(3)].flags.contains(Flags::LEADING_WIDE_CHAR_SPACER));
+        assert!(term.grid[Line(1)][Column(0)].flags.contains(Flags::WIDE_CHAR));
+        assert_eq!(term.cursor.point, Point::new(Line(1), Column(2)));
+        let text = term.bounds_to_string(Point::new(2, Column(0)), Point::new(1, Column(3)));
+        assert_eq!(text, "\u{2160}a\u{4E00}\n");
+
+        // Width overrides take precedence over the Unicode tables
+        config.unicode.width_overrides =
+            vec![WidthOverride { start: '\u{4E00}', end: '\u{4E00}', width: 1 }];
+        term.update_config(&config);
+        term.input('\u{4E00}');
+        assert_eq!(term.cursor.point, Point::new(Line(1), Column(3)));
+    }
+
+    #[test]
+    fn double_width_lines() {
+        let size = SizeInfo {
+            width: 18.0,
+            height: 9.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let config = MockConfig::default();
+        let mut term = Term::new(&config, &size, Clipboard::new_nop(), Mock);
+        "abcdef".chars().for_each(|c| term.input(c));
+
+        // Characters beyond the first half of the line are cleared
+        term.goto(Line(0), Column(4));
+        term.set_line_size(LineSize::DoubleWidth);
+        assert_eq!(term.cursor.point.col, Column(2));
+        assert_eq!(term.grid[Line(0)][Column(3)].c, ' ');
+
+        // Input wraps at the middle of the line
+        term.goto(Line(0), Column(0));
+        "ghij".chars().for_each(|c| term.input(c));
+        assert_eq!(term.cursor.point, Point::new(Line(1), Column(1)));
+        assert_eq!(term.line_to_string(2, Column(0)..Column(5)), "ghi");
+
+        let columns: Vec<Column> = term
+            .renderable_cells(&config)
+            .filter(|cell| cell.line == Line(0))
+            .map(|cell| cell.column)
+            .collect();
+        assert_eq!(columns, vec![Column(0), Column(2), Column(4)]);
+
+        let point = term.screen_to_cell(Point::new(Line(0), Column(5)));
+        assert_eq!(point, Point::new(Line(0), Column(2)));
+
+        term.clear_screen(ansi::ClearMode::All);
+        assert_eq!(term.line_size(Line(0)), LineSize::Normal);
+    }
+
+    #[test]
+    fn blinking_text_and_cursor() {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let config = MockConfig::default();
+        let mut term = Term::new(&config, &size, Clipboard::new_nop(), Mock);
+        assert!(!term.has_blinking_cursor());
+
+        term.set_mode(ansi::Mode::BlinkingCursor);
+        assert!(term.has_blinking_cursor());
+        assert_eq!(term.mode_state(&ansi::Mode::BlinkingCursor), ModeState::Set);
+
+        term.unset_mode(ansi::Mode::BlinkingCursor);
+        assert!(!term.has_blinking_cursor());
+        assert_eq!(term.mode_state(&ansi::Mode::BlinkingCursor), ModeState::Reset);
+
+        term.terminal_attribute(Attr::BlinkSlow);
+        term.input('a');
+        assert!(!term.has_blinking_cursor());
+
+        let cell = term.renderable_cells(&config).find(|cell| cell.column == Column(0)).unwrap();
+        assert!(cell.flags.contains(Flags::BLINK));
+        assert!(!cell.flags.contains(Flags::HIDDEN));
+
+        term.blink_hidden = true;
+        let cell = term.renderable_cells(&config).find(|cell| cell.column == Column(0)).unwrap();
+        assert!(cell.flags.contains(Flags::BLINK | Flags::HIDDEN));
+    }
 }
 
 #[cfg(all(test, feature = "bench"))]
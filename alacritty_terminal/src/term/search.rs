@@ -0,0 +1,363 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Regex search through the terminal grid and scrollback
+use std::cmp::{min, Reverse};
+use std::ops::RangeInclusive;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::event::EventListener;
+use crate::index::{Column, Point, Side};
+use crate::selection::Selection;
+use crate::term::cell::Flags;
+use crate::term::{Term, TermMode};
+use crate::vi_mode;
+
+/// Buffer coordinates of the first and last cell of a match
+pub type Match = RangeInclusive<Point<usize>>;
+
+/// Direction of a search through the grid
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Direction {
+    /// Towards the bottom of the scrollback history
+    Forward,
+    /// Towards the top of the scrollback history
+    Backward,
+}
+
+impl Default for Direction {
+    fn default() -> Direction {
+        Direction::Forward
+    }
+}
+
+impl Direction {
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Forward => Direction::Backward,
+            Direction::Backward => Direction::Forward,
+        }
+    }
+}
+
+/// Compiled regex for searching the grid
+#[derive(Debug, Clone)]
+pub struct RegexSearch {
+    regex: Regex,
+}
+
+impl RegexSearch {
+    /// Compile a search pattern
+    ///
+    /// Patterns without uppercase characters are matched case-insensitively.
+    pub fn new(pattern: &str) -> Result<RegexSearch, regex::Error> {
+        let case_insensitive = !pattern.chars().any(char::is_uppercase);
+        let regex = RegexBuilder::new(pattern).case_insensitive(case_insensitive).build()?;
+        Ok(RegexSearch { regex })
+    }
+}
+
+impl<T> Term<T> {
+    /// Set the active regex search
+    pub fn set_regex_search(&mut self, search: Option<RegexSearch>) {
+        self.regex_search = search;
+        self.search_matches = None;
+        self.dirty = true;
+    }
+
+    /// Search the visible part of the grid for matches of the active regex search
+    ///
+    /// The matches are kept until the content of the grid or the viewport changes, so redraws
+    /// don't have to run the regex again.
+    pub fn update_search_matches(&mut self) {
+        let display_offset = self.grid.display_offset();
+        if self.search_matches.as_ref().map_or(false, |(offset, _)| *offset == display_offset) {
+            return;
+        }
+
+        self.search_matches = Some((display_offset, self.visible_search_matches()));
+    }
+
+    /// Drop the visible matches after the content of the grid has changed
+    pub fn invalidate_search_matches(&mut self) {
+        self.search_matches = None;
+    }
+
+    /// Check if a regex search is active
+    pub fn regex_search_active(&self) -> bool {
+        self.regex_search.is_some()
+    }
+
+    /// Jump to the next match of the active regex search
+    ///
+    /// The search starts right next to the origin and wraps around at the end of the scrollback
+    /// history. A match is selected and scrolled into view; in vi mode the vi cursor is moved to
+    /// the start of the match.
+    pub fn search_next(&mut self, origin: Point<usize>, direction: Direction) -> Option<Match>
+    where
+        T: EventListener,
+    {
+        let regex_match = self.find_next(origin, direction)?;
+
+        self.scroll_to_point(*regex_match.start());
+        if self.mode.contains(TermMode::VI) {
+            self.vi_mode_cursor.point = *regex_match.start();
+        }
+
+        let mut selection = Selection::simple(*regex_match.start(), Side::Left);
+        selection.update(*regex_match.end(), Side::Right);
+        self.grid.selection = Some(selection);
+
+        self.dirty = true;
+
+        Some(regex_match)
+    }
+
+    /// Find the next match of the active regex search
+    fn find_next(&self, origin: Point<usize>, direction: Direction) -> Option<Match> {
+        let regex = &self.regex_search.as_ref()?.regex;
+        let topmost_line = vi_mode::topmost_line(self);
+
+        let origin = Point::new(min(origin.line, topmost_line), origin.col);
+        let (mut start, mut end) = self.logical_line(origin.line);
+        let origin_start = start;
+        let mut first_line = true;
+
+        loop {
+            let mut matches = self.line_matches(regex, start, end).into_iter();
+            let regex_match = match (direction, first_line) {
+                (Direction::Forward, true) => matches.find(|m| is_before(origin, *m.start())),
+                (Direction::Backward, true) => {
+                    matches.filter(|m| is_before(*m.start(), origin)).last()
+                },
+                (Direction::Forward, false) => matches.next(),
+                (Direction::Backward, false) => matches.last(),
+            };
+
+            // Stop after wrapping around to the origin
+            if regex_match.is_some() || (!first_line && start == origin_start) {
+                return regex_match;
+            }
+            first_line = false;
+
+            let next_line = match direction {
+                Direction::Forward if end == 0 => topmost_line,
+                Direction::Forward => end - 1,
+                Direction::Backward if start == topmost_line => 0,
+                Direction::Backward => start + 1,
+            };
+
+            let (next_start, next_end) = self.logical_line(next_line);
+            start = next_start;
+            end = next_end;
+        }
+    }
+
+    /// All matches of the active regex search which are at least partially visible
+    ///
+    /// The matches are sorted in reading order.
+    pub fn visible_search_matches(&self) -> Vec<Match> {
+        let regex = match &self.regex_search {
+            Some(search) => &search.regex,
+            None => return Vec::new(),
+        };
+
+        let display_offset = self.grid.display_offset();
+        let mut line = display_offset + self.grid.num_lines().0 - 1;
+        let mut matches = Vec::new();
+
+        loop {
+            let (start, end) = self.logical_line(line);
+            matches.append(&mut self.line_matches(regex, start, end));
+
+            if end <= display_offset {
+                break;
+            }
+
+            line = end - 1;
+        }
+
+        matches.sort_by_key(|m| (Reverse(m.start().line), m.start().col));
+
+        matches
+    }
+
+    /// Buffer lines of the first and last row of the line wrapped over the specified row
+    fn logical_line(&self, line: usize) -> (usize, usize) {
+        let topmost_line = vi_mode::topmost_line(self);
+        let last_col = self.grid.num_cols() - 1;
+        let is_wrapped = |line: usize| self.grid[line][last_col].flags.contains(Flags::WRAPLINE);
+
+        let mut start = line;
+        while start < topmost_line && is_wrapped(start + 1) {
+            start += 1;
+        }
+
+        let mut end = line;
+        while end > 0 && is_wrapped(end) {
+            end -= 1;
+        }
+
+        (start, end)
+    }
+
+    /// All non-empty regex matches in the logical line between `start` and `end`
+    fn line_matches(&self, regex: &Regex, start: usize, end: usize) -> Vec<Match> {
+        let mut text = String::new();
+        let mut offsets = Vec::new();
+
+        for line in (end..=start).rev() {
+            let row = &self.grid[line];
+            for col in 0..self.grid.num_cols().0 {
+                let cell = &row[Column(col)];
+                if cell.flags.intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+                {
+                    continue;
+                }
+
+                offsets.push((text.len(), Point::new(line, Column(col))));
+
+                text.push(cell.c);
+                text.push_str(self.cluster(cell));
+            }
+        }
+
+        // Ignore the empty cells at the end of the line
+        let len = text.trim_end_matches(' ').len();
+        text.truncate(len);
+
+        // Point of the cell containing the character at a byte offset
+        let point = |offset: usize| match offsets.binary_search_by_key(&offset, |&(o, _)| o) {
+            Ok(index) => offsets[index].1,
+            Err(index) => offsets[index - 1].1,
+        };
+
+        regex
+            .find_iter(&text)
+            .filter(|m| m.start() != m.end())
+            .map(|m| point(m.start())..=point(m.end() - 1))
+            .collect()
+    }
+}
+
+/// Check if a point precedes another one in reading order
+#[inline]
+pub fn is_before(point: Point<usize>, other: Point<usize>) -> bool {
+    point.line > other.line || (point.line == other.line && point.col < other.col)
+}
+
+/// Check if a point is part of a match
+#[inline]
+pub fn match_contains(regex_match: &Match, point: Point<usize>) -> bool {
+    !is_before(point, *regex_match.start()) && !is_before(*regex_match.end(), point)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::MockConfig;
+    use crate::index::{Column, Point};
+    use crate::term::cell::Flags;
+    use crate::term::test::{mock_term, Mock};
+    use crate::term::Term;
+
+    use super::{Direction, RegexSearch};
+
+    fn search(
+        term: &mut Term<Mock>,
+        pattern: &str,
+        line: usize,
+        col: usize,
+        direction: Direction,
+    ) -> Option<(Point<usize>, Point<usize>)> {
+        term.set_regex_search(Some(RegexSearch::new(pattern).unwrap()));
+        term.search_next(Point::new(line, Column(col)), direction).map(|m| (*m.start(), *m.end()))
+    }
+
+    #[test]
+    fn search_forward_and_backward() {
+        let mut term = mock_term(&["ab ab", "ab", "cd ab"], 0);
+
+        let first = (Point::new(2, Column(0)), Point::new(2, Column(1)));
+        let second = (Point::new(2, Column(3)), Point::new(2, Column(4)));
+        let last = (Point::new(0, Column(3)), Point::new(0, Column(4)));
+
+        assert_eq!(search(&mut term, "ab", 2, 0, Direction::Forward), Some(second));
+        assert_eq!(search(&mut term, "ab", 1, 0, Direction::Backward), Some(second));
+        assert_eq!(search(&mut term, "ab", 0, 4, Direction::Forward), Some(first));
+        assert_eq!(search(&mut term, "ab", 2, 0, Direction::Backward), Some(last));
+        assert_eq!(search(&mut term, "xy", 2, 0, Direction::Forward), None);
+    }
+
+    #[test]
+    fn search_wrapped_line() {
+        let mut term = mock_term(&["xxxx fo", "o"], 0);
+        term.grid_mut()[1][Column(6)].flags.insert(Flags::WRAPLINE);
+
+        let start = Point::new(1, Column(5));
+        let end = Point::new(0, Column(0));
+        assert_eq!(search(&mut term, "foo", 0, 6, Direction::Forward), Some((start, end)));
+        assert_eq!(term.visible_search_matches(), vec![start..=end]);
+    }
+
+    #[test]
+    fn search_history() {
+        let mut term = mock_term(&["error", "ok", "ok", "ok"], 2);
+
+        let start = Point::new(3, Column(0));
+        let end = Point::new(3, Column(4));
+        assert_eq!(search(&mut term, "err.r", 0, 0, Direction::Backward), Some((start, end)));
+        assert_eq!(term.grid().display_offset(), 2);
+        assert_eq!(term.selection_to_string(), Some(String::from("error")));
+    }
+
+    #[test]
+    fn search_smart_case() {
+        let mut term = mock_term(&["Foo foo"], 0);
+
+        let upper = (Point::new(0, Column(0)), Point::new(0, Column(2)));
+        let lower = (Point::new(0, Column(4)), Point::new(0, Column(6)));
+        assert_eq!(search(&mut term, "foo", 0, 6, Direction::Forward), Some(upper));
+        assert_eq!(search(&mut term, "Foo", 0, 0, Direction::Forward), Some(upper));
+        assert_eq!(search(&mut term, "foo", 0, 0, Direction::Forward), Some(lower));
+    }
+
+    #[test]
+    fn search_wide_chars() {
+        let mut term = mock_term(&["a", "b"], 0);
+        term.grid_mut()[1][Column(0)].c = '漢';
+        term.grid_mut()[1][Column(0)].flags.insert(Flags::WIDE_CHAR);
+        term.grid_mut()[1][Column(1)].flags.insert(Flags::WIDE_CHAR_SPACER);
+        term.grid_mut()[1][Column(2)].c = 'x';
+
+        let start = Point::new(1, Column(0));
+        let end = Point::new(1, Column(2));
+        assert_eq!(search(&mut term, "漢x", 0, 0, Direction::Backward), Some((start, end)));
+    }
+
+    #[test]
+    fn render_visible_matches() {
+        let mut term = mock_term(&["ab ab", "ab"], 0);
+        term.set_regex_search(Some(RegexSearch::new("b").unwrap()));
+        term.update_search_matches();
+
+        let config = MockConfig::default();
+        let matches: Vec<_> = term
+            .renderable_cells(&config)
+            .filter(|cell| cell.bg == config.colors.search.matches.background)
+            .map(|cell| (cell.line.0, cell.column.0))
+            .collect();
+        assert_eq!(matches, vec![(0, 1), (0, 4), (1, 1)]);
+    }
+}
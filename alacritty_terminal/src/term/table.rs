@@ -0,0 +1,168 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage for cell attributes which are too large to be stored inside a cell
+//!
+//! Cells are `Copy`, so they only store the index of their entry in a table. Entries which are
+//! not referenced by any cell anymore are removed by sweeping the grid and their slots are
+//! reused by later insertions.
+use std::cmp::max;
+
+/// Minimum number of insertions between two sweeps
+const MIN_SWEEP_INTERVAL: usize = 1024;
+
+/// Ratio between the number of cells in the grid and insertions between two sweeps
+///
+/// Sweeping has to visit every cell, so this bounds the amortized cost of a sweep per insertion.
+const CELLS_PER_INSERTION: usize = 64;
+
+#[derive(Debug)]
+pub struct Table<T> {
+    slots: Vec<Option<T>>,
+
+    /// Indices of empty slots
+    free: Vec<usize>,
+
+    /// Number of insertions since the last sweep
+    insertions: usize,
+}
+
+impl<T> Default for Table<T> {
+    fn default() -> Self {
+        Self { slots: Vec::new(), free: Vec::new(), insertions: 0 }
+    }
+}
+
+impl<T> Table<T> {
+    /// Store a value in the first free slot and return its index
+    pub fn insert(&mut self, value: T) -> usize {
+        self.insertions += 1;
+
+        match self.free.pop() {
+            Some(index) => {
+                self.slots[index] = Some(value);
+                index
+            },
+            None => {
+                self.slots.push(Some(value));
+                self.slots.len() - 1
+            },
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.slots.get(index).and_then(Option::as_ref)
+    }
+
+    /// Number of stored values
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of slots, including empty ones
+    ///
+    /// All indices returned by `insert` are below this.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Check if enough values have been inserted since the last sweep to sweep again
+    ///
+    /// The interval grows with the number of cells which have to be visited and the number of
+    /// values which are still referenced, so sweeping stays cheap on average.
+    pub fn needs_sweep(&self, cells: usize) -> bool {
+        self.insertions >= max(MIN_SWEEP_INTERVAL, max(self.len(), cells / CELLS_PER_INSERTION))
+    }
+
+    /// Remove all values whose index is not marked as referenced
+    ///
+    /// Indices past the end of `referenced` are treated as unreferenced.
+    pub fn sweep(&mut self, referenced: &[bool]) {
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.is_some() && !referenced.get(index).copied().unwrap_or(false) {
+                *slot = None;
+                self.free.push(index);
+            }
+        }
+
+        // Trailing empty slots can be released entirely
+        while let Some(None) = self.slots.last() {
+            self.slots.pop();
+        }
+        let len = self.slots.len();
+        self.free.retain(|index| *index < len);
+
+        // Fill the lowest slots first, so trailing slots can be released by later sweeps
+        self.free.sort_unstable_by(|a, b| b.cmp(a));
+
+        self.insertions = 0;
+    }
+
+    /// Remove all values
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.free.clear();
+        self.insertions = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Table;
+
+    #[test]
+    fn reuse_swept_slots() {
+        let mut table = Table::default();
+        let first = table.insert("first");
+        let second = table.insert("second");
+        let third = table.insert("third");
+
+        let mut referenced = vec![false; table.capacity()];
+        referenced[third] = true;
+        table.sweep(&referenced);
+
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.get(first), None);
+        assert_eq!(table.get(second), None);
+        assert_eq!(table.get(third), Some(&"third"));
+
+        // Freed slots are filled from the lowest index
+        assert_eq!(table.insert("fourth"), first);
+        assert_eq!(table.insert("fifth"), second);
+        assert_eq!(table.capacity(), 3);
+    }
+
+    #[test]
+    fn release_trailing_slots() {
+        let mut table = Table::default();
+        let first = table.insert(1);
+        table.insert(2);
+        table.insert(3);
+
+        let mut referenced = vec![false; table.capacity()];
+        referenced[first] = true;
+        table.sweep(&referenced);
+
+        assert_eq!(table.capacity(), 1);
+        assert_eq!(table.insert(4), 1);
+    }
+}
@@ -0,0 +1,49 @@
+//! Helpers for tests which need a terminal with known content
+
+use std::mem;
+
+use crate::clipboard::Clipboard;
+use crate::config::MockConfig;
+use crate::event::{Event, EventListener};
+use crate::grid::Grid;
+use crate::index::{Column, Line};
+use crate::term::cell::Cell;
+use crate::term::{SizeInfo, Term};
+
+pub struct Mock;
+impl EventListener for Mock {
+    fn send_event(&self, _event: Event) {}
+}
+
+/// Create a terminal seven columns wide, with the last `history` lines in the scrollback
+///
+/// The lines are listed from the top of the scrollback history to the bottom of the screen.
+pub fn mock_term(lines: &[&str], history: usize) -> Term<Mock> {
+    let size = SizeInfo {
+        width: 21.0,
+        height: 51.0,
+        cell_width: 3.0,
+        cell_height: 3.0,
+        padding_x: 0.0,
+        padding_y: 0.0,
+        dpr: 1.0,
+    };
+    let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+
+    let num_lines = lines.len() - history;
+    let mut grid = Grid::new(Line(num_lines), Column(7), history, Cell::default());
+    for _ in 0..history {
+        grid.scroll_up(&(Line(0)..Line(num_lines)), Line(1), &Cell::default());
+    }
+
+    for (index, text) in lines.iter().enumerate() {
+        let row = &mut grid[lines.len() - index - 1];
+        for (col, c) in text.chars().enumerate() {
+            row[Column(col)].c = c;
+        }
+    }
+
+    mem::swap(term.grid_mut(), &mut grid);
+
+    term
+}
@@ -0,0 +1,184 @@
+//! Number of columns occupied by characters and grapheme clusters
+
+use std::cmp::max;
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::config::{AmbiguousWidth, Unicode, UnicodeVersion, WidthOverride};
+
+/// Emoji which were narrow before Unicode 9 gave them the East Asian wide property
+const UNICODE_9_WIDE_EMOJI: &[(char, char)] = &[
+    ('\u{231A}', '\u{231B}'),
+    ('\u{23E9}', '\u{23EC}'),
+    ('\u{23F0}', '\u{23F0}'),
+    ('\u{23F3}', '\u{23F3}'),
+    ('\u{25FD}', '\u{25FE}'),
+    ('\u{2614}', '\u{2615}'),
+    ('\u{2648}', '\u{2653}'),
+    ('\u{267F}', '\u{267F}'),
+    ('\u{2693}', '\u{2693}'),
+    ('\u{26A1}', '\u{26A1}'),
+    ('\u{26AA}', '\u{26AB}'),
+    ('\u{26BD}', '\u{26BE}'),
+    ('\u{26C4}', '\u{26C5}'),
+    ('\u{26CE}', '\u{26CE}'),
+    ('\u{26D4}', '\u{26D4}'),
+    ('\u{26EA}', '\u{26EA}'),
+    ('\u{26F2}', '\u{26F3}'),
+    ('\u{26F5}', '\u{26F5}'),
+    ('\u{26FA}', '\u{26FA}'),
+    ('\u{26FD}', '\u{26FD}'),
+    ('\u{2705}', '\u{2705}'),
+    ('\u{270A}', '\u{270B}'),
+    ('\u{2728}', '\u{2728}'),
+    ('\u{274C}', '\u{274C}'),
+    ('\u{274E}', '\u{274E}'),
+    ('\u{2753}', '\u{2755}'),
+    ('\u{2757}', '\u{2757}'),
+    ('\u{2795}', '\u{2797}'),
+    ('\u{27B0}', '\u{27B0}'),
+    ('\u{27BF}', '\u{27BF}'),
+    ('\u{2B1B}', '\u{2B1C}'),
+    ('\u{2B50}', '\u{2B50}'),
+    ('\u{2B55}', '\u{2B55}'),
+    ('\u{1F004}', '\u{1F004}'),
+    ('\u{1F0CF}', '\u{1F0CF}'),
+    ('\u{1F18E}', '\u{1F18E}'),
+    ('\u{1F191}', '\u{1F19A}'),
+    ('\u{1F300}', '\u{1F64F}'),
+    ('\u{1F680}', '\u{1F6FF}'),
+    ('\u{1F900}', '\u{1F9FF}'),
+];
+
+/// Rules for the number of columns occupied by characters
+///
+/// Every place which lays out characters in the grid has to agree on their width, otherwise the
+/// cursor position of the application and the terminal will drift apart.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WidthPolicy {
+    ambiguous_width: AmbiguousWidth,
+    version: UnicodeVersion,
+    emoji_presentation: bool,
+    overrides: Vec<WidthOverride>,
+}
+
+impl Default for WidthPolicy {
+    fn default() -> Self {
+        WidthPolicy::new(&Unicode::default())
+    }
+}
+
+impl WidthPolicy {
+    pub fn new(config: &Unicode) -> Self {
+        WidthPolicy {
+            ambiguous_width: config.ambiguous_width,
+            version: config.version,
+            emoji_presentation: config.emoji_presentation(),
+            overrides: config.width_overrides.clone(),
+        }
+    }
+
+    /// Number of columns occupied by a character
+    ///
+    /// Returns `None` for control characters.
+    pub fn char_width(&self, c: char) -> Option<usize> {
+        if c.is_control() {
+            return None;
+        }
+
+        // Later overrides take precedence over earlier ones
+        if let Some(width_override) = self.overrides.iter().rev().find(|o| o.contains(c)) {
+            return Some(width_override.width as usize);
+        }
+
+        let width = match self.ambiguous_width {
+            AmbiguousWidth::Narrow => c.width()?,
+            AmbiguousWidth::Wide => c.width_cjk()?,
+        };
+
+        if width == 2 && self.version == UnicodeVersion::Unicode8 && is_unicode_9_wide_emoji(c) {
+            return Some(1);
+        }
+
+        Some(width)
+    }
+
+    /// Number of columns occupied by a grapheme cluster
+    ///
+    /// Clusters are as wide as their first character, unless they are displayed as emoji.
+    pub fn cluster_width(&self, cluster: &str) -> usize {
+        let mut chars = cluster.chars();
+        let width = chars.next().and_then(|c| self.char_width(c)).unwrap_or(0);
+
+        // Flags made of two regional indicators and the emoji presentation selector
+        let is_emoji = |c: char| {
+            ('\u{1F1E6}'..='\u{1F1FF}').contains(&c) || (self.emoji_presentation && c == '\u{FE0F}')
+        };
+
+        if chars.any(is_emoji) {
+            max(width, 2)
+        } else {
+            width
+        }
+    }
+}
+
+fn is_unicode_9_wide_emoji(c: char) -> bool {
+    UNICODE_9_WIDE_EMOJI.iter().any(|&(start, end)| start <= c && c <= end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WidthPolicy;
+
+    use crate::config::{AmbiguousWidth, Unicode, UnicodeVersion, WidthOverride};
+
+    #[test]
+    fn ambiguous_width() {
+        let mut config = Unicode::default();
+        assert_eq!(WidthPolicy::new(&config).char_width('\u{2160}'), Some(1));
+
+        config.ambiguous_width = AmbiguousWidth::Wide;
+        let policy = WidthPolicy::new(&config);
+        assert_eq!(policy.char_width('\u{2160}'), Some(2));
+        assert_eq!(policy.char_width('a'), Some(1));
+        assert_eq!(policy.char_width('\u{7}'), None);
+    }
+
+    #[test]
+    fn unicode_8_emoji() {
+        let mut config = Unicode::default();
+        assert_eq!(WidthPolicy::new(&config).char_width('\u{231A}'), Some(2));
+
+        config.version = UnicodeVersion::Unicode8;
+        let policy = WidthPolicy::new(&config);
+        assert_eq!(policy.char_width('\u{231A}'), Some(1));
+        assert_eq!(policy.char_width('\u{4E00}'), Some(2));
+    }
+
+    #[test]
+    fn width_overrides() {
+        let mut config = Unicode::default();
+        config.width_overrides = vec![
+            WidthOverride { start: '\u{E0A0}', end: '\u{E0D4}', width: 2 },
+            WidthOverride { start: '\u{E0B0}', end: '\u{E0B0}', width: 1 },
+        ];
+        let policy = WidthPolicy::new(&config);
+
+        assert_eq!(policy.char_width('\u{E0A0}'), Some(2));
+        assert_eq!(policy.char_width('\u{E0B0}'), Some(1));
+        assert_eq!(policy.char_width('\u{E0D5}'), Some(1));
+    }
+
+    #[test]
+    fn emoji_presentation() {
+        let mut config = Unicode::default();
+        assert_eq!(WidthPolicy::new(&config).cluster_width("\u{2764}\u{FE0F}"), 2);
+        assert_eq!(WidthPolicy::new(&config).cluster_width("\u{1F1FA}\u{1F1F8}"), 2);
+
+        config.set_emoji_presentation(false);
+        let policy = WidthPolicy::new(&config);
+        assert_eq!(policy.cluster_width("\u{2764}\u{FE0F}"), 1);
+        assert_eq!(policy.cluster_width("\u{1F1FA}\u{1F1F8}"), 2);
+    }
+}
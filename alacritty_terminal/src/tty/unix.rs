@@ -27,7 +27,7 @@ use signal_hook::{self as sighook, iterator::Signals};
 
 use mio::unix::EventedFd;
 use std::ffi::CStr;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io;
 use std::mem::MaybeUninit;
 use std::os::unix::{
@@ -54,6 +54,35 @@ pub fn child_pid() -> pid_t {
     PID.load(Ordering::Relaxed) as pid_t
 }
 
+/// Name of the process running in the foreground of the child's terminal
+///
+/// This relies on procfs, so it is not available on macOS.
+pub fn foreground_process_name() -> Option<String> {
+    #[cfg(not(target_os = "freebsd"))]
+    let proc_prefix = "";
+    #[cfg(target_os = "freebsd")]
+    let proc_prefix = "/compat/linux";
+
+    // The process name in the stat file is followed by the state, parent process, process group,
+    // session, terminal and the terminal's foreground process group
+    let stat = fs::read_to_string(format!("{}/proc/{}/stat", proc_prefix, child_pid())).ok()?;
+    let fields = &stat[stat.rfind(')')? + 1..];
+    let foreground_pgid: pid_t = fields.split_whitespace().nth(5)?.parse().ok()?;
+    if foreground_pgid <= 0 {
+        return None;
+    }
+
+    let name = fs::read_to_string(format!("{}/proc/{}/comm", proc_prefix, foreground_pgid)).ok()?;
+    Some(name.trim_end().to_owned())
+}
+
+/// Network name of the local machine
+pub fn hostname() -> Option<String> {
+    let mut buf = [0; 256];
+    let hostname = nix::unistd::gethostname(&mut buf).ok()?;
+    hostname.to_str().ok().map(String::from)
+}
+
 /// Get raw fds for master/slave ends of a new pty
 fn make_pty(size: winsize) -> (RawFd, RawFd) {
     let mut win_size = size;
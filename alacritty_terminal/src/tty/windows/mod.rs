@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::env;
 use std::io::{self, Read, Write};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::TryRecvError;
@@ -38,6 +39,16 @@ pub fn is_conpty() -> bool {
     IS_CONPTY.load(Ordering::Relaxed)
 }
 
+/// Name of the process running in the foreground of the child's terminal
+pub fn foreground_process_name() -> Option<String> {
+    None
+}
+
+/// Network name of the local machine
+pub fn hostname() -> Option<String> {
+    env::var("COMPUTERNAME").ok()
+}
+
 #[derive(Clone)]
 pub enum PtyHandle<'a> {
     Winpty(winpty::WinptyHandle<'a>),
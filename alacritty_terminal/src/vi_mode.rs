@@ -0,0 +1,403 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Keyboard-driven cursor movement through the grid and scrollback
+//!
+//! The vi mode cursor is stored in buffer coordinates, so it can move into the
+//! scrollback history independently of the terminal's cursor.
+use std::cmp::min;
+
+use serde::Deserialize;
+
+use crate::index::{Column, Point};
+use crate::term::cell::{Cell, Flags, LineLength};
+use crate::term::{Search, Term};
+
+/// Possible vi mode motion movements
+#[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum ViMotion {
+    /// Move up
+    Up,
+    /// Move down
+    Down,
+    /// Move left
+    Left,
+    /// Move right
+    Right,
+    /// Move to start of line
+    First,
+    /// Move to end of line
+    Last,
+    /// Move to the top of the scrollback history
+    Top,
+    /// Move to the bottom of the scrollback history
+    Bottom,
+    /// Move to top of screen
+    High,
+    /// Move to center of screen
+    Middle,
+    /// Move to bottom of screen
+    Low,
+    /// Move to start of semantically separated word
+    SemanticLeft,
+    /// Move to start of next semantically separated word
+    SemanticRight,
+    /// Move to end of semantically separated word
+    SemanticRightEnd,
+    /// Move to opposing bracket
+    Bracket,
+}
+
+/// Cursor tracking vi mode position
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ViModeCursor {
+    /// Position of the cursor in buffer coordinates
+    pub point: Point<usize>,
+}
+
+impl ViModeCursor {
+    pub fn new(point: Point<usize>) -> Self {
+        Self { point }
+    }
+
+    /// Move vi mode cursor
+    #[must_use = "this returns the result of the operation, without modifying the original"]
+    pub fn motion<T>(mut self, term: &Term<T>, motion: ViMotion) -> Self {
+        let grid = term.grid();
+        let num_lines = grid.num_lines().0;
+        let display_offset = grid.display_offset();
+        let last_col = grid.num_cols() - 1;
+
+        match motion {
+            ViMotion::Up => self.point.line = min(self.point.line + 1, topmost_line(term)),
+            ViMotion::Down => self.point.line = self.point.line.saturating_sub(1),
+            ViMotion::Left => {
+                self.point.col = Column(self.point.col.saturating_sub(1));
+                if is_spacer(cell(term, self.point)) {
+                    self.point.col = Column(self.point.col.saturating_sub(1));
+                }
+            },
+            ViMotion::Right => {
+                self.point.col = min(self.point.col + 1, last_col);
+                if is_spacer(cell(term, self.point)) {
+                    self.point.col = min(self.point.col + 1, last_col);
+                }
+            },
+            ViMotion::First => self.point.col = Column(0),
+            ViMotion::Last => {
+                let line_length = grid[self.point.line].line_length();
+                self.point.col = Column(line_length.saturating_sub(1));
+            },
+            ViMotion::Top => self.point = Point::new(topmost_line(term), Column(0)),
+            ViMotion::Bottom => self.point = Point::new(0, Column(0)),
+            ViMotion::High => {
+                self.point = Point::new(display_offset + num_lines - 1, Column(0));
+            },
+            ViMotion::Middle => {
+                let line = display_offset + num_lines - 1 - (num_lines - 1) / 2;
+                self.point = Point::new(line, Column(0));
+            },
+            ViMotion::Low => self.point = Point::new(display_offset, Column(0)),
+            ViMotion::SemanticLeft => self.point = semantic_left(term, self.point),
+            ViMotion::SemanticRight => self.point = semantic_right(term, self.point),
+            ViMotion::SemanticRightEnd => self.point = semantic_right_end(term, self.point),
+            ViMotion::Bracket => {
+                self.point = term.bracket_search(self.point).unwrap_or(self.point);
+            },
+        }
+
+        self
+    }
+}
+
+/// Buffer index of the oldest line which can be reached by the cursor
+pub(crate) fn topmost_line<T>(term: &Term<T>) -> usize {
+    let grid = term.grid();
+    grid.num_lines().0 + grid.scroll_limit() - 1
+}
+
+/// Move to the start of the previous word
+fn semantic_left<T>(term: &Term<T>, start: Point<usize>) -> Point<usize> {
+    let mut point = start;
+
+    // Skip over whitespace preceding the word
+    loop {
+        point = match retreat(term, point) {
+            Some(point) => point,
+            None => return point,
+        };
+
+        if !is_space(cell(term, point)) {
+            break;
+        }
+    }
+
+    // Semantic escape chars are words of their own
+    if is_escape(term, cell(term, point)) {
+        return point;
+    }
+
+    while let Some(prev) = retreat(term, point) {
+        if is_line_end(term, prev) || !is_word(term, cell(term, prev)) {
+            break;
+        }
+
+        point = prev;
+    }
+
+    point
+}
+
+/// Move to the start of the next word
+fn semantic_right<T>(term: &Term<T>, start: Point<usize>) -> Point<usize> {
+    let mut point = start;
+
+    // Skip over the remainder of the current word
+    if is_word(term, cell(term, point)) {
+        while let Some(next) = advance(term, point) {
+            if is_line_end(term, point) || !is_word(term, cell(term, next)) {
+                break;
+            }
+
+            point = next;
+        }
+    }
+
+    // Stop at the first character after the whitespace
+    while let Some(next) = advance(term, point) {
+        point = next;
+
+        if !is_space(cell(term, point)) {
+            break;
+        }
+    }
+
+    point
+}
+
+/// Move to the end of the next word
+fn semantic_right_end<T>(term: &Term<T>, start: Point<usize>) -> Point<usize> {
+    let mut point = start;
+
+    // Skip over whitespace following the current position
+    loop {
+        point = match advance(term, point) {
+            Some(point) => point,
+            None => return point,
+        };
+
+        if !is_space(cell(term, point)) {
+            break;
+        }
+    }
+
+    // Semantic escape chars are words of their own
+    if is_escape(term, cell(term, point)) {
+        return point;
+    }
+
+    while let Some(next) = advance(term, point) {
+        if is_line_end(term, point) || !is_word(term, cell(term, next)) {
+            break;
+        }
+
+        point = next;
+    }
+
+    point
+}
+
+/// Next cell in reading order, skipping wide char spacers
+fn advance<T>(term: &Term<T>, mut point: Point<usize>) -> Option<Point<usize>> {
+    let last_col = term.grid().num_cols() - 1;
+
+    loop {
+        if point.col < last_col {
+            point.col += 1;
+        } else if point.line > 0 {
+            point = Point::new(point.line - 1, Column(0));
+        } else {
+            return None;
+        }
+
+        if !is_spacer(cell(term, point)) {
+            return Some(point);
+        }
+    }
+}
+
+/// Previous cell in reading order, skipping wide char spacers
+fn retreat<T>(term: &Term<T>, mut point: Point<usize>) -> Option<Point<usize>> {
+    let last_col = term.grid().num_cols() - 1;
+
+    loop {
+        if point.col > Column(0) {
+            point.col -= 1;
+        } else if point.line < topmost_line(term) {
+            point = Point::new(point.line + 1, last_col);
+        } else {
+            return None;
+        }
+
+        if !is_spacer(cell(term, point)) {
+            return Some(point);
+        }
+    }
+}
+
+#[inline]
+fn cell<T>(term: &Term<T>, point: Point<usize>) -> &Cell {
+    &term.grid()[point.line][point.col]
+}
+
+/// Check if the point is at the end of a line which is not wrapped
+#[inline]
+fn is_line_end<T>(term: &Term<T>, point: Point<usize>) -> bool {
+    point.col == term.grid().num_cols() - 1 && !cell(term, point).flags.contains(Flags::WRAPLINE)
+}
+
+#[inline]
+fn is_spacer(cell: &Cell) -> bool {
+    cell.flags.contains(Flags::WIDE_CHAR_SPACER)
+}
+
+#[inline]
+fn is_space(cell: &Cell) -> bool {
+    cell.c == ' ' || cell.c == '\t'
+}
+
+#[inline]
+fn is_escape<T>(term: &Term<T>, cell: &Cell) -> bool {
+    term.semantic_escape_chars().contains(cell.c)
+}
+
+#[inline]
+fn is_word<T>(term: &Term<T>, cell: &Cell) -> bool {
+    !is_space(cell) && !is_escape(term, cell)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::Scroll;
+    use crate::index::{Column, Point};
+    use crate::term::cell::Flags;
+    use crate::term::test::{mock_term, Mock};
+    use crate::term::Term;
+
+    use super::{ViModeCursor, ViMotion};
+
+    fn motion(term: &Term<Mock>, line: usize, col: usize, motion: ViMotion) -> Point<usize> {
+        ViModeCursor::new(Point::new(line, Column(col))).motion(term, motion).point
+    }
+
+    #[test]
+    fn motion_simple() {
+        let term = mock_term(&["aaaaaaa", "bbbbbbb", "ccccccc"], 0);
+
+        assert_eq!(motion(&term, 1, 3, ViMotion::Left), Point::new(1, Column(2)));
+        assert_eq!(motion(&term, 1, 3, ViMotion::Right), Point::new(1, Column(4)));
+        assert_eq!(motion(&term, 1, 3, ViMotion::Up), Point::new(2, Column(3)));
+        assert_eq!(motion(&term, 1, 3, ViMotion::Down), Point::new(0, Column(3)));
+
+        // Movement is clamped to the grid
+        assert_eq!(motion(&term, 1, 0, ViMotion::Left), Point::new(1, Column(0)));
+        assert_eq!(motion(&term, 1, 6, ViMotion::Right), Point::new(1, Column(6)));
+        assert_eq!(motion(&term, 2, 3, ViMotion::Up), Point::new(2, Column(3)));
+        assert_eq!(motion(&term, 0, 3, ViMotion::Down), Point::new(0, Column(3)));
+    }
+
+    #[test]
+    fn motion_line_bounds() {
+        let term = mock_term(&["aaa", "bbbbb"], 0);
+
+        assert_eq!(motion(&term, 1, 2, ViMotion::First), Point::new(1, Column(0)));
+        assert_eq!(motion(&term, 1, 0, ViMotion::Last), Point::new(1, Column(2)));
+        assert_eq!(motion(&term, 0, 0, ViMotion::Last), Point::new(0, Column(4)));
+    }
+
+    #[test]
+    fn motion_history() {
+        let mut term = mock_term(&["1", "2", "3", "4", "5"], 2);
+
+        assert_eq!(motion(&term, 0, 3, ViMotion::Top), Point::new(4, Column(0)));
+        assert_eq!(motion(&term, 4, 3, ViMotion::Bottom), Point::new(0, Column(0)));
+        assert_eq!(motion(&term, 4, 0, ViMotion::Up), Point::new(4, Column(0)));
+
+        // Screen motions follow the display offset
+        assert_eq!(motion(&term, 4, 0, ViMotion::High), Point::new(2, Column(0)));
+        assert_eq!(motion(&term, 4, 0, ViMotion::Middle), Point::new(1, Column(0)));
+        assert_eq!(motion(&term, 4, 0, ViMotion::Low), Point::new(0, Column(0)));
+
+        term.scroll_display(Scroll::Top);
+        assert_eq!(motion(&term, 0, 0, ViMotion::High), Point::new(4, Column(0)));
+        assert_eq!(motion(&term, 0, 0, ViMotion::Middle), Point::new(3, Column(0)));
+        assert_eq!(motion(&term, 0, 0, ViMotion::Low), Point::new(2, Column(0)));
+    }
+
+    #[test]
+    fn motion_semantic() {
+        let term = mock_term(&["ab (cd)", "  ef   ", "gh"], 0);
+
+        assert_eq!(motion(&term, 2, 0, ViMotion::SemanticRight), Point::new(2, Column(3)));
+        assert_eq!(motion(&term, 2, 3, ViMotion::SemanticRight), Point::new(2, Column(4)));
+        assert_eq!(motion(&term, 2, 4, ViMotion::SemanticRight), Point::new(2, Column(6)));
+        assert_eq!(motion(&term, 2, 6, ViMotion::SemanticRight), Point::new(1, Column(2)));
+        assert_eq!(motion(&term, 1, 2, ViMotion::SemanticRight), Point::new(0, Column(0)));
+
+        assert_eq!(motion(&term, 0, 0, ViMotion::SemanticLeft), Point::new(1, Column(2)));
+        assert_eq!(motion(&term, 1, 2, ViMotion::SemanticLeft), Point::new(2, Column(6)));
+        assert_eq!(motion(&term, 2, 6, ViMotion::SemanticLeft), Point::new(2, Column(4)));
+        assert_eq!(motion(&term, 2, 5, ViMotion::SemanticLeft), Point::new(2, Column(4)));
+        assert_eq!(motion(&term, 2, 4, ViMotion::SemanticLeft), Point::new(2, Column(3)));
+        assert_eq!(motion(&term, 2, 3, ViMotion::SemanticLeft), Point::new(2, Column(0)));
+
+        assert_eq!(motion(&term, 2, 0, ViMotion::SemanticRightEnd), Point::new(2, Column(1)));
+        assert_eq!(motion(&term, 2, 1, ViMotion::SemanticRightEnd), Point::new(2, Column(3)));
+        assert_eq!(motion(&term, 2, 4, ViMotion::SemanticRightEnd), Point::new(2, Column(5)));
+        assert_eq!(motion(&term, 2, 6, ViMotion::SemanticRightEnd), Point::new(1, Column(3)));
+    }
+
+    #[test]
+    fn motion_semantic_wrapped_line() {
+        let mut term = mock_term(&["aaaaaaa", "aa bb"], 0);
+        term.grid_mut()[1][Column(6)].flags.insert(Flags::WRAPLINE);
+
+        assert_eq!(motion(&term, 1, 0, ViMotion::SemanticRight), Point::new(0, Column(3)));
+        assert_eq!(motion(&term, 0, 1, ViMotion::SemanticLeft), Point::new(1, Column(0)));
+        assert_eq!(motion(&term, 1, 0, ViMotion::SemanticRightEnd), Point::new(0, Column(1)));
+    }
+
+    #[test]
+    fn motion_bracket() {
+        let term = mock_term(&["(a [b])"], 0);
+
+        assert_eq!(motion(&term, 0, 0, ViMotion::Bracket), Point::new(0, Column(6)));
+        assert_eq!(motion(&term, 0, 6, ViMotion::Bracket), Point::new(0, Column(0)));
+        assert_eq!(motion(&term, 0, 3, ViMotion::Bracket), Point::new(0, Column(5)));
+
+        // Nothing happens without a bracket below the cursor
+        assert_eq!(motion(&term, 0, 1, ViMotion::Bracket), Point::new(0, Column(1)));
+    }
+
+    #[test]
+    fn motion_wide_chars() {
+        let mut term = mock_term(&["a"], 0);
+        term.grid_mut()[0][Column(1)].c = '汉';
+        term.grid_mut()[0][Column(1)].flags.insert(Flags::WIDE_CHAR);
+        term.grid_mut()[0][Column(2)].flags.insert(Flags::WIDE_CHAR_SPACER);
+
+        assert_eq!(motion(&term, 0, 1, ViMotion::Right), Point::new(0, Column(3)));
+        assert_eq!(motion(&term, 0, 3, ViMotion::Left), Point::new(0, Column(1)));
+    }
+}
@@ -62,6 +62,11 @@ ref_tests! {
     insert_blank_reset
     erase_chars_reset
     scroll_up_reset
+    selective_erasure_line
+    selective_erasure_screen
+    erase_protected
+    decslrm_insert_delete
+    decslrm_scroll
 }
 
 fn read_u8<P>(path: P) -> Vec<u8>